@@ -4,11 +4,15 @@ use std::io::ErrorKind as IoErrorKind;
 use std::path::PathBuf;
 
 use async_fs as fs;
+use blocking::unblock;
 use bstr::{BString, ByteSlice};
 use futures_lite::prelude::*;
 use mlua::prelude::*;
 
-use lune_utils::TableBuilder;
+use lune_utils::{
+    TableBuilder,
+    fs::{encoding_for_label, get_lua_filesystem},
+};
 
 mod copy;
 mod metadata;
@@ -38,6 +42,8 @@ pub fn typedefs() -> String {
 pub fn module(lua: Lua) -> LuaResult<LuaTable> {
     TableBuilder::new(lua)?
         .with_async_function("readFile", fs_read_file)?
+        .with_async_function("readFileBuffer", fs_read_file_buffer)?
+        .with_async_function("readTextFile", fs_read_text_file)?
         .with_async_function("readDir", fs_read_dir)?
         .with_async_function("writeFile", fs_write_file)?
         .with_async_function("writeDir", fs_write_dir)?
@@ -57,6 +63,37 @@ async fn fs_read_file(lua: Lua, path: String) -> LuaResult<LuaString> {
     lua.create_string(bytes)
 }
 
+async fn fs_read_file_buffer(lua: Lua, path: String) -> LuaResult<LuaBuffer> {
+    let fs = get_lua_filesystem(&lua);
+    let bytes = unblock(move || fs.read(path.as_ref()))
+        .await
+        .into_lua_err()?;
+
+    lua.create_buffer(bytes)
+}
+
+async fn fs_read_text_file(
+    lua: Lua,
+    (path, encoding): (String, Option<String>),
+) -> LuaResult<LuaString> {
+    let encoding = match encoding {
+        None => None,
+        Some(label) => Some(encoding_for_label(&label).ok_or_else(|| {
+            LuaError::runtime(format!("Unknown or unsupported encoding '{label}'"))
+        })?),
+    };
+
+    let fs = get_lua_filesystem(&lua);
+    let text = unblock(move || match encoding {
+        None => fs.read_to_string(path.as_ref()),
+        Some(encoding) => fs.read_to_string_with_encoding(path.as_ref(), encoding),
+    })
+    .await
+    .into_lua_err()?;
+
+    lua.create_string(text)
+}
+
 async fn fs_read_dir(_: Lua, path: String) -> LuaResult<Vec<String>> {
     let mut dir_strings = Vec::new();
     let mut dir = fs::read_dir(&path).await.into_lua_err()?;
@@ -12,7 +12,7 @@ use mlua_luau_scheduler::Functions;
 use lune_utils::{
     TableBuilder,
     path::get_current_dir,
-    process::{ProcessArgs, ProcessEnv},
+    process::{ProcessArgs, ProcessBuildConfig, ProcessEnv, ProcessProgramName},
 };
 
 mod create;
@@ -70,6 +70,26 @@ pub fn module(lua: Lua) -> LuaResult<LuaTable> {
 
     process_args.set_readonly(true);
 
+    let process_program_name = match lua.app_data_ref::<ProcessProgramName>() {
+        Some(program_name) => program_name.name().to_string(),
+        None => ProcessProgramName::current().name().to_string(),
+    };
+
+    // The build config is only given to standalone binaries built with one,
+    // and was already validated during the build, so failing here is unlikely
+    let process_build_config = match lua
+        .app_data_ref::<ProcessBuildConfig>()
+        .as_deref()
+        .and_then(ProcessBuildConfig::json)
+    {
+        Some(json) => {
+            let value = serde_json::from_str::<serde_json::Value>(json)
+                .map_err(|e| LuaError::runtime(format!("Invalid build config: {e}")))?;
+            lua.to_value(&value)?
+        }
+        None => LuaValue::Nil,
+    };
+
     // Create our process exit function, the scheduler crate provides this
     let fns = Functions::new(lua.clone())?;
     let process_exit = fns.exit;
@@ -80,8 +100,10 @@ pub fn module(lua: Lua) -> LuaResult<LuaTable> {
         .with_value("arch", arch)?
         .with_value("endianness", endianness)?
         .with_value("args", process_args)?
+        .with_value("programName", process_program_name)?
         .with_value("cwd", cwd_str)?
         .with_value("env", process_env)?
+        .with_value("buildConfig", process_build_config)?
         .with_value("exit", process_exit)?
         .with_async_function("exec", process_exec)?
         .with_function("create", process_create)?
@@ -0,0 +1,63 @@
+use std::{
+    borrow::Cow,
+    path::{Component, Path, PathBuf},
+};
+
+use tauri::utils::assets::{AssetKey, AssetsIter, CspHash};
+
+/**
+    Frontend assets that are read from disk on every request, instead of
+    being embedded when compiling, so that changes show up after reloading.
+
+    The path is either a single HTML file, served as `index.html`,
+    or a directory, which is served the same way as `frontendDist`.
+*/
+pub(crate) struct DiskAssets {
+    path: PathBuf,
+}
+
+impl DiskAssets {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /**
+        Returns the file on disk for the given asset key, if it is inside of the frontend.
+    */
+    fn file_for(&self, key: &AssetKey) -> Option<PathBuf> {
+        let key = key.as_ref().trim_start_matches('/');
+        if self.path.is_file() {
+            return matches!(key, "" | "index.html").then(|| self.path.clone());
+        }
+
+        let relative = Path::new(key);
+        if relative
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+        {
+            return None;
+        }
+        let path = self.path.join(relative);
+        if path.is_dir() {
+            Some(path.join("index.html"))
+        } else {
+            Some(path)
+        }
+    }
+}
+
+impl<R: tauri::Runtime> tauri::Assets<R> for DiskAssets {
+    fn get(&self, key: &AssetKey) -> Option<Cow<'_, [u8]>> {
+        let path = self.file_for(key)?;
+        std::fs::read(path).ok().map(Cow::Owned)
+    }
+
+    fn iter(&self) -> Box<AssetsIter<'_>> {
+        // Assets are only ever read on request, and never listed up front
+        Box::new(std::iter::empty())
+    }
+
+    fn csp_hashes(&self, _html_path: &AssetKey) -> Box<dyn Iterator<Item = CspHash<'_>> + '_> {
+        Box::new(std::iter::empty())
+    }
+}
@@ -1,23 +1,150 @@
+use lune_utils::tauri::{ConfigIcon, ConfigOptions, check_config_keys, check_identifier};
 use mlua::prelude::*;
-use std::sync::{Arc, Mutex};
-use tauri::{Emitter, Listener};
+use std::{
+    cell::RefCell,
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tauri::{Emitter, Listener, Manager, utils::config::Csp};
+
+mod assets;
+mod payload;
+mod stream;
+mod window;
+
+use self::assets::DiskAssets;
+use self::payload::{PayloadShape, payload_kind};
+use self::stream::{DEFAULT_STREAM_TIMEOUT, EmitStream};
+use self::window::LuaWindow;
 
 const TYPEDEFS: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/types.d.luau"));
 
+/// The content security policy used for apps that do not specify one, allowing
+/// only local content, the Tauri IPC bridge, and inline styles to be loaded
+pub const DEFAULT_CSP: &str = "default-src 'self' ipc: http://ipc.localhost; img-src 'self' asset: http://asset.localhost data:; style-src 'self' 'unsafe-inline'";
+
+/// All known plugins, and whether they were enabled for this build
+const PLUGINS: &[(&str, bool)] = &[
+    ("dialog", cfg!(feature = "plugin-dialog")),
+    ("clipboard", cfg!(feature = "plugin-clipboard")),
+    ("notification", cfg!(feature = "plugin-notification")),
+    ("global-shortcut", cfg!(feature = "plugin-global-shortcut")),
+];
+
+fn enabled_plugins() -> Vec<&'static str> {
+    PLUGINS
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+/**
+    Converts a Lua value to JSON, to be emitted as the payload of an event.
+
+    Tables where all keys are positive integers become arrays, with any gaps
+    filled in using `null`, unless they are too sparse, and all other tables
+    become objects with their keys sorted, so that the same table is always sent
+    the same way. Empty tables become empty objects, `nil` becomes `null`, and
+    values that can not be represented in JSON, such as functions, are an error.
+*/
+pub(crate) fn payload_to_json(payload: &LuaValue) -> LuaResult<serde_json::Value> {
+    let payload = payload
+        .to_serializable()
+        .detect_mixed_tables(true)
+        .sort_keys(true);
+    serde_json::to_value(payload).map_err(LuaError::external)
+}
+
 struct UnsafeLua(Lua);
 unsafe impl Send for UnsafeLua {}
 unsafe impl Sync for UnsafeLua {}
 
 #[derive(Clone)]
-struct LuaAppHandle(tauri::AppHandle);
+struct LuaAppHandle {
+    handle: tauri::AppHandle,
+    config: Option<Arc<TauriConfig>>,
+}
 
 impl LuaUserData for LuaAppHandle {
     fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
         methods.add_method("emit", |_, this, (event, payload): (String, LuaValue)| {
-            this.0
-                .emit(&event, payload)
+            this.handle
+                .emit(&event, payload_to_json(&payload)?)
                 .map_err(|e| LuaError::external(e))
         });
+
+        // app:unlisten(handle) -> boolean, usable from within listener callbacks
+        methods.add_method(
+            "unlisten",
+            |_, _, handle: LuaUserDataRef<ListenerHandle>| Ok(handle.remove()),
+        );
+
+        // app:window(label?) -> Window, defaulting to the main window
+        methods.add_method("window", |_, this, label: Option<String>| {
+            LuaWindow::get(&this.handle, label)
+        });
+
+        // app:get_window(label) -> Window?, same as app:window but returns nil for unknown labels
+        methods.add_method("get_window", |_, this, label: String| {
+            Ok(LuaWindow::find(&this.handle, &label))
+        });
+
+        // app:emit_stream(event, payload, on_chunk, on_done, timeout?) -> id
+        methods.add_method(
+            "emit_stream",
+            |lua,
+             this,
+             (event, payload, on_chunk, on_done, timeout): (
+                String,
+                LuaValue,
+                LuaFunction,
+                LuaFunction,
+                Option<f64>,
+            )| {
+                let timeout =
+                    Duration::try_from_secs_f64(timeout.unwrap_or(DEFAULT_STREAM_TIMEOUT))
+                        .ok()
+                        .filter(|timeout| !timeout.is_zero())
+                        .ok_or_else(|| {
+                            LuaError::runtime("Expected timeout to be a positive number")
+                        })?;
+                EmitStream::start(
+                    lua,
+                    this.handle.clone(),
+                    &event,
+                    &payload,
+                    on_chunk,
+                    on_done,
+                    timeout,
+                )
+            },
+        );
+
+        // Prefer the config given to tauri.new, falling back to the
+        // config that the app was built with for the legacy API
+        methods.add_method("name", |_, this, ()| {
+            Ok(match &this.config {
+                Some(config) => config.name.clone(),
+                None => this.handle.package_info().name.clone(),
+            })
+        });
+        methods.add_method("version", |_, this, ()| {
+            Ok(match &this.config {
+                Some(config) => config.version.clone(),
+                None => this.handle.package_info().version.to_string(),
+            })
+        });
+        methods.add_method("identifier", |_, this, ()| {
+            Ok(match &this.config {
+                Some(config) => config.identifier.clone(),
+                None => this.handle.config().identifier.clone(),
+            })
+        });
     }
 }
 
@@ -25,7 +152,447 @@ impl LuaUserData for LuaAppHandle {
 #[derive(Clone)]
 struct TauriApp {
     config: Arc<TauriConfig>,
-    listeners: Arc<Mutex<Vec<(String, Arc<LuaRegistryKey>)>>>,
+    listeners: Listeners,
+}
+
+/// A listener registered using app:listen(), app:listen_on(), app:once(), or the legacy tauri.listen()
+struct AppListener {
+    id: u32,
+    event: String,
+    /// The label of the window to listen on, or `None` to listen for events from anywhere
+    window: Option<String>,
+    callback: Arc<LuaRegistryKey>,
+    /// Whether this listener removes itself after its first event
+    once: bool,
+    /// The payload that the callback expects, if it was given to app:listen()
+    shape: Option<Arc<PayloadShape>>,
+    /// The id given to this listener by Tauri, once registered with a running app
+    event_id: Option<tauri::EventId>,
+}
+
+/// A listener registered using app:on_window_event(), for the lifecycle events of a single window
+struct WindowListener {
+    id: u32,
+    label: String,
+    callback: Arc<LuaRegistryKey>,
+}
+
+/// Everything needed to dispatch events to listeners, available once an app is running
+#[derive(Clone)]
+struct Dispatcher {
+    handle: tauri::AppHandle,
+    lua: Arc<UnsafeLua>,
+    config: Option<Arc<TauriConfig>>,
+    log_level: LogLevel,
+}
+
+impl Dispatcher {
+    /**
+        Registers the given listener with the running app, returning the id given to it by Tauri.
+
+        Listeners that should only fire once are removed from the given
+        listeners before their callback runs, same as Tauri's `once_any`.
+    */
+    fn register(
+        &self,
+        listener: &AppListener,
+        listeners: &Listeners,
+    ) -> Result<tauri::EventId, String> {
+        let dispatcher = self.clone();
+        let event_name = listener.event.clone();
+        let registry_key = listener.callback.clone();
+        let once = listener.once;
+        let shape = listener.shape.clone();
+        let listener_handle = ListenerHandle {
+            id: listener.id,
+            listeners: listeners.clone(),
+        };
+        let fired = Arc::new(AtomicBool::new(false));
+
+        let handler = move |event: tauri::Event| {
+            // Events may arrive again before the main thread gets to remove the listener
+            if once && fired.swap(true, Ordering::SeqCst) {
+                return;
+            }
+            let payload = event.payload().to_string();
+            let dispatcher_inner = dispatcher.clone();
+            let event_name = event_name.clone();
+            let registry_key = registry_key.clone();
+            let shape = shape.clone();
+            let listener_handle = listener_handle.clone();
+
+            let _ = dispatcher.handle.run_on_main_thread(move || {
+                let dispatcher = dispatcher_inner;
+                if once {
+                    listener_handle.remove();
+                }
+                let lua = &dispatcher.lua.0;
+                if dispatcher.log_level >= LogLevel::Debug {
+                    eprintln!("[tauri] dispatching event '{event_name}'");
+                }
+                if let Ok(func) = lua.registry_value::<LuaFunction>(&*registry_key) {
+                    let lua_app = LuaAppHandle {
+                        handle: dispatcher.handle.clone(),
+                        config: dispatcher.config.clone(),
+                    };
+                    // Payloads that are not valid JSON are passed on as raw strings, which
+                    // callbacks can tell apart from JSON strings using the payload kind
+                    let parsed = serde_json::from_str::<serde_json::Value>(&payload).ok();
+                    let kind = payload_kind(parsed.as_ref());
+                    let arg = match &parsed {
+                        Some(val) => lua.to_value(val).unwrap_or(LuaValue::Nil),
+                        None => LuaValue::String(lua.create_string(&payload).unwrap()),
+                    };
+
+                    // Info about the event is given last, so that existing callbacks
+                    // taking only the payload and app handle keep working as before
+                    let info = event_info(lua, &event_name, &arg, kind, listener_handle);
+
+                    // Payloads that do not have the expected shape never reach the callback
+                    if let Some(shape) = &shape
+                        && let Err(mismatch) = shape.check(parsed.as_ref())
+                    {
+                        let result = match &shape.on_mismatch {
+                            Some(handler) => {
+                                lua.registry_value::<LuaFunction>(handler)
+                                    .and_then(|handler| {
+                                        let mismatch = mismatch.to_table(lua, &payload)?;
+                                        handler.call::<()>((mismatch, lua_app, info?))
+                                    })
+                            }
+                            None => {
+                                if dispatcher.log_level >= LogLevel::Warn {
+                                    eprintln!("[tauri] ignored event '{event_name}', {mismatch}");
+                                }
+                                Ok(())
+                            }
+                        };
+                        if let Err(e) = result {
+                            eprintln!(
+                                "[tauri] error in payload mismatch handler for '{event_name}': {e}"
+                            );
+                        }
+                        return;
+                    }
+
+                    let result = info.and_then(|info| func.call::<()>((arg, lua_app, info)));
+                    if let Err(e) = result {
+                        eprintln!("[tauri] error in listener for '{event_name}': {e}");
+                    }
+                }
+            });
+        };
+
+        match &listener.window {
+            None => Ok(self.handle.listen_any(listener.event.clone(), handler)),
+            Some(label) => {
+                let window = self.handle.get_webview_window(label).ok_or_else(|| {
+                    format!(
+                        "Failed to listen for '{}', no window with label '{label}' exists",
+                        listener.event
+                    )
+                })?;
+                Ok(window.listen(listener.event.clone(), handler))
+            }
+        }
+    }
+}
+
+/**
+    Creates the table describing an event, given to listener callbacks as their
+    third argument, so that callbacks shared by several events can tell them apart.
+*/
+fn event_info(
+    lua: &Lua,
+    name: &str,
+    payload: &LuaValue,
+    payload_kind: &str,
+    listener: ListenerHandle,
+) -> LuaResult<LuaTable> {
+    let info = lua.create_table()?;
+    info.set("name", name)?;
+    info.set("id", listener.id)?;
+    info.set("payload", payload)?;
+    info.set("payload_kind", payload_kind)?;
+    info.set("listener", listener)?;
+    Ok(info)
+}
+
+/**
+    Creates the table describing a window event, given to window event listeners as their first
+    argument, or returns `None` for events that are not passed on, such as drag and drop.
+*/
+fn window_event_info(
+    lua: &Lua,
+    label: &str,
+    event: &tauri::WindowEvent,
+) -> LuaResult<Option<LuaTable>> {
+    let info = lua.create_table()?;
+    info.set("label", label)?;
+    let kind = match event {
+        tauri::WindowEvent::CloseRequested { .. } => "close-requested",
+        tauri::WindowEvent::Destroyed => "destroyed",
+        tauri::WindowEvent::Focused(focused) => {
+            info.set("focused", *focused)?;
+            "focused"
+        }
+        tauri::WindowEvent::Resized(size) => {
+            info.set("width", size.width)?;
+            info.set("height", size.height)?;
+            "resized"
+        }
+        tauri::WindowEvent::Moved(position) => {
+            info.set("x", position.x)?;
+            info.set("y", position.y)?;
+            "moved"
+        }
+        tauri::WindowEvent::ScaleFactorChanged {
+            scale_factor,
+            new_inner_size,
+            ..
+        } => {
+            info.set("scale_factor", *scale_factor)?;
+            info.set("width", new_inner_size.width)?;
+            info.set("height", new_inner_size.height)?;
+            "scale-factor-changed"
+        }
+        _ => return Ok(None),
+    };
+    info.set("kind", kind)?;
+    Ok(Some(info))
+}
+
+/// An opaque handle to a listener, returned by app:listen() and given to unlisten
+#[derive(Clone)]
+struct ListenerHandle {
+    id: u32,
+    listeners: Listeners,
+}
+
+impl ListenerHandle {
+    fn remove(&self) -> bool {
+        self.listeners.remove(self.id)
+    }
+}
+
+impl LuaUserData for ListenerHandle {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_meta_method(LuaMetaMethod::ToString, |_, this, ()| {
+            Ok(format!("ListenerHandle({})", this.id))
+        });
+    }
+}
+
+/// Listeners shared by the methods of a TauriApp, or by the legacy top-level functions
+#[derive(Clone, Default)]
+struct Listeners(Arc<Mutex<ListenersInner>>);
+
+#[derive(Default)]
+struct ListenersInner {
+    next_id: u32,
+    list: Vec<AppListener>,
+    window_list: Vec<WindowListener>,
+    dispatcher: Option<Dispatcher>,
+}
+
+impl Listeners {
+    /**
+        Adds a listener, returning a handle for use with unlisten.
+
+        Listeners added before the app runs are registered once it starts,
+        listeners added while it is running are registered immediately.
+    */
+    fn add(
+        &self,
+        lua: &Lua,
+        event: String,
+        window: Option<String>,
+        callback: LuaFunction,
+    ) -> LuaResult<ListenerHandle> {
+        self.insert(lua, event, window, callback, None, false)
+    }
+
+    /**
+        Adds a listener that is only called for payloads with the given shape,
+        returning a handle for use with unlisten.
+    */
+    fn add_with_shape(
+        &self,
+        lua: &Lua,
+        event: String,
+        callback: LuaFunction,
+        shape: PayloadShape,
+    ) -> LuaResult<ListenerHandle> {
+        self.insert(lua, event, None, callback, Some(Arc::new(shape)), false)
+    }
+
+    /**
+        Adds a listener that removes itself after its first event, returning a handle
+        for use with unlisten, which can remove it before the event ever happens.
+    */
+    fn add_once(
+        &self,
+        lua: &Lua,
+        event: String,
+        callback: LuaFunction,
+    ) -> LuaResult<ListenerHandle> {
+        self.insert(lua, event, None, callback, None, true)
+    }
+
+    fn insert(
+        &self,
+        lua: &Lua,
+        event: String,
+        window: Option<String>,
+        callback: LuaFunction,
+        shape: Option<Arc<PayloadShape>>,
+        once: bool,
+    ) -> LuaResult<ListenerHandle> {
+        let callback = Arc::new(lua.create_registry_value(callback)?);
+        let mut inner = self.0.lock().unwrap();
+        inner.next_id += 1;
+        let mut listener = AppListener {
+            id: inner.next_id,
+            event,
+            window,
+            callback,
+            once,
+            shape,
+            event_id: None,
+        };
+        if let Some(dispatcher) = &inner.dispatcher {
+            let event_id = dispatcher
+                .register(&listener, self)
+                .map_err(LuaError::runtime)?;
+            listener.event_id = Some(event_id);
+        }
+        let id = listener.id;
+        inner.list.push(listener);
+        Ok(ListenerHandle {
+            id,
+            listeners: self.clone(),
+        })
+    }
+
+    /**
+        Adds a listener for the lifecycle events of the window with the given label,
+        returning a handle for use with unlisten. The window does not need to exist yet.
+    */
+    fn add_window(
+        &self,
+        lua: &Lua,
+        label: String,
+        callback: LuaFunction,
+    ) -> LuaResult<ListenerHandle> {
+        let callback = Arc::new(lua.create_registry_value(callback)?);
+        let mut inner = self.0.lock().unwrap();
+        inner.next_id += 1;
+        let id = inner.next_id;
+        inner.window_list.push(WindowListener {
+            id,
+            label,
+            callback,
+        });
+        Ok(ListenerHandle {
+            id,
+            listeners: self.clone(),
+        })
+    }
+
+    /**
+        Calls the window listeners for the given window with an event.
+
+        Tauri gives window events on the main thread, so callbacks are called right away,
+        instead of being queued like other events, which lets a callback returning `false`
+        for a `close-requested` event prevent the window from closing.
+    */
+    fn dispatch_window_event(&self, window: &tauri::Window, event: &tauri::WindowEvent) {
+        // Callbacks may add or remove listeners, so they must not be called while locked
+        let (dispatcher, callbacks) = {
+            let inner = self.0.lock().unwrap();
+            let Some(dispatcher) = inner.dispatcher.clone() else {
+                return;
+            };
+            let callbacks = inner
+                .window_list
+                .iter()
+                .filter(|listener| listener.label == window.label())
+                .map(|listener| listener.callback.clone())
+                .collect::<Vec<_>>();
+            (dispatcher, callbacks)
+        };
+        if callbacks.is_empty() {
+            return;
+        }
+
+        let lua = &dispatcher.lua.0;
+        let label = window.label();
+        let info = match window_event_info(lua, label, event) {
+            Ok(Some(info)) => info,
+            Ok(None) => return,
+            Err(e) => {
+                eprintln!("[tauri] error in window event listener for '{label}': {e}");
+                return;
+            }
+        };
+        if dispatcher.log_level >= LogLevel::Debug {
+            eprintln!("[tauri] dispatching window event for '{label}'");
+        }
+
+        let mut prevent_close = false;
+        for callback in callbacks {
+            let lua_app = LuaAppHandle {
+                handle: dispatcher.handle.clone(),
+                config: dispatcher.config.clone(),
+            };
+            let result = lua
+                .registry_value::<LuaFunction>(&*callback)
+                .and_then(|func| func.call::<LuaValue>((info.clone(), lua_app)));
+            match result {
+                Ok(LuaValue::Boolean(false)) => prevent_close = true,
+                Ok(_) => {}
+                Err(e) => eprintln!("[tauri] error in window event listener for '{label}': {e}"),
+            }
+        }
+        if prevent_close && let tauri::WindowEvent::CloseRequested { api, .. } = event {
+            api.prevent_close();
+        }
+    }
+
+    /**
+        Removes the listener with the given id, returning `false` if no such listener exists.
+    */
+    fn remove(&self, id: u32) -> bool {
+        let mut inner = self.0.lock().unwrap();
+        if let Some(index) = inner
+            .window_list
+            .iter()
+            .position(|listener| listener.id == id)
+        {
+            inner.window_list.remove(index);
+            return true;
+        }
+        let Some(index) = inner.list.iter().position(|listener| listener.id == id) else {
+            return false;
+        };
+        let listener = inner.list.remove(index);
+        if let (Some(dispatcher), Some(event_id)) = (&inner.dispatcher, listener.event_id) {
+            dispatcher.handle.unlisten(event_id);
+        }
+        true
+    }
+
+    /**
+        Registers all listeners with a newly started app.
+    */
+    fn start(&self, dispatcher: Dispatcher) -> Result<(), String> {
+        let mut inner = self.0.lock().unwrap();
+        for listener in &mut inner.list {
+            listener.event_id = Some(dispatcher.register(listener, self)?);
+        }
+        inner.dispatcher = Some(dispatcher);
+        Ok(())
+    }
 }
 
 #[derive(Clone, Default)]
@@ -38,185 +605,457 @@ struct TauriConfig {
     window_title: String,
     window_width: u32,
     window_height: u32,
+    window_x: Option<i32>,
+    window_y: Option<i32>,
+    window_center: bool,
+    window_min_width: Option<u32>,
+    window_min_height: Option<u32>,
+    window_resizable: bool,
+    window_decorations: bool,
+    window_transparent: bool,
+    window_always_on_top: bool,
+    window_fullscreen: bool,
+    csp: String,
+}
+
+impl TauriConfig {
+    /**
+        Applies the window and security settings in this config to the given context.
+    */
+    fn apply_to_context(&self, context: &mut tauri::Context<tauri::Wry>) {
+        let app_config = &mut context.config_mut().app;
+        app_config.security.csp = Some(Csp::Policy(self.csp.clone()));
+        if let Some(window) = app_config.windows.first_mut() {
+            window.title.clone_from(&self.window_title);
+            window.width = f64::from(self.window_width);
+            window.height = f64::from(self.window_height);
+            // Centering takes precedence over any explicit position
+            if self.window_center {
+                window.center = true;
+                window.x = None;
+                window.y = None;
+            } else {
+                window.x = self.window_x.map(f64::from);
+                window.y = self.window_y.map(f64::from);
+            }
+            window.min_width = self.window_min_width.map(f64::from);
+            window.min_height = self.window_min_height.map(f64::from);
+            window.resizable = self.window_resizable;
+            window.decorations = self.window_decorations;
+            window.transparent = self.window_transparent;
+            window.always_on_top = self.window_always_on_top;
+            window.fullscreen = self.window_fullscreen;
+        }
+    }
+
+    /**
+        Warns if the configured window position is not on any of the available monitors.
+    */
+    fn warn_if_offscreen(&self, app: &tauri::App) {
+        let (Some(x), Some(y)) = (self.window_x, self.window_y) else {
+            return;
+        };
+        if self.window_center {
+            return;
+        }
+        let monitors = app.available_monitors().unwrap_or_default();
+        let on_screen = monitors.iter().any(|monitor| {
+            // NOTE: Monitor bounds are in physical pixels, window positions are logical
+            let scale = monitor.scale_factor();
+            let pos = monitor.position().to_logical::<f64>(scale);
+            let size = monitor.size().to_logical::<f64>(scale);
+            let (x, y) = (f64::from(x), f64::from(y));
+            x >= pos.x && y >= pos.y && x < pos.x + size.width && y < pos.y + size.height
+        });
+        if !monitors.is_empty() && !on_screen {
+            eprintln!(
+                "Warning: window position ({x}, {y}) is outside of all available monitors, \
+                the window may not be visible"
+            );
+        }
+    }
+}
+
+/// How much the Tauri integration itself logs, set using the options for app:run()
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+enum LogLevel {
+    Error,
+    #[default]
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl FromLua for LogLevel {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let LuaValue::String(s) = &value else {
+            return Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "LogLevel".to_string(),
+                message: None,
+            });
+        };
+        match s.to_str()?.to_ascii_lowercase().as_str() {
+            "error" => Ok(Self::Error),
+            "warn" => Ok(Self::Warn),
+            "info" => Ok(Self::Info),
+            "debug" => Ok(Self::Debug),
+            "trace" => Ok(Self::Trace),
+            other => Err(LuaError::runtime(format!(
+                "Invalid log level '{other}', expected one of: error, warn, info, debug, trace"
+            ))),
+        }
+    }
+}
+
+/// Options given to app:run(), all of which are optional
+#[derive(Default)]
+struct RunOptions {
+    on_ready: Option<Arc<LuaRegistryKey>>,
+    on_exit: Option<Arc<LuaRegistryKey>>,
+    log_level: LogLevel,
+}
+
+impl FromLua for RunOptions {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        let options = match value {
+            LuaValue::Nil => return Ok(Self::default()),
+            LuaValue::Table(options) => options,
+            value => {
+                return Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "RunOptions".to_string(),
+                    message: Some("Expected a table of options or nil".to_string()),
+                });
+            }
+        };
+        let hook = |name: &str| -> LuaResult<Option<Arc<LuaRegistryKey>>> {
+            match options.get::<Option<LuaFunction>>(name)? {
+                Some(func) => Ok(Some(Arc::new(lua.create_registry_value(func)?))),
+                None => Ok(None),
+            }
+        };
+        Ok(Self {
+            on_ready: hook("on_ready")?,
+            on_exit: hook("on_exit")?,
+            log_level: options
+                .get::<Option<LogLevel>>("log_level")?
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/**
+    Calls a hook given to app:run() with the app handle, logging any error it throws.
+*/
+fn call_run_hook(lua: &Lua, name: &str, hook: &LuaRegistryKey, app: LuaAppHandle, level: LogLevel) {
+    if level >= LogLevel::Debug {
+        eprintln!("[tauri] calling {name} hook");
+    }
+    let result = lua
+        .registry_value::<LuaFunction>(hook)
+        .and_then(|func| func.call::<()>(app));
+    if let Err(e) = result {
+        eprintln!("[tauri] error in {name} hook: {e}");
+    }
 }
 
 impl LuaUserData for TauriApp {
     fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
-        // app:listen(event, callback)
+        // app:name(), app:version(), app:identifier()
+        methods.add_method("name", |_, this, ()| Ok(this.config.name.clone()));
+        methods.add_method("version", |_, this, ()| Ok(this.config.version.clone()));
+        methods.add_method("identifier", |_, this, ()| {
+            Ok(this.config.identifier.clone())
+        });
+
+        // app:plugins()
+        methods.add_method("plugins", |_, _, ()| Ok(enabled_plugins()));
+
+        // app:listen(event, callback, shape?) -> handle or app:listen({ [event]: callback }) -> { [event]: handle }
         methods.add_method(
             "listen",
-            |lua, this, (event, func): (String, LuaFunction)| {
-                let mut list = this.listeners.lock().unwrap();
-                list.push((event, Arc::new(lua.create_registry_value(func)?)));
-                Ok(())
+            |lua, this, args: (LuaValue, Option<LuaFunction>, Option<PayloadShape>)| {
+                let (events, func, shape) = args;
+                match events {
+                    LuaValue::String(event) => {
+                        let func = func.ok_or_else(|| {
+                            LuaError::runtime("Expected a callback function as second argument")
+                        })?;
+                        let event = event.to_str()?.to_string();
+                        let handle = match shape {
+                            Some(shape) => {
+                                this.listeners.add_with_shape(lua, event, func, shape)?
+                            }
+                            None => this.listeners.add(lua, event, None, func)?,
+                        };
+                        handle.into_lua(lua)
+                    }
+                    LuaValue::Table(_) if shape.is_some() => Err(LuaError::runtime(
+                        "Payload shapes can only be given when listening for a single event",
+                    )),
+                    LuaValue::Table(mappings) => {
+                        let handles = lua.create_table()?;
+                        for pair in mappings.pairs::<String, LuaFunction>() {
+                            let (event, func) = pair?;
+                            let handle = this.listeners.add(lua, event.clone(), None, func)?;
+                            handles.set(event, handle)?;
+                        }
+                        Ok(LuaValue::Table(handles))
+                    }
+                    value => Err(LuaError::runtime(format!(
+                        "Expected an event name or a table of event callbacks, got {}",
+                        value.type_name()
+                    ))),
+                }
             },
         );
 
-        // app:run()
-        methods.add_method("run", |lua, this, ()| {
-            let listeners = this.listeners.clone();
-            let unsafe_lua = Arc::new(UnsafeLua(lua.clone()));
-
-            let context = tauri::generate_context!("tauri.conf.json");
-
-            tauri::Builder::default()
-                .setup(move |app| {
-                    let handle = app.handle();
-                    let unsafe_lua = unsafe_lua.clone();
-                    let list = listeners.lock().unwrap();
-
-                    for (event_name, registry_key) in list.iter() {
-                        let event_name = event_name.clone();
-                        let registry_key = registry_key.clone();
-                        let unsafe_lua = unsafe_lua.clone();
-                        let app_handle = handle.clone();
-
-                        handle.listen_any(event_name, move |event| {
-                            let payload = event.payload().to_string();
-                            let unsafe_lua = unsafe_lua.clone();
-                            let registry_key = registry_key.clone();
-                            let app_handle_inner = app_handle.clone();
-
-                            let _ = app_handle.run_on_main_thread(move || {
-                                let lua = &unsafe_lua.0;
-                                if let Ok(func) = lua.registry_value::<LuaFunction>(&*registry_key)
-                                {
-                                    let lua_app = LuaAppHandle(app_handle_inner);
-                                    let arg = if let Ok(val) =
-                                        serde_json::from_str::<serde_json::Value>(&payload)
-                                    {
-                                        lua.to_value(&val).unwrap_or(LuaValue::Nil)
-                                    } else {
-                                        LuaValue::String(lua.create_string(&payload).unwrap())
-                                    };
-
-                                    let _ = func.call::<()>((arg, lua_app));
-                                }
-                            });
-                        });
-                    }
+        // app:listen_on(label, event, callback) -> handle
+        methods.add_method(
+            "listen_on",
+            |lua, this, (label, event, func): (String, String, LuaFunction)| {
+                this.listeners.add(lua, event, Some(label), func)
+            },
+        );
 
-                    Ok(())
-                })
-                .run(context)
-                .map_err(|e| LuaError::external(e))
+        // app:once(event, callback) -> handle, removed automatically after the first event
+        methods.add_method("once", |lua, this, (event, func): (String, LuaFunction)| {
+            this.listeners.add_once(lua, event, func)
+        });
+
+        // app:on_window_event(label, callback) -> handle, where callbacks may return
+        // false for a close-requested event to prevent the window from closing
+        methods.add_method(
+            "on_window_event",
+            |lua, this, (label, func): (String, LuaFunction)| {
+                this.listeners.add_window(lua, label, func)
+            },
+        );
+
+        // app:unlisten(handle) -> boolean
+        methods.add_method(
+            "unlisten",
+            |_, _, handle: LuaUserDataRef<ListenerHandle>| Ok(handle.remove()),
+        );
+
+        // app:run() or app:run({ on_ready = fn, on_exit = fn, log_level = "debug" })
+        methods.add_method("run", |lua, this, options: RunOptions| {
+            build_and_run(lua, &this.listeners, Some(this.config.clone()), options)
         });
     }
 }
 
+thread_local! {
+    static CUSTOM_CONTEXT: RefCell<Option<tauri::Context<tauri::Wry>>> = const { RefCell::new(None) };
+}
+
+/**
+    Sets the context to use for the next app that is run, instead of the one built into this crate.
+
+    This is used by apps built using `lune tauri build`, which embed their own
+    frontend, icons and `tauri.conf.json`, and then run their script using Lune.
+    Any config given to `tauri.new` in the script is still applied on top of it.
+*/
+pub fn set_context(context: tauri::Context<tauri::Wry>) {
+    CUSTOM_CONTEXT.with_borrow_mut(|custom| *custom = Some(context));
+}
+
+static FRONTEND_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/**
+    Sets a frontend on disk to serve for the next app that is run, instead of
+    the one embedded in its context, either a single HTML file or a directory.
+
+    This is used by `lune tauri dev`, which runs scripts without compiling
+    them, and files are read on every request, so reloading picks up changes.
+*/
+pub fn set_frontend_path(path: PathBuf) {
+    *FRONTEND_PATH.lock().unwrap() = Some(path);
+}
+
+/**
+    Builds and runs a Tauri app until it exits, registering the given listeners once it has started.
+
+    Shared by app:run() and the legacy tauri.run(), which does not have a config, so that
+    both register listeners and dispatch their events to the main thread the same way.
+*/
+fn build_and_run(
+    lua: &Lua,
+    listeners: &Listeners,
+    config: Option<Arc<TauriConfig>>,
+    options: RunOptions,
+) -> LuaResult<()> {
+    let unsafe_lua = Arc::new(UnsafeLua(lua.clone()));
+    let listeners = listeners.clone();
+    let RunOptions {
+        on_ready,
+        on_exit,
+        log_level,
+    } = options;
+
+    let mut context = match CUSTOM_CONTEXT.with_borrow_mut(Option::take) {
+        Some(context) => context,
+        None => tauri::generate_context!("tauri.conf.json"),
+    };
+    if let Some(config) = &config {
+        config.apply_to_context(&mut context);
+    }
+    if let Some(path) = FRONTEND_PATH.lock().unwrap().take() {
+        context.set_assets(Box::new(DiskAssets::new(path)));
+    }
+
+    let exit_lua = unsafe_lua.clone();
+    let exit_config = config.clone();
+    let window_listeners = listeners.clone();
+
+    let app = tauri::Builder::default()
+        .setup(move |app| {
+            if log_level >= LogLevel::Warn
+                && let Some(config) = &config
+            {
+                config.warn_if_offscreen(app);
+            }
+            let handle = app.handle();
+
+            listeners.start(Dispatcher {
+                handle: handle.clone(),
+                lua: unsafe_lua.clone(),
+                config: config.clone(),
+                log_level,
+            })?;
+
+            if let Some(on_ready) = &on_ready {
+                let lua_app = LuaAppHandle {
+                    handle: handle.clone(),
+                    config: config.clone(),
+                };
+                call_run_hook(&unsafe_lua.0, "on_ready", on_ready, lua_app, log_level);
+            }
+
+            Ok(())
+        })
+        .on_window_event(move |window, event| {
+            window_listeners.dispatch_window_event(window, event);
+        })
+        .build(context)
+        .map_err(|e| LuaError::external(e))?;
+
+    app.run(move |handle, event| {
+        if let tauri::RunEvent::Exit = event
+            && let Some(on_exit) = &on_exit
+        {
+            let lua_app = LuaAppHandle {
+                handle: handle.clone(),
+                config: exit_config.clone(),
+            };
+            call_run_hook(&exit_lua.0, "on_exit", on_exit, lua_app, log_level);
+        }
+    });
+
+    Ok(())
+}
+
 pub fn module(lua: Lua) -> LuaResult<LuaTable> {
     let table = lua.create_table()?;
 
     table.set("version", tauri::VERSION)?;
 
+    // tauri.versions, for bug reports about differences between platforms, where
+    // the webview version is only known at runtime, and missing if unavailable
+    let versions = lua.create_table()?;
+    versions.set("tauri", tauri::VERSION)?;
+    versions.set("lune_integration", env!("CARGO_PKG_VERSION"))?;
+    versions.set("webview", tauri::webview_version().ok())?;
+    table.set("versions", versions)?;
+
     // tauri.new(config) -> TauriApp
     table.set(
         "new",
         lua.create_function(|_, config: LuaTable| {
-            let name = config
-                .get::<String>("name")
-                .unwrap_or_else(|_| "Lune App".to_string());
-            let identifier = config
-                .get::<String>("identifier")
-                .unwrap_or_else(|_| "org.lune.app".to_string());
-            let version = config
-                .get::<String>("version")
-                .unwrap_or_else(|_| "0.1.0".to_string());
-            let icon = config.get::<String>("icon").ok();
-            let html = config.get::<String>("html").ok();
-
-            let (window_title, window_width, window_height) =
-                if let Ok(window) = config.get::<LuaTable>("window") {
-                    (
-                        window
-                            .get::<String>("title")
-                            .unwrap_or_else(|_| name.clone()),
-                        window.get::<u32>("width").unwrap_or(800),
-                        window.get::<u32>("height").unwrap_or(600),
-                    )
-                } else {
-                    (name.clone(), 800, 600)
-                };
+            check_config_keys(&config)?;
+
+            let options = ConfigOptions::new(Some(&config), "");
+            let name = options
+                .option("name")?
+                .unwrap_or_else(|| "Lune App".to_string());
+            let identifier = options
+                .option("identifier")?
+                .unwrap_or_else(|| "org.lune.app".to_string());
+            check_identifier(&identifier)?;
+            let version = options
+                .option("version")?
+                .unwrap_or_else(|| "0.1.0".to_string());
+            // Text icons are only generated when building, and have no path to use here
+            let icon = match options.icon("icon")? {
+                Some(ConfigIcon::Path(path)) => Some(path),
+                Some(ConfigIcon::Text { .. }) | None => None,
+            };
+            let html = options.option("html")?;
+
+            // The policy applies to all windows, and must not be empty if given,
+            // since an empty policy would silently disable the CSP entirely
+            let csp = match config.get::<LuaValue>("csp")? {
+                LuaValue::Nil => DEFAULT_CSP.to_string(),
+                LuaValue::String(csp) if !csp.to_str()?.trim().is_empty() => {
+                    csp.to_str()?.to_string()
+                }
+                _ => return Err(LuaError::runtime("Expected csp to be a non-empty string")),
+            };
+
+            // All window options are optional, and default to a regular resizable window
+            let window = options.table("window")?;
+            let options = options.nested(window.as_ref(), "window.");
 
             Ok(TauriApp {
                 config: Arc::new(TauriConfig {
+                    window_title: options.option("title")?.unwrap_or_else(|| name.clone()),
+                    window_width: options.size("width", 800)?,
+                    window_height: options.size("height", 600)?,
+                    window_x: options.option("x")?,
+                    window_y: options.option("y")?,
+                    window_center: options.option("center")?.unwrap_or(false),
+                    window_min_width: options.option("min_width")?,
+                    window_min_height: options.option("min_height")?,
+                    window_resizable: options.option("resizable")?.unwrap_or(true),
+                    window_decorations: options.option("decorations")?.unwrap_or(true),
+                    window_transparent: options.option("transparent")?.unwrap_or(false),
+                    window_always_on_top: options.option("always_on_top")?.unwrap_or(false),
+                    window_fullscreen: options.option("fullscreen")?.unwrap_or(false),
                     name,
                     identifier,
                     version,
                     icon,
                     html,
-                    window_title,
-                    window_width,
-                    window_height,
+                    csp,
                 }),
-                listeners: Arc::new(Mutex::new(Vec::new())),
+                listeners: Listeners::default(),
             })
         })?,
     )?;
 
-    // Legacy: tauri.listen() and tauri.run() for backwards compatibility
-    let listeners = Arc::new(Mutex::new(Vec::<(String, Arc<LuaRegistryKey>)>::new()));
+    // Legacy: tauri.listen(), tauri.unlisten() and tauri.run() for backwards compatibility,
+    // these share a single set of listeners and otherwise behave the same as the app methods
+    let listeners = Listeners::default();
 
     let listeners_clone = listeners.clone();
     table.set(
         "listen",
         lua.create_function(move |lua, (event, func): (String, LuaFunction)| {
-            let mut list = listeners_clone.lock().unwrap();
-            list.push((event, Arc::new(lua.create_registry_value(func)?)));
-            Ok(())
+            listeners_clone.add(lua, event, None, func)
         })?,
     )?;
 
-    let unsafe_lua = Arc::new(UnsafeLua(lua.clone()));
     table.set(
-        "run",
-        lua.create_function(move |_, ()| {
-            let listeners = listeners.clone();
-            let unsafe_lua = unsafe_lua.clone();
-
-            let context = tauri::generate_context!("tauri.conf.json");
-
-            tauri::Builder::default()
-                .setup(move |app| {
-                    let handle = app.handle();
-                    let unsafe_lua = unsafe_lua.clone();
-                    let list = listeners.lock().unwrap();
-
-                    for (event_name, registry_key) in list.iter() {
-                        let event_name = event_name.clone();
-                        let registry_key = registry_key.clone();
-                        let unsafe_lua = unsafe_lua.clone();
-                        let app_handle = handle.clone();
-
-                        handle.listen_any(event_name, move |event| {
-                            let payload = event.payload().to_string();
-                            let unsafe_lua = unsafe_lua.clone();
-                            let registry_key = registry_key.clone();
-                            let app_handle_inner = app_handle.clone();
-
-                            let _ = app_handle.run_on_main_thread(move || {
-                                let lua = &unsafe_lua.0;
-                                if let Ok(func) = lua.registry_value::<LuaFunction>(&*registry_key)
-                                {
-                                    let lua_app = LuaAppHandle(app_handle_inner);
-                                    let arg = if let Ok(val) =
-                                        serde_json::from_str::<serde_json::Value>(&payload)
-                                    {
-                                        lua.to_value(&val).unwrap_or(LuaValue::Nil)
-                                    } else {
-                                        LuaValue::String(lua.create_string(&payload).unwrap())
-                                    };
-
-                                    let _ = func.call::<()>((arg, lua_app));
-                                }
-                            });
-                        });
-                    }
+        "unlisten",
+        lua.create_function(|_, handle: LuaUserDataRef<ListenerHandle>| Ok(handle.remove()))?,
+    )?;
 
-                    Ok(())
-                })
-                .run(context)
-                .map_err(|e| LuaError::external(e))
+    table.set(
+        "run",
+        lua.create_function(move |lua, ()| {
+            build_and_run(lua, &listeners, None, RunOptions::default())
         })?,
     )?;
 
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use mlua::prelude::*;
+use serde_json::Value as JsonValue;
+
+/**
+    Returns the kind of an event payload, given to listener callbacks as `payload_kind`
+    in their event info, so that scripts can tell a payload that was valid JSON but not
+    an object apart from one that was not JSON at all and was passed on as a raw string.
+*/
+pub fn payload_kind(payload: Option<&JsonValue>) -> &'static str {
+    match payload {
+        None => "invalid-json",
+        Some(JsonValue::Object(_)) => "object",
+        Some(JsonValue::Array(_)) => "array",
+        Some(JsonValue::String(_)) => "string",
+        Some(JsonValue::Number(_)) => "number",
+        Some(JsonValue::Bool(_)) => "boolean",
+        Some(JsonValue::Null) => "null",
+    }
+}
+
+/**
+    The payload that a listener expects, given as the third argument to app:listen().
+
+    Payloads that do not match are never given to the listener callback, and instead
+    go to `on_mismatch` if given, or are reported as a warning if not.
+*/
+pub struct PayloadShape {
+    /// Keys that the payload must be an object with
+    keys: Vec<String>,
+    /// Called with the mismatch, the app handle, and the event info instead of the callback
+    pub on_mismatch: Option<Arc<LuaRegistryKey>>,
+}
+
+impl PayloadShape {
+    /**
+        Checks the given payload against this shape, where `None`
+        is a payload that could not be parsed as JSON at all.
+    */
+    pub fn check(&self, payload: Option<&JsonValue>) -> Result<(), PayloadMismatch> {
+        match payload {
+            None => Err(PayloadMismatch::InvalidJson),
+            Some(JsonValue::Object(object)) => {
+                let missing = self
+                    .keys
+                    .iter()
+                    .filter(|key| !object.contains_key(key.as_str()))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if missing.is_empty() {
+                    Ok(())
+                } else {
+                    Err(PayloadMismatch::MissingKeys(missing))
+                }
+            }
+            Some(other) => Err(PayloadMismatch::NotObject(payload_kind(Some(other)))),
+        }
+    }
+}
+
+impl FromLua for PayloadShape {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        let LuaValue::Table(shape) = value else {
+            return Err(LuaError::runtime(format!(
+                "Expected a table describing the payload as third argument, got {}",
+                value.type_name()
+            )));
+        };
+        let keys = shape
+            .get::<Option<Vec<String>>>("keys")
+            .map_err(|_| LuaError::runtime("Expected payload keys to be a list of strings"))?
+            .unwrap_or_default();
+        let on_mismatch = shape
+            .get::<Option<LuaFunction>>("on_mismatch")
+            .map_err(|_| LuaError::runtime("Expected on_mismatch to be a function"))?
+            .map(|func| lua.create_registry_value(func).map(Arc::new))
+            .transpose()?;
+        Ok(Self { keys, on_mismatch })
+    }
+}
+
+/**
+    Why an event payload did not match the shape that its listener expects.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PayloadMismatch {
+    /// The payload was not valid JSON, and would have been passed on as a raw string
+    InvalidJson,
+    /// The payload was valid JSON, of the given kind, but not an object
+    NotObject(&'static str),
+    /// The payload was an object, but without the given keys
+    MissingKeys(Vec<String>),
+}
+
+impl PayloadMismatch {
+    /**
+        Creates the table given to `on_mismatch` handlers, with the `kind` of
+        mismatch, a readable `message`, the `missing` keys, if any, and the
+        `raw` payload as it was received from the frontend.
+    */
+    pub fn to_table(&self, lua: &Lua, raw: &str) -> LuaResult<LuaTable> {
+        let table = lua.create_table()?;
+        let kind = match self {
+            Self::InvalidJson => "invalid-json",
+            Self::NotObject(_) => "not-object",
+            Self::MissingKeys(keys) => {
+                table.set("missing", keys.clone())?;
+                "missing-keys"
+            }
+        };
+        table.set("kind", kind)?;
+        table.set("message", self.to_string())?;
+        table.set("raw", raw)?;
+        Ok(table)
+    }
+}
+
+impl std::fmt::Display for PayloadMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidJson => write!(f, "payload is not valid JSON"),
+            Self::NotObject(kind) => {
+                write!(f, "payload is valid JSON of type {kind}, not an object")
+            }
+            Self::MissingKeys(keys) => write!(f, "payload is missing the keys {}", keys.join(", ")),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use mlua::prelude::*;
+use serde_json::Value as JsonValue;
+use tauri::{Emitter, Listener};
+
+use crate::{UnsafeLua, payload_to_json};
+
+/// How long to wait for a stream to finish when no timeout is given, in seconds
+pub const DEFAULT_STREAM_TIMEOUT: f64 = 60.0;
+
+/// Correlation ids for streams, unique for the lifetime of the process
+static NEXT_STREAM_ID: AtomicU64 = AtomicU64::new(1);
+
+/**
+    A stream started using app:emit_stream(), which receives correlated
+    responses from the frontend until it is done, or until it times out.
+
+    The frontend responds by emitting `<event>:stream` events with a payload of
+    `{ id, chunk }` for each chunk, followed by `{ id, done: true, result }`.
+*/
+pub struct EmitStream {
+    id: u64,
+    lua: Arc<UnsafeLua>,
+    handle: tauri::AppHandle,
+    on_chunk: LuaRegistryKey,
+    on_done: LuaRegistryKey,
+    finished: AtomicBool,
+    event_id: OnceLock<tauri::EventId>,
+}
+
+impl EmitStream {
+    /**
+        Starts a new stream, emitting the given event and payload to the frontend,
+        once a listener for the responses to it has been registered.
+
+        Returns the correlation id for the stream.
+    */
+    pub fn start(
+        lua: &Lua,
+        handle: tauri::AppHandle,
+        event: &str,
+        payload: &LuaValue,
+        on_chunk: LuaFunction,
+        on_done: LuaFunction,
+        timeout: Duration,
+    ) -> LuaResult<u64> {
+        let id = NEXT_STREAM_ID.fetch_add(1, Ordering::Relaxed);
+        let payload = payload_to_json(payload)?;
+        let stream = Arc::new(Self {
+            id,
+            lua: Arc::new(UnsafeLua(lua.clone())),
+            handle,
+            on_chunk: lua.create_registry_value(on_chunk)?,
+            on_done: lua.create_registry_value(on_done)?,
+            finished: AtomicBool::new(false),
+            event_id: OnceLock::new(),
+        });
+
+        let receiver = Arc::clone(&stream);
+        let event_id = stream
+            .handle
+            .listen_any(format!("{event}:stream"), move |event| {
+                receiver.receive(event.payload());
+            });
+        let _ = stream.event_id.set(event_id);
+
+        let timed_out = Arc::clone(&stream);
+        thread::spawn(move || {
+            thread::sleep(timeout);
+            let stream = Arc::clone(&timed_out);
+            let _ = timed_out.handle.run_on_main_thread(move || {
+                stream.finish(None, Some("stream timed out"));
+            });
+        });
+
+        let message = serde_json::json!({ "id": id, "payload": payload });
+        if let Err(e) = stream.handle.emit(event, message) {
+            stream.stop();
+            return Err(LuaError::external(e));
+        }
+
+        Ok(id)
+    }
+
+    /**
+        Receives a response from the frontend, ignoring any that are for other streams.
+    */
+    fn receive(self: &Arc<Self>, payload: &str) {
+        let Ok(mut response) = serde_json::from_str::<JsonValue>(payload) else {
+            return;
+        };
+        if response.get("id").and_then(JsonValue::as_u64) != Some(self.id) {
+            return;
+        }
+        let done = response.get("done").and_then(JsonValue::as_bool) == Some(true);
+        let stream = Arc::clone(self);
+        let _ = self.handle.run_on_main_thread(move || {
+            if done {
+                stream.finish(response.get_mut("result").map(JsonValue::take), None);
+            } else if !stream.finished.load(Ordering::SeqCst) {
+                let chunk = response.get_mut("chunk").map(JsonValue::take);
+                stream.call("on_chunk", &stream.on_chunk, chunk, None);
+            }
+        });
+    }
+
+    /**
+        Finishes the stream, calling `on_done` exactly once, with either a result or an error.
+    */
+    fn finish(&self, result: Option<JsonValue>, error: Option<&str>) {
+        if self.stop() {
+            self.call("on_done", &self.on_done, result, error);
+        }
+    }
+
+    /**
+        Stops listening for responses, returning `false` if the stream had already stopped.
+    */
+    fn stop(&self) -> bool {
+        if self.finished.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        if let Some(event_id) = self.event_id.get() {
+            self.handle.unlisten(*event_id);
+        }
+        true
+    }
+
+    fn call(
+        &self,
+        name: &str,
+        key: &LuaRegistryKey,
+        value: Option<JsonValue>,
+        error: Option<&str>,
+    ) {
+        let lua = &self.lua.0;
+        let value = value
+            .and_then(|value| lua.to_value(&value).ok())
+            .unwrap_or(LuaValue::Nil);
+        let result = lua
+            .registry_value::<LuaFunction>(key)
+            .and_then(|func| func.call::<()>((value, error)));
+        if let Err(e) = result {
+            eprintln!(
+                "[tauri] error in {name} callback for stream {}: {e}",
+                self.id
+            );
+        }
+    }
+}
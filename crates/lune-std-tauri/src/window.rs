@@ -0,0 +1,117 @@
+use std::sync::mpsc;
+
+use mlua::prelude::*;
+use tauri::{LogicalSize, Manager};
+
+/// The label of the window created from the app config, used when no label is given
+pub const MAIN_WINDOW_LABEL: &str = "main";
+
+/**
+    A window of a running app, returned by app:window().
+
+    Changes to windows are sent to the main thread by Tauri, so
+    these methods are safe to call from any listener or hook.
+*/
+pub struct LuaWindow {
+    window: tauri::WebviewWindow,
+}
+
+impl LuaWindow {
+    /**
+        Gets the window with the given label, erroring if no such window exists.
+    */
+    pub fn get(handle: &tauri::AppHandle, label: Option<String>) -> LuaResult<Self> {
+        let label = label.as_deref().unwrap_or(MAIN_WINDOW_LABEL);
+        match Self::find(handle, label) {
+            Some(window) => Ok(window),
+            None => Err(LuaError::runtime(format!(
+                "No window with label '{label}' exists"
+            ))),
+        }
+    }
+
+    /**
+        Gets the window with the given label, or `None` if no such window exists.
+    */
+    pub fn find(handle: &tauri::AppHandle, label: &str) -> Option<Self> {
+        handle
+            .get_webview_window(label)
+            .map(|window| Self { window })
+    }
+
+    /**
+        Runs the given function with the window on the main thread, and waits for its result.
+
+        Lua always runs on the main thread while the app is running, in which
+        case Tauri runs the function immediately instead of queueing it.
+    */
+    fn on_main_thread<F>(&self, f: F) -> LuaResult<()>
+    where
+        F: FnOnce(&tauri::WebviewWindow) -> tauri::Result<()> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::sync_channel(1);
+        let window = self.window.clone();
+        self.window
+            .run_on_main_thread(move || {
+                let _ = tx.send(f(&window));
+            })
+            .map_err(LuaError::external)?;
+        rx.recv()
+            .map_err(LuaError::external)?
+            .map_err(LuaError::external)
+    }
+}
+
+/**
+    Converts a width and height given to a window method into a logical size.
+*/
+fn size_from_lua(method: &str, width: f64, height: f64) -> LuaResult<LogicalSize<f64>> {
+    let valid = |n: f64| n.is_finite() && n > 0.0;
+    if valid(width) && valid(height) {
+        Ok(LogicalSize::new(width, height))
+    } else {
+        Err(LuaError::runtime(format!(
+            "Expected {method} to be given a positive width and height, got {width} x {height}"
+        )))
+    }
+}
+
+impl LuaUserData for LuaWindow {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("label", |_, this, ()| Ok(this.window.label().to_string()));
+
+        // window:set_min_size(width, height) and window:clear_min_size()
+        methods.add_method("set_min_size", |_, this, (width, height): (f64, f64)| {
+            let size = size_from_lua("set_min_size", width, height)?;
+            this.on_main_thread(move |window| window.set_min_size(Some(size)))
+        });
+        methods.add_method("clear_min_size", |_, this, ()| {
+            this.on_main_thread(|window| window.set_min_size(None::<LogicalSize<f64>>))
+        });
+
+        // window:set_max_size(width, height) and window:clear_max_size()
+        methods.add_method("set_max_size", |_, this, (width, height): (f64, f64)| {
+            let size = size_from_lua("set_max_size", width, height)?;
+            this.on_main_thread(move |window| window.set_max_size(Some(size)))
+        });
+        methods.add_method("clear_max_size", |_, this, ()| {
+            this.on_main_thread(|window| window.set_max_size(None::<LogicalSize<f64>>))
+        });
+
+        methods.add_method("show", |_, this, ()| {
+            this.on_main_thread(tauri::WebviewWindow::show)
+        });
+        methods.add_method("hide", |_, this, ()| {
+            this.on_main_thread(tauri::WebviewWindow::hide)
+        });
+        methods.add_method("close", |_, this, ()| {
+            this.on_main_thread(tauri::WebviewWindow::close)
+        });
+        methods.add_method("set_focus", |_, this, ()| {
+            this.on_main_thread(tauri::WebviewWindow::set_focus)
+        });
+        methods.add_method("set_title", |_, this, title: String| {
+            this.on_main_thread(move |window| window.set_title(&title))
+        });
+    }
+}
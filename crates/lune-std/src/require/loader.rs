@@ -1,6 +1,6 @@
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
     rc::Rc,
 };
@@ -9,7 +9,7 @@ use async_channel::{Receiver, Sender};
 use async_fs::read as read_file;
 
 use lune_utils::fs::FileSystem;
-use lune_utils::path::constants::FILE_CHUNK_PREFIX;
+use lune_utils::path::{constants::FILE_CHUNK_PREFIX, get_current_dir};
 use mlua::prelude::*;
 use mlua_luau_scheduler::LuaSchedulerExt;
 use std::sync::Arc;
@@ -25,6 +25,8 @@ type RequireResultReceiver = Receiver<RequireResult>;
 struct RequireLoaderState {
     tx: Rc<RefCell<HashMap<PathBuf, RequireResultSender>>>,
     rx: Rc<RefCell<HashMap<PathBuf, RequireResultReceiver>>>,
+    /// Modules that are currently waiting on other modules to finish loading
+    waiting: Rc<RefCell<HashMap<PathBuf, Vec<PathBuf>>>>,
 }
 
 impl RequireLoaderState {
@@ -32,6 +34,7 @@ impl RequireLoaderState {
         Self {
             tx: Rc::new(RefCell::new(HashMap::new())),
             rx: Rc::new(RefCell::new(HashMap::new())),
+            waiting: Rc::new(RefCell::new(HashMap::new())),
         }
     }
 
@@ -50,6 +53,69 @@ impl RequireLoaderState {
         self.tx.borrow_mut().remove(path);
         self.rx.borrow_mut().remove(path);
     }
+
+    fn start_waiting(&self, from: &Path, to: &Path) {
+        let mut waiting = self.waiting.borrow_mut();
+        waiting
+            .entry(from.to_path_buf())
+            .or_default()
+            .push(to.to_path_buf());
+    }
+
+    fn stop_waiting(&self, from: &Path, to: &Path) {
+        let mut waiting = self.waiting.borrow_mut();
+        if let Some(targets) = waiting.get_mut(from) {
+            if let Some(index) = targets.iter().position(|target| target == to) {
+                targets.swap_remove(index);
+            }
+            if targets.is_empty() {
+                waiting.remove(from);
+            }
+        }
+    }
+
+    /**
+        Checks if the module at `from` waiting on the pending module at `to` would
+        result in a cycle, meaning `to` is already waiting on `from`, directly or
+        through other modules, and if so, returns the full cycle starting at `to`.
+    */
+    fn find_cycle(&self, from: &Path, to: &Path) -> Option<Vec<PathBuf>> {
+        let waiting = self.waiting.borrow();
+        let mut visited = HashSet::new();
+        let mut stack = vec![vec![to.to_path_buf()]];
+        while let Some(chain) = stack.pop() {
+            let last = chain.last().expect("chain is never empty");
+            if last == from {
+                let mut cycle = chain.clone();
+                cycle.push(to.to_path_buf());
+                return Some(cycle);
+            }
+            if !visited.insert(last.clone()) {
+                continue;
+            }
+            for next in waiting.get(last).into_iter().flatten() {
+                let mut chain = chain.clone();
+                chain.push(next.clone());
+                stack.push(chain);
+            }
+        }
+        None
+    }
+}
+
+fn circular_require_error(cycle: &[PathBuf]) -> LuaError {
+    let cwd = get_current_dir();
+    let chain = cycle
+        .iter()
+        .map(|path| {
+            path.strip_prefix(&cwd)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join(" -> ");
+    LuaError::runtime(format!("circular require detected: {chain}"))
 }
 
 /**
@@ -68,19 +134,28 @@ impl RequireLoader {
         }
     }
 
+    /**
+        Creates a function that loads the module at the given path.
+
+        The `requirer_path` is the file of the module that is requiring, if any, and is
+        used to detect circular requires instead of waiting forever for them to resolve.
+    */
     pub(crate) fn load(
         &self,
         lua: &Lua,
+        requirer_path: Option<&Path>,
         relative_path: &Path,
         absolute_path: &Path,
         fs: Arc<dyn FileSystem>,
     ) -> LuaResult<LuaFunction> {
+        let requirer_path = requirer_path.map(Path::to_path_buf);
         let relative_path = relative_path.to_path_buf();
         let absolute_path = absolute_path.to_path_buf();
 
         let state = self.state.clone();
 
         lua.create_async_function(move |lua, (): ()| {
+            let requirer_path = requirer_path.clone();
             let relative_path = relative_path.clone();
             let absolute_path = absolute_path.clone();
             let fs = fs.clone();
@@ -89,8 +164,21 @@ impl RequireLoader {
 
             async move {
                 if let Some(rx) = state.get_pending_at_path(&absolute_path) {
-                    rx.recv()
-                        .await
+                    // Waiting on a module that is itself waiting on us would never finish
+                    let Some(requirer_path) = requirer_path else {
+                        return rx
+                            .recv()
+                            .await
+                            .into_lua_err()
+                            .context("require process was interrupted (future dropped)")?;
+                    };
+                    if let Some(cycle) = state.find_cycle(&requirer_path, &absolute_path) {
+                        return Err(circular_require_error(&cycle));
+                    }
+                    state.start_waiting(&requirer_path, &absolute_path);
+                    let result = rx.recv().await;
+                    state.stop_waiting(&requirer_path, &absolute_path);
+                    result
                         .into_lua_err()
                         .context("require process was interrupted (future dropped)")?
                 } else {
@@ -104,7 +192,13 @@ impl RequireLoader {
 
                     let thread_id = lua.push_thread_back(chunk, ())?;
                     lua.track_thread(thread_id);
+                    if let Some(requirer_path) = &requirer_path {
+                        state.start_waiting(requirer_path, &absolute_path);
+                    }
                     lua.wait_for_thread(thread_id).await;
+                    if let Some(requirer_path) = &requirer_path {
+                        state.stop_waiting(requirer_path, &absolute_path);
+                    }
 
                     let thread_res = lua
                         .get_thread_result(thread_id)
@@ -30,6 +30,9 @@ pub struct RequireResolver {
     /// Path to the current filesystem entry that
     /// directly represents the current module path.
     resolved: Option<LuauModulePath>,
+    /// Path to the file of the module that is currently requiring,
+    /// if it could be resolved, used to detect circular requires.
+    requirer: Option<PathBuf>,
     /// Loader and accompanying state.
     loader: RequireLoader,
     /// Filesystem abstraction.
@@ -46,6 +49,7 @@ impl RequireResolver {
             relative: PathBuf::new(),
             absolute: PathBuf::new(),
             resolved: None,
+            requirer: None,
             loader: RequireLoader::new(),
             fs,
         }
@@ -93,6 +97,7 @@ impl LuaRequire for RequireResolver {
         // no file has been resolved from the current module path navigation.
         // It is really only useful when debugging the require resolver state.
         self.navigate_reset();
+        self.requirer = None;
 
         let chunk_name = chunk_name.trim_start_matches('=');
 
@@ -100,7 +105,13 @@ impl LuaRequire for RequireResolver {
             let rel = relative_path_normalize(Path::new(path));
             let abs = clean_path_and_make_absolute(&rel);
 
-            self.navigate_to(rel, abs)
+            self.navigate_to(rel, abs)?;
+            self.requirer = self
+                .resolved
+                .as_ref()
+                .and_then(|resolved| resolved.target().as_file())
+                .map(Path::to_path_buf);
+            Ok(())
         } else {
             Err(LuaNavigateError::Other(LuaError::runtime(
                 "cannot reset require state from non-file chunk",
@@ -153,8 +164,15 @@ impl LuaRequire for RequireResolver {
     }
 
     fn cache_key(&self) -> String {
+        // Modules are keyed by their canonical file paths when possible, so that
+        // the same module is never loaded twice under two different spellings
         let resolved = self.resolved.as_ref();
-        resolved.expect("called has_module first").to_string()
+        let resolved = resolved.expect("called has_module first");
+        resolved
+            .target()
+            .as_file()
+            .and_then(|file| self.fs.canonicalize(file).ok())
+            .map_or_else(|| resolved.to_string(), |path| path.display().to_string())
     }
 
     fn has_config(&self) -> bool {
@@ -169,7 +187,12 @@ impl LuaRequire for RequireResolver {
         let resolved = self.resolved.as_ref();
         let resolved = resolved.expect("called has_module first");
         let resolved = resolved.target().as_file().expect("tried to require a dir");
-        self.loader
-            .load(lua, self.relative.as_path(), resolved, self.fs.clone())
+        self.loader.load(
+            lua,
+            self.requirer.as_deref(),
+            self.relative.as_path(),
+            resolved,
+            self.fs.clone(),
+        )
     }
 }
@@ -0,0 +1,299 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::path::clean_path;
+
+use super::{FileMetadata, FileSystem};
+
+#[derive(Debug, Default)]
+struct MemFileSystemInner {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+    dirs: BTreeSet<PathBuf>,
+}
+
+impl MemFileSystemInner {
+    /**
+        Adds the given directory, and all of its parent directories.
+    */
+    fn insert_dir_all(&mut self, path: &Path) -> Result<()> {
+        for dir in path.ancestors().map(clean_path) {
+            if self.files.contains_key(&dir) {
+                return Err(Error::new(
+                    ErrorKind::AlreadyExists,
+                    format!("a file already exists at '{}'", dir.display()),
+                ));
+            }
+            if !self.dirs.insert(dir) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/**
+    A filesystem that is stored entirely in memory, useful for tests,
+    and as an overlay on top of another filesystem.
+
+    Paths are cleaned before being used, meaning that `./a/../b` and `b`
+    refer to the same file, and relative paths are relative to the root
+    directory `.`, which always exists.
+
+    Clones share the same files, so a clone can be given to a
+    runtime while the original is kept to inspect any writes later.
+
+    # Example Usage
+
+    ```rs
+    let fs = MemFileSystem::new()
+        .with_file("main.luau", "return require('./lib/util')")
+        .with_file("lib/util.luau", "return {}")
+        .with_dir("assets");
+    ```
+*/
+#[derive(Debug, Clone, Default)]
+pub struct MemFileSystem {
+    inner: Arc<Mutex<MemFileSystemInner>>,
+}
+
+impl MemFileSystem {
+    /**
+        Creates a new, empty, in-memory filesystem.
+    */
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+        Adds a file with the given contents, creating any missing parent directories.
+
+        # Panics
+
+        Panics if a parent of the file was already added as a file.
+    */
+    #[must_use]
+    pub fn with_file(self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) -> Self {
+        let path = path.as_ref();
+        let parent = path.parent().unwrap_or(Path::new(""));
+        self.create_dir_all(parent)
+            .expect("parent of file must not be a file");
+        self.write(path, &contents.into())
+            .expect("file must not be a directory");
+        self
+    }
+
+    /**
+        Adds a directory, creating any missing parent directories.
+
+        # Panics
+
+        Panics if the directory, or any of its parents, was already added as a file.
+    */
+    #[must_use]
+    pub fn with_dir(self, path: impl AsRef<Path>) -> Self {
+        self.create_dir_all(path.as_ref())
+            .expect("directory must not be a file");
+        self
+    }
+
+    fn not_found(path: &Path) -> Error {
+        Error::new(
+            ErrorKind::NotFound,
+            format!("no such file or directory: '{}'", path.display()),
+        )
+    }
+}
+
+impl FileSystem for MemFileSystem {
+    fn is_file(&self, path: &Path) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.files.contains_key(&clean_path(path))
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        let path = clean_path(path);
+        let inner = self.inner.lock().unwrap();
+        path == Path::new(".") || inner.dirs.contains(&path)
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        if self.is_dir(path) {
+            return Err(Error::new(
+                ErrorKind::IsADirectory,
+                format!("can not read directory '{}'", path.display()),
+            ));
+        }
+        let inner = self.inner.lock().unwrap();
+        match inner.files.get(&clean_path(path)) {
+            Some(contents) => Ok(contents.clone()),
+            None => Err(Self::not_found(path)),
+        }
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        let bytes = self.read(path)?;
+        String::from_utf8(bytes).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        if !self.is_dir(path) {
+            if self.is_file(path) {
+                return Err(Error::new(
+                    ErrorKind::NotADirectory,
+                    format!("can not read '{}' as a directory", path.display()),
+                ));
+            }
+            return Err(Self::not_found(path));
+        }
+
+        // Only direct children are listed, same as for other filesystems
+        let dir = clean_path(path);
+        let inner = self.inner.lock().unwrap();
+        let mut entries = inner
+            .files
+            .keys()
+            .chain(inner.dirs.iter())
+            .filter(|child| *child != &dir && child.parent().map(clean_path).as_ref() == Some(&dir))
+            .filter_map(|child| child.file_name())
+            .map(|name| path.join(name))
+            .collect::<Vec<_>>();
+        entries.sort();
+        Ok(entries)
+    }
+
+    fn metadata(&self, path: &Path) -> Result<FileMetadata> {
+        if self.is_dir(path) {
+            return Ok(FileMetadata {
+                size: 0,
+                is_dir: true,
+                modified: None,
+            });
+        }
+        let inner = self.inner.lock().unwrap();
+        match inner.files.get(&clean_path(path)) {
+            Some(contents) => Ok(FileMetadata {
+                size: contents.len() as u64,
+                is_dir: false,
+                modified: None,
+            }),
+            None => Err(Self::not_found(path)),
+        }
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let file = clean_path(path);
+        let parent = file.parent().map_or_else(|| PathBuf::from("."), clean_path);
+        let mut inner = self.inner.lock().unwrap();
+        if inner.dirs.contains(&file) || file == Path::new(".") {
+            return Err(Error::new(
+                ErrorKind::IsADirectory,
+                format!("can not write to directory '{}'", path.display()),
+            ));
+        }
+        if parent != Path::new(".") && !inner.dirs.contains(&parent) {
+            return Err(Self::not_found(&parent));
+        }
+        inner.files.insert(file, contents.to_vec());
+        Ok(())
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        self.inner.lock().unwrap().insert_dir_all(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.files.remove(&clean_path(path)) {
+            Some(_) => Ok(()),
+            None => Err(Self::not_found(path)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> MemFileSystem {
+        MemFileSystem::new()
+            .with_file("main.luau", "return require('./lib')")
+            .with_file("lib/init.luau", "return {}")
+            .with_file("lib/nested/deep/util.luau", "return true")
+            .with_dir("assets/empty")
+    }
+
+    #[test]
+    fn lists_nested_directories() {
+        let fs = tree();
+        assert_eq!(
+            fs.read_dir(Path::new(".")).unwrap(),
+            ["./assets", "./lib", "./main.luau"].map(PathBuf::from)
+        );
+        assert_eq!(
+            fs.read_dir(Path::new("lib")).unwrap(),
+            ["lib/init.luau", "lib/nested"].map(PathBuf::from)
+        );
+        assert_eq!(
+            fs.read_dir(Path::new("lib/nested")).unwrap(),
+            ["lib/nested/deep"].map(PathBuf::from)
+        );
+        assert!(fs.is_dir(Path::new("./lib/nested/../nested/deep")));
+        assert!(fs.is_empty_dir(Path::new("assets/empty")).unwrap());
+        assert!(!fs.is_empty_dir(Path::new("assets")).unwrap());
+
+        let err = fs.read_dir(Path::new("missing")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        let err = fs.read_dir(Path::new("main.luau")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotADirectory);
+    }
+
+    #[test]
+    fn reads_after_insert() {
+        let fs = tree();
+        assert_eq!(
+            fs.read_to_string(Path::new("./lib/init.luau")).unwrap(),
+            "return {}"
+        );
+        assert_eq!(fs.metadata(Path::new("main.luau")).unwrap().size, 23);
+        assert!(fs.read(Path::new("lib")).is_err());
+
+        // Clones share the same files, so writes are visible through both
+        let clone = fs.clone();
+        clone
+            .write(Path::new("lib/init.luau"), b"return false")
+            .unwrap();
+        assert_eq!(
+            fs.read_to_string(Path::new("lib/init.luau")).unwrap(),
+            "return false"
+        );
+        assert_eq!(
+            fs.write(Path::new("missing/file.luau"), b"")
+                .unwrap_err()
+                .kind(),
+            ErrorKind::NotFound
+        );
+
+        fs.remove_file(Path::new("main.luau")).unwrap();
+        assert!(!clone.is_file(Path::new("main.luau")));
+        assert!(fs.remove_file(Path::new("main.luau")).is_err());
+    }
+
+    #[test]
+    fn resolves_requires() {
+        let fs = tree();
+        assert_eq!(
+            fs.resolve(Path::new("main.luau"), "./lib").unwrap(),
+            PathBuf::from("lib/init.luau")
+        );
+        assert_eq!(
+            fs.resolve(Path::new("lib/init.luau"), "@self/nested/deep/util")
+                .unwrap(),
+            PathBuf::from("lib/nested/deep/util.luau")
+        );
+    }
+}
@@ -0,0 +1,400 @@
+use std::{
+    io::{Cursor, Error, ErrorKind, Read, Result},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use mlua::prelude::*;
+
+use crate::path::{LuauFilePath, LuauModulePath, clean_path};
+
+pub use encoding_rs::Encoding;
+
+mod memory;
+mod overlay;
+mod trace;
+
+pub use self::memory::MemFileSystem;
+pub use self::overlay::OverlayFileSystem;
+pub use self::trace::{
+    FileSystemAccess, FileSystemAccessSummary, FileSystemOperation, TracingFileSystem,
+};
+
+/**
+    Metadata for a file or directory in a [`FileSystem`].
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileMetadata {
+    /// The size of the file in bytes, uncompressed, or zero for directories
+    pub size: u64,
+    pub is_dir: bool,
+    /// The time that the file was last modified, if known
+    pub modified: Option<SystemTime>,
+}
+
+/**
+    A trait for abstracting filesystem operations.
+*/
+pub trait FileSystem: Send + Sync + std::fmt::Debug {
+    fn is_file(&self, path: &Path) -> bool;
+    fn is_dir(&self, path: &Path) -> bool;
+
+    /**
+        Checks if a file or directory exists at the given path.
+
+        The default implementation checks for a file and then for a directory,
+        filesystems that can check both at once should override this.
+    */
+    fn exists(&self, path: &Path) -> bool {
+        self.is_file(path) || self.is_dir(path)
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>>;
+    fn read_to_string(&self, path: &Path) -> Result<String>;
+
+    /**
+        Opens the file at the given path for reading its contents as a stream.
+
+        The default implementation reads the full file into memory first,
+        filesystems that can stream file contents should override this.
+    */
+    fn open(&self, path: &Path) -> Result<Box<dyn Read + Send>> {
+        Ok(Box::new(Cursor::new(self.read(path)?)))
+    }
+
+    /**
+        Reads the file at the given path as text in the given encoding, transcoding it to UTF-8.
+
+        A byte order mark at the start of the file takes precedence over the given encoding,
+        and any malformed text results in an error instead of using replacement characters.
+    */
+    fn read_to_string_with_encoding(
+        &self,
+        path: &Path,
+        encoding: &'static Encoding,
+    ) -> Result<String> {
+        let bytes = self.read(path)?;
+        let (text, used_encoding, had_errors) = encoding.decode(&bytes);
+        if had_errors {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "file at '{}' is not valid {} text",
+                    path.display(),
+                    used_encoding.name()
+                ),
+            ));
+        }
+        Ok(text.into_owned())
+    }
+
+    /**
+        Reads the direct children of the directory at the given path.
+
+        Entries are always sorted lexicographically, so that listings
+        are consistent regardless of the filesystem implementation.
+    */
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>>;
+
+    /**
+        Checks if the directory at the given path has no children.
+
+        The default implementation reads the full directory listing, filesystems
+        that can check for children more efficiently should override this.
+    */
+    fn is_empty_dir(&self, path: &Path) -> Result<bool> {
+        self.read_dir(path).map(|entries| entries.is_empty())
+    }
+
+    /**
+        Gets metadata for the file or directory at the given path.
+
+        The default implementation reads the full file to find its size, and
+        never knows modification times - filesystems that can get metadata
+        without reading the file, such as from an archive header, should override this.
+    */
+    fn metadata(&self, path: &Path) -> Result<FileMetadata> {
+        if self.is_dir(path) {
+            return Ok(FileMetadata {
+                size: 0,
+                is_dir: true,
+                modified: None,
+            });
+        }
+        let bytes = self.read(path)?;
+        Ok(FileMetadata {
+            size: bytes.len() as u64,
+            is_dir: false,
+            modified: None,
+        })
+    }
+
+    /**
+        Returns the canonical form of the given path, with `.` and `..` resolved,
+        so that different spellings of the same path can be compared, such as
+        when keying caches of loaded modules.
+
+        The default implementation cleans the path logically, filesystems with
+        their own notion of canonical paths, such as ones that follow symbolic
+        links or that have a root of their own, should override this.
+
+        # Errors
+
+        If nothing exists at the given path.
+    */
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
+        if !self.exists(path) {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("no such file or directory: '{}'", path.display()),
+            ));
+        }
+        Ok(clean_path(path))
+    }
+
+    /**
+        Writes the given contents to the file at the given path,
+        creating the file if it does not exist, and replacing it if it does.
+
+        The default implementation returns an [`ErrorKind::Unsupported`] error,
+        meaning that filesystems are read-only unless they override this.
+    */
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let _ = contents;
+        Err(unsupported("write", path))
+    }
+
+    /**
+        Creates a directory at the given path, including any missing parent directories.
+
+        The default implementation returns an [`ErrorKind::Unsupported`] error,
+        meaning that filesystems are read-only unless they override this.
+    */
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        Err(unsupported("create directory", path))
+    }
+
+    /**
+        Removes the file at the given path.
+
+        The default implementation returns an [`ErrorKind::Unsupported`] error,
+        meaning that filesystems are read-only unless they override this.
+    */
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        Err(unsupported("remove file", path))
+    }
+
+    /**
+        Resolves a require specifier, such as `./module` or `@self/module`,
+        relative to the module file at `from`, to the module file it refers to.
+
+        Module files are searched for the same way as `require` does at runtime,
+        using [`LuauFilePath`], so that all filesystems resolve requires identically.
+        Aliases other than `@self` depend on `.luaurc` files, and are only supported
+        by filesystems that map paths starting with the alias, such as `@lib`, to a
+        directory, returning the path to the module starting with the alias.
+
+        # Errors
+
+        - If the specifier is not a relative path, a `@self` alias, or a known alias.
+        - If the specifier is ambiguous, or does not resolve to a module file.
+    */
+    fn resolve(&self, from: &Path, specifier: &str) -> Result<PathBuf> {
+        // Requires are relative to the module path, meaning that
+        // the module for `dir/init.luau` is `dir`, and not the file
+        let module = LuauModulePath::strip(from);
+        let target = if let Some(rest) = specifier.strip_prefix("@self/") {
+            module.join(rest)
+        } else if specifier.starts_with("./") || specifier.starts_with("../") {
+            module.parent().unwrap_or(Path::new("")).join(specifier)
+        } else if let Some(alias) = specifier.split('/').next().filter(|a| a.starts_with('@'))
+            && self.is_dir(Path::new(alias))
+        {
+            PathBuf::from(specifier)
+        } else {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("can not resolve require '{specifier}' without a config file"),
+            ));
+        };
+        let target = clean_path(target);
+
+        match LuauFilePath::resolve_with_fs(&target, self) {
+            Ok(LuauFilePath::File(path)) => Ok(path),
+            Ok(LuauFilePath::Directory(_)) | Err(LuaNavigateError::NotFound) => Err(Error::new(
+                ErrorKind::NotFound,
+                format!("no module found for require '{specifier}'"),
+            )),
+            Err(LuaNavigateError::Ambiguous) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("require '{specifier}' is ambiguous"),
+            )),
+            Err(LuaNavigateError::Other(e)) => Err(Error::other(e.to_string())),
+        }
+    }
+}
+
+fn unsupported(operation: &str, path: &Path) -> Error {
+    Error::new(
+        ErrorKind::Unsupported,
+        format!(
+            "can not {operation} at '{}', filesystem is read-only",
+            path.display()
+        ),
+    )
+}
+
+/**
+    Looks up a text encoding by its label, such as `utf-8`, `latin1` or `utf-16le`.
+
+    Labels are matched according to the WHATWG Encoding Standard, meaning
+    that some labels map to a superset, such as `latin1` to `windows-1252`.
+*/
+#[must_use]
+pub fn encoding_for_label(label: &str) -> Option<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+}
+
+/**
+    Standard filesystem implementation using `std::fs`.
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct StdFileSystem;
+
+impl FileSystem for StdFileSystem {
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn open(&self, path: &Path) -> Result<Box<dyn Read + Send>> {
+        Ok(Box::new(std::fs::File::open(path)?))
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(path)? {
+            entries.push(entry?.path());
+        }
+        entries.sort();
+        Ok(entries)
+    }
+
+    fn is_empty_dir(&self, path: &Path) -> Result<bool> {
+        Ok(std::fs::read_dir(path)?.next().is_none())
+    }
+
+    fn metadata(&self, path: &Path) -> Result<FileMetadata> {
+        let metadata = std::fs::metadata(path)?;
+        Ok(FileMetadata {
+            size: if metadata.is_dir() { 0 } else { metadata.len() },
+            is_dir: metadata.is_dir(),
+            modified: metadata.modified().ok(),
+        })
+    }
+
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
+        // Same as std::fs::canonicalize, but without UNC prefixes on Windows
+        // where possible, so that paths match the current directory from std.rs
+        dunce::canonicalize(path)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        std::fs::write(path, contents)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        std::fs::create_dir_all(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        std::fs::remove_file(path)
+    }
+}
+
+/**
+    Sets the filesystem implementation to use for the given Lua state.
+
+    Standard libraries that support custom filesystems will
+    use this instead of reading directly from the disk.
+*/
+pub fn set_lua_filesystem(lua: &Lua, fs: Arc<dyn FileSystem>) {
+    lua.set_app_data(fs);
+}
+
+/**
+    Gets the filesystem implementation to use for the given Lua state,
+    falling back to [`StdFileSystem`] if none has been set.
+*/
+#[must_use]
+pub fn get_lua_filesystem(lua: &Lua) -> Arc<dyn FileSystem> {
+    match lua.app_data_ref::<Arc<dyn FileSystem>>() {
+        Some(fs) => Arc::clone(&fs),
+        None => Arc::new(StdFileSystem),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonicalizes_std_paths() {
+        let root = std::env::temp_dir().join(format!("lune-fs-canonical-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("lib")).unwrap();
+        std::fs::write(root.join("lib").join("util.luau"), "return {}").unwrap();
+
+        let direct = StdFileSystem.canonicalize(&root.join("lib").join("util.luau"));
+        let dotted = StdFileSystem.canonicalize(&root.join("lib/../lib/./util.luau"));
+        let missing = StdFileSystem.canonicalize(&root.join("missing.luau"));
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let direct = direct.unwrap();
+        assert!(direct.is_absolute());
+        assert!(direct.ends_with("lib/util.luau"));
+        assert_eq!(dotted.unwrap(), direct);
+        assert_eq!(missing.unwrap_err().kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn canonicalizes_memory_paths() {
+        let fs = MemFileSystem::new().with_file("lib/util.luau", "return {}");
+        assert_eq!(
+            fs.canonicalize(Path::new("./lib/../lib/util.luau"))
+                .unwrap(),
+            Path::new("lib/util.luau")
+        );
+        assert!(fs.canonicalize(Path::new("lib/missing.luau")).is_err());
+    }
+
+    #[test]
+    fn checks_existence_of_files_and_dirs() {
+        let fs = MemFileSystem::new()
+            .with_file("lib/util.luau", "return {}")
+            .with_dir("assets");
+        assert!(fs.exists(Path::new("lib/util.luau")));
+        assert!(fs.exists(Path::new("lib")));
+        assert!(fs.exists(Path::new("assets")));
+        assert!(!fs.exists(Path::new("lib/missing.luau")));
+
+        let root = std::env::temp_dir();
+        assert!(StdFileSystem.exists(&root));
+        assert!(!StdFileSystem.exists(&root.join("lune-fs-missing-file.luau")));
+    }
+}
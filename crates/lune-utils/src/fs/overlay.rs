@@ -0,0 +1,182 @@
+use std::{
+    collections::BTreeSet,
+    io::{Read, Result},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use super::{FileMetadata, FileSystem};
+
+/**
+    A filesystem that layers one filesystem on top of another.
+
+    Files in the top layer take precedence over files with the same path in the
+    bottom layer, such as real files on disk overriding the files embedded in a
+    standalone binary during development. Paths exist if either layer has them,
+    and directories list the children of both layers, without duplicates.
+
+    Writes always go to the top layer, and the bottom layer is never modified.
+*/
+#[derive(Debug, Clone)]
+pub struct OverlayFileSystem {
+    pub top: Arc<dyn FileSystem>,
+    pub bottom: Arc<dyn FileSystem>,
+}
+
+impl OverlayFileSystem {
+    #[must_use]
+    pub fn new(top: Arc<dyn FileSystem>, bottom: Arc<dyn FileSystem>) -> Self {
+        Self { top, bottom }
+    }
+
+    /**
+        Returns the layer that file contents at the given path should be read from.
+    */
+    fn layer_for_file(&self, path: &Path) -> &dyn FileSystem {
+        if self.top.is_file(path) {
+            &*self.top
+        } else {
+            &*self.bottom
+        }
+    }
+}
+
+impl FileSystem for OverlayFileSystem {
+    fn is_file(&self, path: &Path) -> bool {
+        self.top.is_file(path) || self.bottom.is_file(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.top.is_dir(path) || self.bottom.is_dir(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.top.exists(path) || self.bottom.exists(path)
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        self.layer_for_file(path).read(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        self.layer_for_file(path).read_to_string(path)
+    }
+
+    fn open(&self, path: &Path) -> Result<Box<dyn Read + Send>> {
+        self.layer_for_file(path).open(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let (in_top, in_bottom) = (self.top.is_dir(path), self.bottom.is_dir(path));
+        if !in_top && !in_bottom {
+            // Neither layer has the directory, so either error is fine
+            return self.bottom.read_dir(path);
+        }
+
+        // Layers may format the paths of children differently, so
+        // they are deduplicated and joined again using their names
+        let mut names = BTreeSet::new();
+        for (is_dir, layer) in [(in_top, &self.top), (in_bottom, &self.bottom)] {
+            if is_dir {
+                for child in layer.read_dir(path)? {
+                    if let Some(name) = child.file_name() {
+                        names.insert(name.to_os_string());
+                    }
+                }
+            }
+        }
+        Ok(names.into_iter().map(|name| path.join(name)).collect())
+    }
+
+    fn metadata(&self, path: &Path) -> Result<FileMetadata> {
+        if self.top.exists(path) {
+            self.top.metadata(path)
+        } else {
+            self.bottom.metadata(path)
+        }
+    }
+
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
+        if self.top.exists(path) {
+            self.top.canonicalize(path)
+        } else {
+            self.bottom.canonicalize(path)
+        }
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        self.top.write(path, contents)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        self.top.create_dir_all(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        self.top.remove_file(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fs::MemFileSystem;
+
+    use super::*;
+
+    fn overlay() -> (MemFileSystem, MemFileSystem, OverlayFileSystem) {
+        let top = MemFileSystem::new()
+            .with_file("main.luau", "print('from disk')")
+            .with_file("lib/dev.luau", "return 'dev'");
+        let bottom = MemFileSystem::new()
+            .with_file("main.luau", "print('embedded')")
+            .with_file("lib/util.luau", "return {}")
+            .with_dir("assets");
+        let overlay = OverlayFileSystem::new(Arc::new(top.clone()), Arc::new(bottom.clone()));
+        (top, bottom, overlay)
+    }
+
+    #[test]
+    fn reads_from_the_top_layer_first() {
+        let (_, bottom, overlay) = overlay();
+        assert_eq!(
+            overlay.read_to_string(Path::new("main.luau")).unwrap(),
+            "print('from disk')"
+        );
+        assert_eq!(
+            overlay.read_to_string(Path::new("lib/util.luau")).unwrap(),
+            "return {}"
+        );
+        assert!(overlay.is_file(Path::new("lib/dev.luau")));
+        assert!(overlay.is_dir(Path::new("assets")));
+        assert!(!overlay.is_file(Path::new("missing.luau")));
+        assert_eq!(overlay.metadata(Path::new("main.luau")).unwrap().size, 18);
+
+        // Writes only ever go to the top layer
+        overlay
+            .write(Path::new("lib/util.luau"), b"return false")
+            .unwrap();
+        assert_eq!(
+            overlay.read_to_string(Path::new("lib/util.luau")).unwrap(),
+            "return false"
+        );
+        assert_eq!(
+            bottom.read_to_string(Path::new("lib/util.luau")).unwrap(),
+            "return {}"
+        );
+    }
+
+    #[test]
+    fn merges_directories_from_both_layers() {
+        let (_, _, overlay) = overlay();
+        assert_eq!(
+            overlay.read_dir(Path::new(".")).unwrap(),
+            ["./assets", "./lib", "./main.luau"].map(PathBuf::from)
+        );
+        assert_eq!(
+            overlay.read_dir(Path::new("lib")).unwrap(),
+            ["lib/dev.luau", "lib/util.luau"].map(PathBuf::from)
+        );
+        assert!(overlay.read_dir(Path::new("assets")).unwrap().is_empty());
+        assert!(overlay.read_dir(Path::new("missing")).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    collections::HashMap,
+    io::{Read, Result},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use super::{FileMetadata, FileSystem};
+
+/**
+    A filesystem operation that was recorded by a [`TracingFileSystem`].
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileSystemOperation {
+    IsFile,
+    IsDir,
+    Exists,
+    Read,
+    ReadToString,
+    Open,
+    ReadDir,
+    IsEmptyDir,
+    Metadata,
+    Canonicalize,
+    Write,
+    CreateDirAll,
+    RemoveFile,
+}
+
+/**
+    A single recorded access to a filesystem.
+
+    The number of bytes is only recorded for operations that read or write
+    file contents, and will be zero for all other kinds of operations - files
+    that are opened are streamed after the access is recorded, so opening is zero too.
+*/
+#[derive(Debug, Clone)]
+pub struct FileSystemAccess {
+    pub operation: FileSystemOperation,
+    pub path: PathBuf,
+    pub bytes: usize,
+    pub duration: Duration,
+}
+
+/**
+    All recorded accesses to a single path, aggregated together.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct FileSystemAccessSummary {
+    pub path: PathBuf,
+    pub calls: usize,
+    pub bytes: usize,
+    pub duration: Duration,
+}
+
+/**
+    A filesystem that delegates to an inner filesystem,
+    while recording every access made to it for profiling.
+
+    Clones share the same access log, so a clone can be given to
+    a runtime while the original is kept to inspect the log later.
+*/
+#[derive(Debug, Clone)]
+pub struct TracingFileSystem<F> {
+    inner: F,
+    log: Arc<Mutex<Vec<FileSystemAccess>>>,
+}
+
+impl<F: FileSystem> TracingFileSystem<F> {
+    #[must_use]
+    pub fn new(inner: F) -> Self {
+        Self {
+            inner,
+            log: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /**
+        Returns all accesses recorded so far, in the order they were made.
+    */
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn accesses(&self) -> Vec<FileSystemAccess> {
+        self.log.lock().unwrap().clone()
+    }
+
+    /**
+        Returns all accesses recorded so far aggregated by path,
+        sorted by the total time spent accessing each path.
+    */
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn summary(&self) -> Vec<FileSystemAccessSummary> {
+        let log = self.log.lock().unwrap();
+        let mut by_path = HashMap::<&Path, FileSystemAccessSummary>::new();
+        for access in log.iter() {
+            let summary =
+                by_path
+                    .entry(access.path.as_path())
+                    .or_insert_with(|| FileSystemAccessSummary {
+                        path: access.path.clone(),
+                        ..Default::default()
+                    });
+            summary.calls += 1;
+            summary.bytes += access.bytes;
+            summary.duration += access.duration;
+        }
+
+        let mut summaries = by_path.into_values().collect::<Vec<_>>();
+        summaries.sort_by(|a, b| b.duration.cmp(&a.duration).then(a.path.cmp(&b.path)));
+        summaries
+    }
+
+    /**
+        Clears all accesses recorded so far.
+    */
+    #[allow(clippy::missing_panics_doc)]
+    pub fn clear(&self) {
+        self.log.lock().unwrap().clear();
+    }
+
+    fn record<T>(
+        &self,
+        operation: FileSystemOperation,
+        path: &Path,
+        f: impl FnOnce() -> T,
+        bytes: impl FnOnce(&T) -> usize,
+    ) -> T {
+        let start = Instant::now();
+        let result = f();
+        let duration = start.elapsed();
+        self.log.lock().unwrap().push(FileSystemAccess {
+            operation,
+            path: path.to_path_buf(),
+            bytes: bytes(&result),
+            duration,
+        });
+        result
+    }
+}
+
+impl<F: FileSystem> FileSystem for TracingFileSystem<F> {
+    fn is_file(&self, path: &Path) -> bool {
+        let op = FileSystemOperation::IsFile;
+        self.record(op, path, || self.inner.is_file(path), |_| 0)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        let op = FileSystemOperation::IsDir;
+        self.record(op, path, || self.inner.is_dir(path), |_| 0)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let op = FileSystemOperation::Exists;
+        self.record(op, path, || self.inner.exists(path), |_| 0)
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        let op = FileSystemOperation::Read;
+        self.record(
+            op,
+            path,
+            || self.inner.read(path),
+            |res| res.as_ref().map_or(0, Vec::len),
+        )
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        let op = FileSystemOperation::ReadToString;
+        self.record(
+            op,
+            path,
+            || self.inner.read_to_string(path),
+            |res| res.as_ref().map_or(0, String::len),
+        )
+    }
+
+    fn open(&self, path: &Path) -> Result<Box<dyn Read + Send>> {
+        let op = FileSystemOperation::Open;
+        self.record(op, path, || self.inner.open(path), |_| 0)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let op = FileSystemOperation::ReadDir;
+        self.record(op, path, || self.inner.read_dir(path), |_| 0)
+    }
+
+    fn is_empty_dir(&self, path: &Path) -> Result<bool> {
+        let op = FileSystemOperation::IsEmptyDir;
+        self.record(op, path, || self.inner.is_empty_dir(path), |_| 0)
+    }
+
+    fn metadata(&self, path: &Path) -> Result<FileMetadata> {
+        let op = FileSystemOperation::Metadata;
+        self.record(op, path, || self.inner.metadata(path), |_| 0)
+    }
+
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
+        let op = FileSystemOperation::Canonicalize;
+        self.record(op, path, || self.inner.canonicalize(path), |_| 0)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let op = FileSystemOperation::Write;
+        self.record(
+            op,
+            path,
+            || self.inner.write(path, contents),
+            |res| if res.is_ok() { contents.len() } else { 0 },
+        )
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        let op = FileSystemOperation::CreateDirAll;
+        self.record(op, path, || self.inner.create_dir_all(path), |_| 0)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        let op = FileSystemOperation::RemoveFile;
+        self.record(op, path, || self.inner.remove_file(path), |_| 0)
+    }
+}
@@ -7,6 +7,7 @@ pub mod fmt;
 pub mod fs;
 pub mod path;
 pub mod process;
+pub mod tauri;
 
 pub use self::table_builder::TableBuilder;
 pub use self::version_string::get_version_string;
@@ -36,7 +36,7 @@ impl LuauFilePath {
 
     pub fn resolve_with_fs(
         module: impl AsRef<Path>,
-        fs: &dyn FileSystem,
+        fs: &(impl FileSystem + ?Sized),
     ) -> Result<Self, LuaNavigateError> {
         let module = module.as_ref();
 
@@ -212,7 +212,7 @@ impl LuauModulePath {
 
     pub fn resolve_with_fs(
         module: impl Into<PathBuf>,
-        fs: &dyn FileSystem,
+        fs: &(impl FileSystem + ?Sized),
     ) -> Result<Self, LuaNavigateError> {
         let source = module.into();
         let target = LuauFilePath::resolve_with_fs(&source, fs)?;
@@ -0,0 +1,27 @@
+use std::sync::Arc;
+
+/**
+    A structured config that was embedded into a standalone binary at build time,
+    stored as JSON text so that it can be cheaply shared and stored in Lua app data.
+
+    The JSON is validated when building, and is parsed into a Lua
+    value when creating the `process` standard library.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct ProcessBuildConfig {
+    json: Option<Arc<str>>,
+}
+
+impl ProcessBuildConfig {
+    #[must_use]
+    pub fn new(json: impl Into<Arc<str>>) -> Self {
+        Self {
+            json: Some(json.into()),
+        }
+    }
+
+    #[must_use]
+    pub fn json(&self) -> Option<&str> {
+        self.json.as_deref()
+    }
+}
@@ -4,12 +4,16 @@ use mlua::prelude::*;
 use os_str_bytes::{OsStrBytes, OsStringBytes};
 
 mod args;
+mod build_config;
 mod env;
 mod jit;
+mod program_name;
 
 pub use self::args::ProcessArgs;
+pub use self::build_config::ProcessBuildConfig;
 pub use self::env::ProcessEnv;
 pub use self::jit::ProcessJitEnablement;
+pub use self::program_name::ProcessProgramName;
 
 fn lua_value_to_os_string(res: LuaResult<LuaValue>, to: &'static str) -> LuaResult<OsString> {
     let (btype, bs) = match res {
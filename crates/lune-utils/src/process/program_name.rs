@@ -0,0 +1,43 @@
+use std::{env::args_os, path::Path, sync::Arc};
+
+/**
+    The name that the current program was invoked as, such as `lune`
+    or the name of a standalone binary, without any leading directories.
+
+    This is given to scripts separately from the process arguments,
+    so that they can print usage messages using the real name.
+*/
+#[derive(Debug, Clone)]
+pub struct ProcessProgramName {
+    name: Arc<str>,
+}
+
+impl ProcessProgramName {
+    #[must_use]
+    pub fn new(name: impl Into<Arc<str>>) -> Self {
+        Self { name: name.into() }
+    }
+
+    /**
+        Gets the name from the first argument given to the current process,
+        falling back to `lune` on platforms where it is not available.
+    */
+    #[must_use]
+    pub fn current() -> Self {
+        let name = args_os()
+            .next()
+            .and_then(|arg0| {
+                Path::new(&arg0)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "lune".to_string());
+        Self::new(name)
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -0,0 +1,393 @@
+/*!
+    Validation of the config given to `tauri.new`, shared by the `@lune/tauri`
+    library at runtime and by `lune tauri build` when evaluating scripts, so
+    that both accept and reject exactly the same configs.
+*/
+
+use mlua::prelude::*;
+
+/// All keys that are known in the config table given to `tauri.new`
+pub const CONFIG_KEYS: &[&str] = &[
+    "name",
+    "identifier",
+    "version",
+    "icon",
+    "html",
+    "csp",
+    "window",
+];
+
+/**
+    Checks that the config table given to `tauri.new` has no unknown keys,
+    so that typos such as `identifer` are not silently ignored.
+
+    # Errors
+
+    Errors if the table contains any key that is not in [`CONFIG_KEYS`].
+*/
+pub fn check_config_keys(config: &LuaTable) -> LuaResult<()> {
+    let mut unknown = Vec::new();
+    for pair in config.pairs::<LuaValue, LuaValue>() {
+        let (key, _) = pair?;
+        let key = key.to_string()?;
+        if !CONFIG_KEYS.contains(&key.as_str()) {
+            unknown.push(format!("'{key}'"));
+        }
+    }
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    unknown.sort();
+    Err(LuaError::runtime(format!(
+        "Unknown key {} in tauri.new config, expected one of: {}",
+        unknown.join(", "),
+        CONFIG_KEYS.join(", ")
+    )))
+}
+
+/**
+    Checks that an identifier is in the reverse domain name notation Tauri
+    requires, such as `com.example.app`, with at least two segments that only
+    contain alphanumeric characters and hyphens.
+
+    # Errors
+
+    Errors if the identifier is not in reverse domain name notation.
+*/
+pub fn check_identifier(identifier: &str) -> LuaResult<()> {
+    let segments = identifier.split('.').collect::<Vec<_>>();
+    let valid = segments.len() >= 2
+        && segments.iter().all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(LuaError::runtime(format!(
+            "Invalid identifier '{identifier}', expected reverse domain name \
+            notation using only alphanumeric characters and hyphens, such as 'com.example.app'"
+        )))
+    }
+}
+
+/**
+    The icon given to `tauri.new`, either as a path to a PNG image,
+    or as a table describing an icon generated from text.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigIcon {
+    Path(String),
+    Text {
+        text: String,
+        background: Option<String>,
+    },
+}
+
+/**
+    A table in the config given to `tauri.new`, such as the config itself or its
+    `window` table, where all options are optional, but must be of the expected
+    type if given at all, instead of being silently ignored or converted.
+*/
+#[derive(Clone, Copy)]
+pub struct ConfigOptions<'a> {
+    table: Option<&'a LuaTable>,
+    path: &'a str,
+    is_absent: fn(&LuaValue) -> bool,
+}
+
+impl<'a> ConfigOptions<'a> {
+    /**
+        Creates options for the given table, where `path` is the path to
+        the table, such as `window.`, which is used in error messages.
+    */
+    #[must_use]
+    pub fn new(table: Option<&'a LuaTable>, path: &'a str) -> Self {
+        Self {
+            table,
+            path,
+            is_absent: LuaValue::is_nil,
+        }
+    }
+
+    /**
+        Sets which values are treated the same as values that were not given
+        at all, in addition to `nil` - such as stubs when evaluating a script.
+    */
+    #[must_use]
+    pub fn with_absent(mut self, is_absent: fn(&LuaValue) -> bool) -> Self {
+        self.is_absent = is_absent;
+        self
+    }
+
+    /**
+        Creates options for the nested table with the given key, using the same
+        values as absent, or for no table at all if it was not given.
+
+        # Errors
+
+        Errors if the value is given, but is not a table.
+    */
+    pub fn table(&self, key: &str) -> LuaResult<Option<LuaTable>> {
+        match self.value(key)? {
+            None => Ok(None),
+            Some(LuaValue::Table(table)) => Ok(Some(table)),
+            Some(value) => Err(self.type_error(key, "a table", &value)),
+        }
+    }
+
+    /**
+        Creates options for the given nested table, read using [`ConfigOptions::table`].
+    */
+    #[must_use]
+    pub fn nested<'b>(&self, table: Option<&'b LuaTable>, path: &'b str) -> ConfigOptions<'b> {
+        ConfigOptions {
+            table,
+            path,
+            is_absent: self.is_absent,
+        }
+    }
+
+    /**
+        Reads an optional value of the given type.
+
+        # Errors
+
+        Errors if the value is given, but is not of the expected type.
+    */
+    pub fn option<T: ConfigValue>(&self, key: &str) -> LuaResult<Option<T>> {
+        let Some(value) = self.value(key)? else {
+            return Ok(None);
+        };
+        if T::matches(&value)
+            && let Some(table) = self.table
+            && let Ok(value) = table.get::<T>(key)
+        {
+            return Ok(Some(value));
+        }
+        Err(self.type_error(key, T::EXPECTED, &value))
+    }
+
+    /**
+        Reads a window width or height, which must be a positive number if given,
+        and where fractional sizes are truncated, same as when converting them to integers.
+
+        # Errors
+
+        Errors if the value is given, but is not a positive number.
+    */
+    pub fn size(&self, key: &str, default: u32) -> LuaResult<u32> {
+        match self.value(key)? {
+            None => Ok(default),
+            Some(LuaValue::Integer(size)) if size > 0 => {
+                u32::try_from(size).map_err(LuaError::external)
+            }
+            Some(LuaValue::Number(size)) if (1.0..=f64::from(u32::MAX)).contains(&size) => {
+                Ok(size as u32)
+            }
+            Some(value) => Err(self.type_error(key, "a positive number", &value)),
+        }
+    }
+
+    /**
+        Reads an icon, which is either a path, or a table with a path or text,
+        where a path in the table takes precedence over the text in it.
+
+        # Errors
+
+        Errors if the icon is neither a string nor a table, or if
+        it is a table without either a path or text in it.
+    */
+    pub fn icon(&self, key: &str) -> LuaResult<Option<ConfigIcon>> {
+        match self.value(key)? {
+            None => Ok(None),
+            Some(LuaValue::String(path)) => Ok(Some(ConfigIcon::Path(path.to_str()?.to_string()))),
+            Some(LuaValue::Table(icon)) => {
+                let path = format!("{}{key}.", self.path);
+                let icon = self.nested(Some(&icon), &path);
+                if let Some(path) = icon.option("path")? {
+                    return Ok(Some(ConfigIcon::Path(path)));
+                }
+                let Some(text) = icon.option("text")? else {
+                    return Err(LuaError::runtime(format!(
+                        "Expected {}{key} to have either a path or text",
+                        self.path
+                    )));
+                };
+                Ok(Some(ConfigIcon::Text {
+                    text,
+                    background: icon.option("background")?,
+                }))
+            }
+            Some(value) => Err(self.type_error(key, "a string or a table", &value)),
+        }
+    }
+
+    fn value(&self, key: &str) -> LuaResult<Option<LuaValue>> {
+        let Some(table) = self.table else {
+            return Ok(None);
+        };
+        let value = table.get::<LuaValue>(key)?;
+        if value.is_nil() || (self.is_absent)(&value) {
+            Ok(None)
+        } else {
+            Ok(Some(value))
+        }
+    }
+
+    fn type_error(&self, key: &str, expected: &str, value: &LuaValue) -> LuaError {
+        LuaError::runtime(format!(
+            "Expected {}{key} to be {expected}, got {}",
+            self.path,
+            value
+                .to_string()
+                .unwrap_or_else(|_| value.type_name().to_string())
+        ))
+    }
+}
+
+/**
+    A type of value that can be given as an option in the config given to `tauri.new`.
+*/
+pub trait ConfigValue: FromLua {
+    /// A description of the expected type, used in error messages
+    const EXPECTED: &'static str;
+    /// Whether the given value is of this type, without converting it
+    fn matches(value: &LuaValue) -> bool;
+}
+
+impl ConfigValue for String {
+    const EXPECTED: &'static str = "a string";
+    fn matches(value: &LuaValue) -> bool {
+        matches!(value, LuaValue::String(_))
+    }
+}
+
+impl ConfigValue for bool {
+    const EXPECTED: &'static str = "a boolean";
+    fn matches(value: &LuaValue) -> bool {
+        matches!(value, LuaValue::Boolean(_))
+    }
+}
+
+impl ConfigValue for i32 {
+    const EXPECTED: &'static str = "a whole number";
+    fn matches(value: &LuaValue) -> bool {
+        is_whole_number(value)
+    }
+}
+
+impl ConfigValue for u32 {
+    const EXPECTED: &'static str = "a non-negative whole number";
+    fn matches(value: &LuaValue) -> bool {
+        is_whole_number(value)
+    }
+}
+
+fn is_whole_number(value: &LuaValue) -> bool {
+    match value {
+        LuaValue::Integer(_) => true,
+        LuaValue::Number(n) => n.fract() == 0.0,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(lua: &Lua, source: &str) -> LuaTable {
+        lua.load(format!("return {source}")).eval().unwrap()
+    }
+
+    #[test]
+    fn reads_options_of_the_expected_type() {
+        let lua = Lua::new();
+        let table = options(
+            &lua,
+            "{ title = 'App', x = -5, min_width = 2.0, center = true }",
+        );
+        let window = ConfigOptions::new(Some(&table), "window.");
+        assert_eq!(
+            window.option::<String>("title").unwrap().as_deref(),
+            Some("App")
+        );
+        assert_eq!(window.option::<i32>("x").unwrap(), Some(-5));
+        assert_eq!(window.option::<u32>("min_width").unwrap(), Some(2));
+        assert_eq!(window.option::<bool>("center").unwrap(), Some(true));
+        assert_eq!(window.option::<bool>("missing").unwrap(), None);
+        assert_eq!(window.size("width", 800).unwrap(), 800);
+        assert_eq!(
+            ConfigOptions::new(None, "")
+                .option::<String>("name")
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_options_of_the_wrong_type() {
+        let lua = Lua::new();
+        let table = options(
+            &lua,
+            "{ width = '800', title = 5, x = 1.5, min_width = -1, center = 'yes', icon = true }",
+        );
+        let window = ConfigOptions::new(Some(&table), "window.");
+        let err = window.size("width", 800).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Expected window.width to be a positive number"),
+            "{err}"
+        );
+        assert!(window.option::<String>("title").is_err());
+        assert!(window.option::<i32>("x").is_err());
+        assert!(window.option::<u32>("min_width").is_err());
+        assert!(window.option::<bool>("center").is_err());
+        assert!(window.icon("icon").is_err());
+        assert!(window.table("title").is_err());
+    }
+
+    #[test]
+    fn treats_given_values_as_absent() {
+        let lua = Lua::new();
+        let table = options(&lua, "{ width = {}, title = {}, icon = {} }");
+        let window = ConfigOptions::new(Some(&table), "window.").with_absent(LuaValue::is_table);
+        assert_eq!(window.size("width", 800).unwrap(), 800);
+        assert_eq!(window.option::<String>("title").unwrap(), None);
+        assert_eq!(window.icon("icon").unwrap(), None);
+        assert!(window.table("icon").unwrap().is_none());
+    }
+
+    #[test]
+    fn reads_icons() {
+        let lua = Lua::new();
+        let table = options(
+            &lua,
+            "{ a = 'icon.png', b = { path = 'icon.png', text = 'L' }, c = { text = 'L' }, d = {} }",
+        );
+        let config = ConfigOptions::new(Some(&table), "");
+        let path = Some(ConfigIcon::Path("icon.png".to_string()));
+        assert_eq!(config.icon("a").unwrap(), path);
+        assert_eq!(config.icon("b").unwrap(), path);
+        assert_eq!(
+            config.icon("c").unwrap(),
+            Some(ConfigIcon::Text {
+                text: "L".to_string(),
+                background: None,
+            })
+        );
+        assert!(config.icon("d").is_err());
+    }
+
+    #[test]
+    fn checks_keys_and_identifiers() {
+        let lua = Lua::new();
+        assert!(check_config_keys(&options(&lua, "{ name = 'App', window = {} }")).is_ok());
+        assert!(check_config_keys(&options(&lua, "{ identifer = 'com.example.app' }")).is_err());
+        assert!(check_identifier("com.example.app").is_ok());
+        assert!(check_identifier("app").is_err());
+        assert!(check_identifier("com..app").is_err());
+    }
+}
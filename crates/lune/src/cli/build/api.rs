@@ -0,0 +1,691 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use async_fs as fs;
+use lune_utils::path::clean_path_and_make_absolute;
+
+use crate::standalone::metadata::{
+    BUILD_CONFIG_FILE_NAME, DEFAULT_ENTRY_NAME, EmbedMode, Metadata, NATIVE_LIBS_DIR, PatchOptions,
+};
+
+use super::base_exe::{
+    DEFAULT_DOWNLOAD_RETRIES, base_executable_target, get_or_download_base_executables,
+};
+use super::compression::BuildCompression;
+use super::embed::{EmbedAs, EmbedExclude, collect_embed_files, expand_embed_globs};
+use super::entries::BuildEntry;
+use super::files::{
+    EmbeddedNames, create_executable_file, decode_source, embedded_file_name, has_source_file_ext,
+    output_path_for, remove_source_file_ext,
+};
+use super::output::{BuildEvent, BuildOutputFormat, BuildWarnings};
+use super::packing::PackedExecutable;
+use super::requires::{
+    ConfigAliases, check_requires, discover_required_files, read_config_aliases,
+};
+use super::target::{BuildTarget, BuildTargetOS};
+
+const BYTES_PER_MEGABYTE: usize = 1024 * 1024;
+const DEFAULT_EMBED_SIZE_WARNING: u64 = 50;
+
+/**
+    Options for building a standalone binary, mirroring the flags of the `build` command.
+
+    Any option left as `None` uses the same default as when it is not given as a flag.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    /// The path to the input file
+    pub input: PathBuf,
+    /// The name to store the input file under in the archive, defaulting to `init.luau`
+    pub entry_name: Option<String>,
+    /// The path to the output file, defaulting to the input path with an executable
+    /// extension, and with the target appended when building for more than one target
+    pub output: Option<PathBuf>,
+    /// The targets to compile for, defaulting to only the current system
+    pub targets: Vec<BuildTarget>,
+    /// Files, directories or glob patterns to embed
+    pub embed: Vec<PathBuf>,
+    /// Files or directories to embed under a different name in the archive
+    pub embed_as: Vec<EmbedAs>,
+    /// Patterns for embedded files to leave out
+    pub embed_excludes: Vec<EmbedExclude>,
+    /// The directory that embedded file names are relative to
+    pub embed_base: Option<PathBuf>,
+    /// The compression method for embedded files
+    pub compression: Option<BuildCompression>,
+    /// Save the given compression method as the default for later builds for the same targets
+    pub save_compression: bool,
+    /// Warn about embedded files larger than this many megabytes
+    pub embed_size_warning: Option<u64>,
+    /// How to store embedded files in the executable
+    pub embed_mode: Option<EmbedMode>,
+    /// A JSON file to embed as `process.buildConfig`
+    pub config: Option<PathBuf>,
+    /// How many times to retry downloading a base executable
+    pub download_retries: Option<u32>,
+    /// Never download a base executable that is not already cached
+    pub no_download: bool,
+    /// A base executable to build from instead of downloading one
+    pub base_exe: Option<PathBuf>,
+    /// Whether to check that all requires resolve to embedded files
+    pub check_requires: Option<bool>,
+    /// Whether the binary should print its Lune version when given `--lune-version`
+    pub version_flag: Option<bool>,
+    /// Globals and standard libraries to remove from the binary
+    pub remove_globals: Vec<String>,
+    /// Native libraries to embed
+    pub native: Vec<PathBuf>,
+    /// Additional named entry points
+    pub entries: Vec<BuildEntry>,
+    /// Strip debug info from the compiled bytecode
+    pub minify: bool,
+    /// Compress the base executable using UPX, trading startup time for a smaller binary
+    pub compress_binary: bool,
+    /// How to report progress and warnings while building, or `None` to never print anything
+    pub progress: Option<BuildOutputFormat>,
+}
+
+/**
+    A binary written by a successful build, returned by [`build_standalone`] for each target.
+*/
+#[derive(Debug, Clone)]
+pub struct BuildOutput {
+    /// The path that the binary was written to
+    pub path: PathBuf,
+    /// The target that the binary was built for
+    pub target: BuildTarget,
+    /// The size of the written binary, in bytes
+    pub bytes: u64,
+    /// The number of files embedded alongside the input file, including any required files
+    pub embedded_files: usize,
+    /// Any warnings found while building, such as embedded paths that do not exist
+    pub warnings: Vec<String>,
+}
+
+/**
+    Builds a standalone binary for each of the given targets using the given options,
+    the same as the `build` command, compiling the input only once for all of them.
+
+    Nothing is printed unless [`BuildOptions::progress`] is set, and the output
+    paths, sizes and any warnings are instead returned once the binaries are written,
+    in the same order as the targets were given.
+
+    # Errors
+
+    Errors if any of the files to embed could not be read, if requires do not resolve
+    to embedded files, if a base executable could not be found or downloaded, or if
+    a binary could not be written - in which case no partial output is left behind.
+*/
+pub async fn build_standalone(options: BuildOptions) -> Result<Vec<BuildOutput>> {
+    let plan = BuildPlan::collect(&options).await?;
+    plan.check_requires(&options)?;
+    plan.write(options).await
+}
+
+/**
+    All files and settings collected for a build, before the binary is written.
+
+    The `build` command also uses this to list the files that
+    would be embedded, and to print the require graph.
+*/
+pub(super) struct BuildPlan {
+    outputs: Vec<(BuildTarget, PathBuf)>,
+    entry_name: String,
+    source_code: Vec<u8>,
+    extra_files: Vec<(String, Vec<u8>)>,
+    native_libs: Vec<String>,
+    entries: BTreeMap<String, String>,
+    pub aliases: ConfigAliases,
+    pub payload_size: usize,
+    warnings: BuildWarnings,
+}
+
+impl BuildPlan {
+    /**
+        Collects and checks everything to embed for a build, without writing anything.
+    */
+    pub async fn collect(options: &BuildOptions) -> Result<Self> {
+        let input = &options.input;
+        let mut warnings = BuildWarnings::new(options.progress);
+
+        // Derive target specs to use, from the base executable if one was
+        // given, and otherwise defaulting to the current host system
+        let targets = match &options.base_exe {
+            Some(_) if options.targets.len() > 1 => {
+                bail!("--base-exe can only be used when building for a single target");
+            }
+            Some(base_exe) => {
+                let requested = options.targets.first().cloned();
+                let target = base_executable_target(base_exe, requested, &mut warnings)
+                    .await
+                    .with_context(|| {
+                        format!("failed to read base executable '{}'", base_exe.display())
+                    })?;
+                vec![target]
+            }
+            None if options.targets.is_empty() => vec![BuildTarget::current_system()],
+            None => options.targets.clone(),
+        };
+        for (index, target) in targets.iter().enumerate() {
+            let target_name = target.to_string();
+            if targets[..index]
+                .iter()
+                .any(|t| t.to_string() == target_name)
+            {
+                bail!("target '{target_name}' was given more than once");
+            }
+            if options.compress_binary {
+                PackedExecutable::check_supported(target, options.embed_mode.unwrap_or_default())?;
+            }
+            // Sections can only be added to ELF executables, which is checked here
+            // instead of after downloading the base executable for the target
+            if options.embed_mode == Some(EmbedMode::Section) && target.os != BuildTargetOS::Linux {
+                bail!(
+                    "--embed-mode section is only supported for linux targets, \
+                    got '{target_name}' - use --embed-mode trailer instead"
+                );
+            }
+        }
+
+        if options.save_compression && options.compression.is_none() {
+            bail!(
+                "--save-compression requires a compression method to save, given using --compression"
+            );
+        }
+
+        // Derive paths to use, and make sure the output paths are
+        // not the same as the input, so that we don't overwrite it
+        let output_base = options
+            .output
+            .clone()
+            .unwrap_or_else(|| remove_source_file_ext(input));
+        let mut outputs = Vec::with_capacity(targets.len());
+        for target in &targets {
+            let output_path = output_path_for(&output_base, target, targets.len() > 1);
+            if &output_path == input {
+                if options.output.is_some() {
+                    bail!("output path cannot be the same as input path");
+                }
+                bail!(
+                    "output path cannot be the same as input path, please specify a different output path"
+                );
+            }
+            outputs.push((target.clone(), output_path));
+        }
+
+        // The input file is stored under the entry name, which must be a script at the
+        // archive root, since requires in the input are resolved relative to the root
+        let entry_name = options
+            .entry_name
+            .clone()
+            .unwrap_or_else(|| DEFAULT_ENTRY_NAME.to_string());
+        if entry_name.contains(['/', '\\']) || !has_source_file_ext(&entry_name) {
+            bail!("entry name '{entry_name}' must be a file name ending with .luau or .lua");
+        }
+
+        // Try to read the given input file
+        let source_code = fs::read(input).await.context("failed to read input file")?;
+        let source_code = decode_source(source_code)
+            .with_context(|| format!("failed to decode input file '{}'", input.display()))?;
+
+        // Collect paths of all extra files to embed and their names in the archive,
+        // expanding glob patterns and walking directories recursively, skipping
+        // any excluded paths - files given using --embed-as use their own names
+        let mut embed_files = Vec::new();
+        for path in expand_embed_globs(&options.embed, &mut warnings)? {
+            collect_embed_files(
+                &path,
+                |path| embedded_file_name(path, options.embed_base.as_deref()),
+                &options.embed_excludes,
+                &mut embed_files,
+                &mut warnings,
+            )?;
+        }
+        for embed_as in &options.embed_as {
+            collect_embed_files(
+                &embed_as.source,
+                |path| embed_as.name_for(path),
+                &options.embed_excludes,
+                &mut embed_files,
+                &mut warnings,
+            )?;
+        }
+
+        // Read all extra files, making sure that no two distinct
+        // source paths end up with the same name in the archive
+        let mut extra_files = Vec::new();
+        let mut embedded_names = EmbeddedNames::new(&entry_name, input);
+        let size_warning = options
+            .embed_size_warning
+            .unwrap_or(DEFAULT_EMBED_SIZE_WARNING);
+        for (path, name) in embed_files {
+            if !embedded_names.insert(&name, &path)? {
+                continue;
+            }
+            let content = fs::read(&path).await?;
+            if content.len() as u64 > size_warning.saturating_mul(BYTES_PER_MEGABYTE as u64) {
+                warnings.push(format!(
+                    "Embedded file '{}' is {} MB, which will significantly increase the size of the binary",
+                    path.display(),
+                    content.len() / BYTES_PER_MEGABYTE,
+                ));
+            }
+            extra_files.push((name, content));
+        }
+
+        // Validate and embed the build config, if any, so that
+        // malformed configs are caught now instead of at runtime
+        if let Some(config_path) = &options.config {
+            let contents = fs::read(config_path).await.with_context(|| {
+                format!("failed to read config file '{}'", config_path.display())
+            })?;
+            let config =
+                serde_json::from_slice::<serde_json::Value>(&contents).with_context(|| {
+                    format!("config file '{}' is not valid JSON", config_path.display())
+                })?;
+            if embedded_names.insert(BUILD_CONFIG_FILE_NAME, config_path)? {
+                extra_files.push((
+                    BUILD_CONFIG_FILE_NAME.to_string(),
+                    serde_json::to_vec(&config)?,
+                ));
+            }
+        }
+
+        // Embed native libraries under their own directory, since they need to be
+        // extracted by file name before the script can load them, meaning that two
+        // libraries with the same file name would overwrite each other when extracted
+        let mut native_libs = Vec::new();
+        let mut native_paths = BTreeMap::new();
+        for path in &options.native {
+            let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                bail!("native library path '{}' has no file name", path.display());
+            };
+            if let Some(existing) = native_paths.insert(file_name.to_lowercase(), path)
+                && clean_path_and_make_absolute(existing) != clean_path_and_make_absolute(path)
+            {
+                bail!(
+                    "native libraries '{}' and '{}' have the same file name, and would \
+                    overwrite each other when extracted - rename one of them",
+                    existing.display(),
+                    path.display()
+                );
+            }
+            let name = format!("{NATIVE_LIBS_DIR}/{file_name}");
+            let content = fs::read(path)
+                .await
+                .with_context(|| format!("failed to read native library '{}'", path.display()))?;
+            if embedded_names.insert(&name, path)? {
+                extra_files.push((name.clone(), content));
+                native_libs.push(name);
+            }
+        }
+
+        // Embed named entry points same as any other file, so that requires in
+        // them resolve relative to where they are placed in the archive
+        let mut entries = BTreeMap::new();
+        for entry in &options.entries {
+            let name = embedded_file_name(&entry.path, options.embed_base.as_deref())?;
+            if entries.contains_key(&entry.name) {
+                bail!("entry point '{}' was given more than once", entry.name);
+            }
+            let content = fs::read(&entry.path).await.with_context(|| {
+                format!("failed to read entry point '{}'", entry.path.display())
+            })?;
+            if embedded_names.insert(&name, &entry.path)? {
+                extra_files.push((name.clone(), content));
+            }
+            entries.insert(entry.name.clone(), name);
+        }
+
+        // Embed all files required by the input and embedded files, transitively,
+        // relative to the directory of the input file, since it is stored under the
+        // entry name at the archive root and its requires resolve from there - aliases
+        // in a .luaurc file next to the input are followed too, and stored in the
+        // binary, so that aliased requires resolve to embedded files at runtime
+        let input_dir = input.parent().unwrap_or(Path::new(""));
+        let aliases = read_config_aliases(input_dir, &mut warnings).await?;
+        let required_files = discover_required_files(
+            input_dir,
+            &archive_files(&entry_name, &source_code, &extra_files),
+            &aliases,
+        )
+        .await?;
+        for file in required_files {
+            if embedded_names.insert(&file.name, &file.path)? {
+                extra_files.push((file.name, file.contents));
+            }
+        }
+
+        // Warn if the full payload gets close to what any of the targets can load
+        let payload_size = source_code.len()
+            + extra_files
+                .iter()
+                .map(|(_, content)| content.len())
+                .sum::<usize>();
+        for target in &targets {
+            let payload_limit = target.max_binary_size();
+            if payload_size as u64 >= payload_limit / 10 * 9 {
+                warnings.push(format!(
+                    "Embedded payload is {} MB, which is close to the {} MB executable size limit for target '{target}'",
+                    payload_size / BYTES_PER_MEGABYTE,
+                    payload_limit / BYTES_PER_MEGABYTE as u64,
+                ));
+            }
+        }
+
+        Ok(Self {
+            outputs,
+            entry_name,
+            source_code,
+            extra_files,
+            native_libs,
+            entries,
+            aliases,
+            payload_size,
+            warnings,
+        })
+    }
+
+    /**
+        Returns all files that are stored in the archive, starting with the input file under the entry name.
+    */
+    pub fn archive_files(&self) -> Vec<(&str, &[u8])> {
+        archive_files(&self.entry_name, &self.source_code, &self.extra_files)
+    }
+
+    /**
+        Returns the name that the input file is stored under in the archive.
+    */
+    pub fn entry_name(&self) -> &str {
+        &self.entry_name
+    }
+
+    /**
+        Makes sure that all requires resolve to embedded files, since any missing
+        files would otherwise only be found at runtime, unless the check is disabled.
+    */
+    pub fn check_requires(&self, options: &BuildOptions) -> Result<()> {
+        if !options.check_requires.unwrap_or(true) {
+            return Ok(());
+        }
+        let unresolved = check_requires(&self.archive_files(), &self.aliases.dirs);
+        if unresolved.is_empty() {
+            return Ok(());
+        }
+        let input = options.input.display().to_string();
+        let list = unresolved
+            .iter()
+            .map(|u| {
+                let file = if u.file == self.entry_name {
+                    &input
+                } else {
+                    &u.file
+                };
+                format!("    {file}:{}: require(\"{}\")", u.line, u.specifier)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        bail!(
+            "found requires that do not resolve to any embedded file:\n{list}\n\
+            embed the missing files using --embed, or skip this check using --check-requires false"
+        );
+    }
+
+    /**
+        Writes the binaries for this build, one for each target, downloading
+        any base executables that are needed concurrently.
+
+        The input is compiled only once, before anything is downloaded, so that
+        errors in it are reported without waiting for any of the downloads.
+    */
+    pub async fn write(self, options: BuildOptions) -> Result<Vec<BuildOutput>> {
+        if let Some(progress) = options.progress {
+            progress.emit(&BuildEvent::Compiling {
+                input: &options.input,
+            });
+        }
+        let Self {
+            outputs,
+            entry_name,
+            source_code,
+            extra_files,
+            native_libs,
+            entries,
+            aliases,
+            warnings,
+            ..
+        } = self;
+        let bytecode = Metadata::compile_script(source_code, options.minify)
+            .context("failed to compile input file")?;
+
+        // Derive the base executable paths based on the options provided
+        let base_exe_paths = match options.base_exe.clone() {
+            Some(base_exe) => vec![base_exe],
+            None => {
+                let download_retries = options
+                    .download_retries
+                    .or_else(|| env::var("LUNE_DOWNLOAD_RETRIES").ok()?.parse().ok())
+                    .unwrap_or(DEFAULT_DOWNLOAD_RETRIES);
+                get_or_download_base_executables(
+                    outputs.iter().map(|(target, _)| target.clone()).collect(),
+                    download_retries,
+                    !options.no_download,
+                    options.progress,
+                )
+                .await?
+            }
+        };
+
+        let embed_mode = options.embed_mode.unwrap_or_default();
+        let warnings = warnings.into_messages();
+
+        // Binaries are only kept once all of them have been written, so that
+        // a build that fails partway never leaves some of its outputs behind
+        let mut built = Vec::with_capacity(outputs.len());
+        let result = async {
+            for ((target, output_path), base_exe_path) in outputs.into_iter().zip(base_exe_paths) {
+                // Derive compression to use, only saving an explicit choice for the
+                // target when asked to, so that one-off builds never change the default
+                let compression = match options.compression {
+                    Some(compression) => {
+                        if options.save_compression {
+                            compression
+                                .remember_for(&target)
+                                .await
+                                .context("failed to save compression method for target")?;
+                        }
+                        compression
+                    }
+                    None => BuildCompression::remembered_for(&target)
+                        .await
+                        .unwrap_or_default(),
+                };
+
+                // Compress the base executable before appending the payload, if requested,
+                // keeping the packed copy around until the binary has been written
+                let packed_exe = if options.compress_binary {
+                    Some(PackedExecutable::pack(&base_exe_path, &target).await?)
+                } else {
+                    None
+                };
+                let base_exe_path = packed_exe
+                    .as_ref()
+                    .map_or(base_exe_path, |packed| packed.path().to_path_buf());
+
+                let meta = Metadata::create(
+                    &bytecode,
+                    &extra_files,
+                    PatchOptions {
+                        compression: compression.zip_method(),
+                        version_flag: options.version_flag.unwrap_or(true),
+                        entry_name: entry_name.clone(),
+                        removed_globals: options.remove_globals.clone(),
+                        native_libs: native_libs.clone(),
+                        entries: entries.clone(),
+                        aliases: aliases.dirs.clone(),
+                    },
+                )
+                .context("failed to create patched binary")?;
+
+                // Write the payload to the output file, together with the contents of
+                // the lune interpreter, which are streamed instead of being read into memory
+                if let Some(progress) = options.progress {
+                    progress.emit(&BuildEvent::Writing {
+                        output: &output_path,
+                        target: target.to_string(),
+                        compression: compression.to_string(),
+                    });
+                }
+                let mut output_file = create_executable_file(&output_path).await?; // Read & execute for all, write for owner
+                let result = meta
+                    .patch_bin(&base_exe_path, embed_mode, &mut output_file)
+                    .await;
+
+                // Never leave a partially written binary behind if anything failed
+                drop(output_file);
+                drop(packed_exe);
+                let bytes = match result {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        fs::remove_file(&output_path).await.ok();
+                        return Err(e.context("failed to create patched binary"));
+                    }
+                };
+
+                built.push(BuildOutput {
+                    path: output_path,
+                    target,
+                    bytes,
+                    embedded_files: extra_files.len(),
+                    warnings: warnings.clone(),
+                });
+            }
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+        if let Err(e) = result {
+            for built in &built {
+                fs::remove_file(&built.path).await.ok();
+            }
+            return Err(e);
+        }
+
+        Ok(built)
+    }
+}
+
+fn archive_files<'a>(
+    entry_name: &'a str,
+    source_code: &'a [u8],
+    extra_files: &'a [(String, Vec<u8>)],
+) -> Vec<(&'a str, &'a [u8])> {
+    let mut files = vec![(entry_name, source_code)];
+    files.extend(
+        extra_files
+            .iter()
+            .map(|(name, content)| (name.as_str(), content.as_slice())),
+    );
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_without_printing() {
+        let dir = env::temp_dir().join(format!("lune-build-api-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::write(dir.join("main.luau"), "return require('./lib/util')").unwrap();
+        std::fs::write(dir.join("lib/util.luau"), "return true").unwrap();
+
+        // Build from a copy of this executable using an explicit compression method,
+        // so that nothing is read from or written to the cache of the current user
+        let base_exe = dir.join("base");
+        std::fs::copy(env::current_exe().unwrap(), &base_exe).unwrap();
+
+        let built = futures_lite::future::block_on(build_standalone(BuildOptions {
+            input: dir.join("main.luau"),
+            embed: vec![dir.join("missing.txt")],
+            compression: Some(BuildCompression::Deflate),
+            base_exe: Some(base_exe),
+            no_download: true,
+            ..BuildOptions::default()
+        }));
+        let result = built.map(|mut built| {
+            let built = built.remove(0);
+            let written = std::fs::metadata(&built.path).map(|meta| meta.len());
+            (built, written)
+        });
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let (built, written) = result.unwrap();
+        assert_eq!(
+            built.path,
+            dir.join("main")
+                .with_extension(built.target.exe_extension())
+        );
+        assert_eq!(written.unwrap(), built.bytes);
+        assert_eq!(built.embedded_files, 1);
+        assert_eq!(built.warnings.len(), 1);
+        assert!(built.warnings[0].contains("missing.txt"));
+    }
+
+    #[test]
+    fn rejects_sections_for_other_targets() {
+        let dir = env::temp_dir().join(format!("lune-build-section-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("main.luau"), "return nil").unwrap();
+
+        let collect = |target: &str| {
+            futures_lite::future::block_on(BuildPlan::collect(&BuildOptions {
+                input: dir.join("main.luau"),
+                targets: vec![target.parse().unwrap()],
+                embed_mode: Some(EmbedMode::Section),
+                ..BuildOptions::default()
+            }))
+        };
+        let linux = collect("linux-x86_64");
+        let macos = collect("macos-aarch64");
+        let windows = collect("windows-x86_64");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(linux.is_ok());
+        let err = macos.err().unwrap();
+        assert!(
+            err.to_string().contains("only supported for linux"),
+            "{err}"
+        );
+        assert!(windows.is_err());
+    }
+
+    #[test]
+    fn rejects_native_libraries_with_the_same_file_name() {
+        let dir = env::temp_dir().join(format!("lune-build-native-test-{}", std::process::id()));
+        for lib_dir in ["a", "b"] {
+            std::fs::create_dir_all(dir.join(lib_dir)).unwrap();
+            std::fs::write(dir.join(lib_dir).join("libfoo.so"), "not a library").unwrap();
+        }
+        std::fs::write(dir.join("main.luau"), "return nil").unwrap();
+
+        let collect = |native: Vec<PathBuf>| {
+            futures_lite::future::block_on(BuildPlan::collect(&BuildOptions {
+                input: dir.join("main.luau"),
+                native,
+                ..BuildOptions::default()
+            }))
+        };
+        let same = collect(vec![dir.join("a/libfoo.so"), dir.join("a/libfoo.so")]);
+        let different = collect(vec![dir.join("a/libfoo.so"), dir.join("b/libfoo.so")]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(same.unwrap().native_libs.len(), 1);
+        let err = different.err().unwrap();
+        assert!(err.to_string().contains("same file name"), "{err}");
+    }
+}
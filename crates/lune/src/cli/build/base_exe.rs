@@ -1,15 +1,20 @@
 use std::{
+    env, fmt,
     io::{Cursor, Read},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
 };
 
 use async_fs as fs;
+use async_io::Timer;
 use blocking::unblock;
 
 use crate::standalone::metadata::CURRENT_EXE;
 
 use super::{
     files::write_executable_file_to,
+    output::{BuildEvent, BuildOutputFormat, BuildWarnings},
     result::{BuildError, BuildResult},
     target::{BuildTarget, CACHE_DIR},
 };
@@ -29,38 +34,215 @@ const RELEASE_REQUEST_HEADERS: &[(&str, &str)] = &[
     // ("Accept-Encoding", "gzip"),
 ];
 
+/**
+    The number of times to retry a failed download, if not given using `--download-retries`.
+*/
+pub const DEFAULT_DOWNLOAD_RETRIES: u32 = 3;
+
+/**
+    The delay before the first retry of a failed download, doubled for each retry after.
+*/
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/**
+    Detects the target of a base executable given using `--base-exe`, which is
+    used instead of downloading one, so that the build always matches it.
+
+    A target given using `--target` is only used if it is for the same system as
+    the base executable, and is otherwise ignored with a warning - this means that
+    `--target linux-x86_64-musl` may still be used for a musl base executable.
+*/
+pub async fn base_executable_target(
+    path: &Path,
+    requested: Option<BuildTarget>,
+    warnings: &mut BuildWarnings,
+) -> BuildResult<BuildTarget> {
+    let exe = fs::read(path).await?;
+    let detected = BuildTarget::detect_from_exe(&exe)
+        .ok_or_else(|| BuildError::UnknownBaseExecutable(path.to_path_buf()))?;
+
+    match requested {
+        Some(requested) if requested.os == detected.os && requested.arch == detected.arch => {
+            Ok(requested)
+        }
+        Some(requested) => {
+            warnings.push(format!(
+                "Target '{requested}' is ignored, the base executable at {} is for '{detected}'",
+                path.display()
+            ));
+            Ok(detected)
+        }
+        None => Ok(detected),
+    }
+}
+
 /**
     Discovers the path to the base executable to use for cross-compilation.
 
     If the target is the same as the current system, the current executable is used.
 
-    If no binary exists at the target path, it will attempt to download it from the internet.
+    If no binary exists at the target path, it will attempt to download it from the internet,
+    retrying transient failures up to `retries` times with an exponential backoff in between.
+    Releases are downloaded from GitHub, or from the mirror in `LUNE_BASE_EXE_URL` if set,
+    which is required for musl targets, since those are not part of the official releases.
+
+    If `download` is `false`, the network is never used, and missing binaries fail immediately.
+    Progress is only reported if an output format is given, and downloads are silent otherwise.
 */
-pub async fn get_or_download_base_executable(target: BuildTarget) -> BuildResult<PathBuf> {
+pub async fn get_or_download_base_executable(
+    target: BuildTarget,
+    retries: u32,
+    download: bool,
+    output: Option<BuildOutputFormat>,
+) -> BuildResult<PathBuf> {
     if target.is_current_system() {
         return Ok(CURRENT_EXE.to_path_buf());
     }
     if target.cache_path().exists() {
         return Ok(target.cache_path());
     }
+    if !download {
+        return Err(BuildError::NotCached(target));
+    }
 
     // The target is not cached, we must download it
-    println!("Requested target '{target}' does not exist in cache");
     let version = env!("CARGO_PKG_VERSION");
-    let target_triple = format!("lune-{version}-{target}");
-
-    let release_url = format!(
-        "{base_url}/v{version}/{target_triple}.zip",
-        base_url = "https://github.com/lune-org/lune/releases/download",
-    );
+    let release_name = target.release_name(version);
+    let release_url = match env::var("LUNE_BASE_EXE_URL") {
+        Ok(mirror) if !mirror.trim().is_empty() => target.release_url_from(mirror.trim(), version),
+        _ if !target.has_official_release() => return Err(BuildError::NoOfficialRelease(target)),
+        _ => target.release_url(version),
+    };
 
     // NOTE: This is not entirely accurate, but it is clearer for a user
-    println!("Downloading {target_triple}{}...", target.exe_suffix());
+    if let Some(output) = output {
+        output.emit(&BuildEvent::Downloading {
+            target: target.to_string(),
+            release: format!(
+                "{release_name}{} ({})",
+                target.exe_suffix(),
+                target.rust_target_triple()
+            ),
+        });
+    }
+
+    // Try to download the zip file, retrying any failures that may be transient,
+    // meaning that a missing release will fail immediately without any retries
+    let mut attempt = 0;
+    let zip_bytes = loop {
+        match download_release_zip(&release_url, &target).await {
+            Ok(bytes) => break bytes,
+            Err(BuildError::Download(e)) if attempt < retries => {
+                let delay = INITIAL_RETRY_DELAY * 2u32.saturating_pow(attempt);
+                attempt += 1;
+                if output.is_some() {
+                    eprintln!(
+                        "Download failed, retrying in {}s ({attempt}/{retries})...\n{e}",
+                        delay.as_secs()
+                    );
+                }
+                Timer::after(delay).await;
+            }
+            Err(BuildError::Download(e)) => {
+                return Err(BuildError::DownloadFailed {
+                    url: release_url,
+                    attempts: attempt + 1,
+                    error: e,
+                });
+            }
+            Err(e) => return Err(e),
+        }
+    };
 
+    // Start reading the zip file
+    let zip_file = Cursor::new(zip_bytes);
+
+    // Look for and extract the binary file from the zip file
+    // NOTE: We use spawn_blocking here since reading a zip
+    // archive is a somewhat slow / blocking operation
+    let binary_file_name = target.base_exe_name();
+    let binary_file_handle = unblock(move || {
+        let mut archive = zip::ZipArchive::new(zip_file)?;
+
+        let mut binary = Vec::new();
+        archive
+            .by_name(&binary_file_name)
+            .or(Err(BuildError::ZippedBinaryNotFound(binary_file_name)))?
+            .read_to_end(&mut binary)?;
+
+        Ok::<_, BuildError>(binary)
+    });
+    let binary_file_contents = binary_file_handle.await?;
+
+    // Finally, write the extracted binary to the cache
+    if !CACHE_DIR.exists() {
+        fs::create_dir_all(CACHE_DIR.as_path()).await?;
+    }
+    write_executable_file_to(target.cache_path(), binary_file_contents).await?;
+    if let Some(output) = output {
+        output.emit(&BuildEvent::Downloaded {
+            target: target.to_string(),
+        });
+    }
+
+    Ok(target.cache_path())
+}
+
+/**
+    Gets or downloads the base executables for all of the given targets, the same as
+    [`get_or_download_base_executable`], returning their paths in the same order.
+
+    Every target is fetched on its own thread, so that all of the
+    downloads happen concurrently instead of one after another.
+*/
+pub async fn get_or_download_base_executables(
+    targets: Vec<BuildTarget>,
+    retries: u32,
+    download: bool,
+    output: Option<BuildOutputFormat>,
+) -> BuildResult<Vec<PathBuf>> {
+    let tasks = targets
+        .into_iter()
+        .map(|target| {
+            unblock(move || {
+                async_io::block_on(get_or_download_base_executable(
+                    target, retries, download, output,
+                ))
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut paths = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        paths.push(task.await?);
+    }
+    Ok(paths)
+}
+
+/**
+    Parses the url to download a release from, which may come from a user-given mirror,
+    meaning that it is reported as an error instead of assumed to always be valid.
+*/
+fn parse_release_url<U: FromStr>(release_url: &str) -> BuildResult<U>
+where
+    U::Err: fmt::Display,
+{
+    release_url
+        .parse()
+        .map_err(|e: U::Err| BuildError::InvalidReleaseUrl {
+            url: release_url.to_string(),
+            error: e.to_string(),
+        })
+}
+
+/**
+    Makes a single attempt at downloading the release zip file from the given url.
+*/
+async fn download_release_zip(release_url: &str, target: &BuildTarget) -> BuildResult<Vec<u8>> {
     // Try to request to download the zip file from the target url,
     // making sure transient errors are handled gracefully and
     // with a different error message than "not found"
-    let url = release_url.parse().expect("release url is valid");
+    let url = parse_release_url(release_url)?;
     let headers = RELEASE_REQUEST_HEADERS
         .iter()
         .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
@@ -72,7 +254,7 @@ pub async fn get_or_download_base_executable(target: BuildTarget) -> BuildResult
 
     if !parts.status.is_success() {
         if parts.status.as_u16() == 404 {
-            return Err(BuildError::ReleaseTargetNotFound(target));
+            return Err(BuildError::ReleaseTargetNotFound(target.clone()));
         }
         let body = body.into_bytes();
         return Err(BuildError::Download(format!(
@@ -88,32 +270,5 @@ pub async fn get_or_download_base_executable(target: BuildTarget) -> BuildResult
         )));
     }
 
-    // Start reading the zip file
-    let zip_file = Cursor::new(body.into_bytes());
-
-    // Look for and extract the binary file from the zip file
-    // NOTE: We use spawn_blocking here since reading a zip
-    // archive is a somewhat slow / blocking operation
-    let binary_file_name = format!("lune{}", target.exe_suffix());
-    let binary_file_handle = unblock(move || {
-        let mut archive = zip::ZipArchive::new(zip_file)?;
-
-        let mut binary = Vec::new();
-        archive
-            .by_name(&binary_file_name)
-            .or(Err(BuildError::ZippedBinaryNotFound(binary_file_name)))?
-            .read_to_end(&mut binary)?;
-
-        Ok::<_, BuildError>(binary)
-    });
-    let binary_file_contents = binary_file_handle.await?;
-
-    // Finally, write the extracted binary to the cache
-    if !CACHE_DIR.exists() {
-        fs::create_dir_all(CACHE_DIR.as_path()).await?;
-    }
-    write_executable_file_to(target.cache_path(), binary_file_contents).await?;
-    println!("Downloaded successfully and added to cache");
-
-    Ok(target.cache_path())
+    Ok(body.into_bytes().to_vec())
 }
@@ -0,0 +1,95 @@
+use std::{collections::BTreeMap, fmt, path::PathBuf, str::FromStr, sync::LazyLock};
+
+use async_fs as fs;
+use zip::CompressionMethod;
+
+use super::target::{BuildTarget, CACHE_DIR};
+
+static COMPRESSION_CACHE_PATH: LazyLock<PathBuf> =
+    LazyLock::new(|| CACHE_DIR.join("compression.json"));
+
+/**
+    A compression method for the files embedded in a standalone binary
+*/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BuildCompression {
+    #[default]
+    Stored,
+    Deflate,
+    Zstd,
+}
+
+impl BuildCompression {
+    /**
+        All compression methods that can be selected when building.
+    */
+    pub const ALL: &'static [Self] = &[Self::Stored, Self::Deflate, Self::Zstd];
+
+    pub fn zip_method(self) -> CompressionMethod {
+        match self {
+            Self::Stored => CompressionMethod::Stored,
+            Self::Deflate => CompressionMethod::Deflated,
+            Self::Zstd => CompressionMethod::Zstd,
+        }
+    }
+
+    /**
+        Returns the compression method that was saved for the given
+        target using `--save-compression`, if any, from the target cache.
+    */
+    pub async fn remembered_for(target: &BuildTarget) -> Option<Self> {
+        let remembered = read_remembered().await;
+        remembered.get(&target.to_string())?.parse().ok()
+    }
+
+    /**
+        Saves this compression method as the default for later builds for the given target.
+    */
+    pub async fn remember_for(self, target: &BuildTarget) -> std::io::Result<()> {
+        let mut remembered = read_remembered().await;
+        remembered.insert(target.to_string(), self.to_string());
+
+        let contents = serde_json::to_vec_pretty(&remembered)?;
+        if !CACHE_DIR.exists() {
+            fs::create_dir_all(CACHE_DIR.as_path()).await?;
+        }
+        fs::write(COMPRESSION_CACHE_PATH.as_path(), contents).await
+    }
+}
+
+impl fmt::Display for BuildCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stored => write!(f, "stored"),
+            Self::Deflate => write!(f, "deflate"),
+            Self::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+impl FromStr for BuildCompression {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "stored" | "none" => Ok(Self::Stored),
+            "deflate" | "deflated" => Ok(Self::Deflate),
+            "zstd" | "zstandard" => Ok(Self::Zstd),
+            _ => Err(format!(
+                "invalid compression method, expected one of: {}",
+                Self::ALL
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+async fn read_remembered() -> BTreeMap<String, String> {
+    fs::read(COMPRESSION_CACHE_PATH.as_path())
+        .await
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
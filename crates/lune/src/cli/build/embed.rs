@@ -0,0 +1,320 @@
+use std::{
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{Context, Result, bail};
+use glob::{MatchOptions, Pattern};
+
+use super::output::BuildWarnings;
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/**
+    A gitignore-style pattern given using `--embed-exclude`, matched
+    against the names that embedded files are stored under in the archive.
+
+    - Patterns without a slash, such as `node_modules` or `*.map`,
+      match a file or directory with that name at any depth.
+    - Patterns with a slash, such as `assets/raw`, match relative to the
+      archive root instead, and a leading slash is allowed but not required.
+    - Patterns with a trailing slash, such as `cache/`, only match directories.
+
+    Excluding a directory also excludes everything inside of it.
+*/
+#[derive(Debug, Clone)]
+pub struct EmbedExclude {
+    pattern: Pattern,
+    anchored: bool,
+    dir_only: bool,
+}
+
+impl EmbedExclude {
+    /**
+        Returns whether the file or directory with the given archive name is excluded.
+    */
+    pub fn matches(&self, name: &str, is_dir: bool) -> bool {
+        let parts = name.split('/').collect::<Vec<_>>();
+        (1..=parts.len()).any(|count| {
+            let is_parent = count < parts.len();
+            if self.dir_only && !is_parent && !is_dir {
+                return false;
+            }
+            if self.anchored {
+                let prefix = parts[..count].join("/");
+                self.pattern.matches_with(&prefix, MATCH_OPTIONS)
+            } else {
+                self.pattern.matches_with(parts[count - 1], MATCH_OPTIONS)
+            }
+        })
+    }
+}
+
+impl FromStr for EmbedExclude {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let dir_only = s.ends_with('/');
+        let trimmed = s.trim_end_matches('/');
+        let anchored = trimmed.contains('/');
+        let trimmed = trimmed.trim_start_matches('/');
+        if trimmed.is_empty() {
+            return Err(format!("invalid exclude pattern '{s}', pattern is empty"));
+        }
+        let pattern =
+            Pattern::new(trimmed).map_err(|e| format!("invalid exclude pattern '{s}': {e}"))?;
+        Ok(Self {
+            pattern,
+            anchored,
+            dir_only,
+        })
+    }
+}
+
+/**
+    A file or directory given using `--embed-as src:dest`, stored under the given
+    destination in the archive instead of under its path relative to the embed base.
+
+    Directories have their contents stored with the destination as a prefix, such as
+    `assets:/www` storing `assets/index.html` as `www/index.html`, while files are stored
+    with the destination as their full name. A leading slash in the destination is
+    allowed but not required, since names are always relative to the archive root.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbedAs {
+    pub source: PathBuf,
+    pub dest: String,
+}
+
+impl EmbedAs {
+    /**
+        Creates a new mapping, validating that the destination stays inside of the archive.
+    */
+    pub fn new(source: impl Into<PathBuf>, dest: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        for part in dest.split(['/', '\\']) {
+            match part {
+                "" | "." => {}
+                ".." => {
+                    return Err(format!(
+                        "embed destination '{dest}' escapes the archive root"
+                    ));
+                }
+                part => parts.push(part),
+            }
+        }
+        Ok(Self {
+            source: source.into(),
+            dest: parts.join("/"),
+        })
+    }
+
+    /**
+        Returns the name that the given path, which is either the source
+        itself or a path inside of it, is stored under in the archive.
+    */
+    pub fn name_for(&self, path: &Path) -> Result<String> {
+        let Ok(relative) = path.strip_prefix(&self.source) else {
+            bail!(
+                "embedded path '{}' is outside of '{}'",
+                path.display(),
+                self.source.display()
+            );
+        };
+
+        let mut parts = Vec::new();
+        if !self.dest.is_empty() {
+            parts.push(self.dest.clone());
+        }
+        for component in relative.components() {
+            match component {
+                Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+                Component::CurDir => {}
+                _ => bail!(
+                    "embedded path '{}' escapes the archive root",
+                    path.display()
+                ),
+            }
+        }
+        if parts.is_empty() {
+            bail!(
+                "embedded path '{}' needs a destination name, such as '{}:name'",
+                path.display(),
+                self.source.display()
+            );
+        }
+
+        Ok(parts.join("/"))
+    }
+}
+
+impl FromStr for EmbedAs {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The last colon separates the two, so that Windows paths such as `C:\assets` work
+        match s.rsplit_once(':') {
+            Some((source, dest)) if !source.is_empty() => Self::new(source, dest),
+            _ => Err(format!(
+                "invalid embed mapping '{s}', expected the format src:dest"
+            )),
+        }
+    }
+}
+
+/**
+    Collects the files to embed for the given file or directory, walking directories
+    recursively, along with the names that they are stored under in the archive.
+
+    Paths whose names are excluded are skipped, and paths that do not exist are warned about.
+*/
+pub fn collect_embed_files(
+    path: &Path,
+    name_for: impl Fn(&Path) -> Result<String>,
+    excludes: &[EmbedExclude],
+    files: &mut Vec<(PathBuf, String)>,
+    warnings: &mut BuildWarnings,
+) -> Result<()> {
+    let skipped = |path: &Path, is_dir: bool| {
+        name_for(path).is_ok_and(|name| is_excluded(excludes, &name, is_dir))
+    };
+    if path.is_dir() {
+        // Sorted, so that files are embedded in the same order on every
+        // platform, and building the same files gives the same binary
+        let walker = walkdir::WalkDir::new(path)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| !skipped(entry.path(), entry.file_type().is_dir()));
+        for entry in walker {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                let name = name_for(entry.path())?;
+                files.push((entry.into_path(), name));
+            }
+        }
+    } else if path.is_file() {
+        if !skipped(path, false) {
+            files.push((path.to_path_buf(), name_for(path)?));
+        }
+    } else {
+        warnings.push(format!(
+            "Path '{}' does not exist or is not readable, skipping...",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+/**
+    Expands any glob patterns given using `--embed`, such as `assets/**/*.png`.
+
+    Paths without any glob characters, or that exist exactly as given,
+    are returned unchanged, and patterns that match nothing are warned about.
+*/
+pub fn expand_embed_globs(paths: &[PathBuf], warnings: &mut BuildWarnings) -> Result<Vec<PathBuf>> {
+    let mut expanded = Vec::new();
+    for path in paths {
+        let pattern = path
+            .to_str()
+            .filter(|s| s.contains(['*', '?', '[']) && !path.exists());
+        let Some(pattern) = pattern else {
+            expanded.push(path.clone());
+            continue;
+        };
+
+        let matches = glob::glob_with(pattern, MATCH_OPTIONS)
+            .with_context(|| format!("invalid embed pattern '{pattern}'"))?
+            .collect::<Result<Vec<_>, _>>()?;
+        if matches.is_empty() {
+            warnings.push(format!(
+                "Pattern '{pattern}' did not match any files, skipping..."
+            ));
+        }
+        expanded.extend(matches);
+    }
+    Ok(expanded)
+}
+
+/**
+    Returns whether the given archive name is excluded by any of the given patterns.
+*/
+pub fn is_excluded(excludes: &[EmbedExclude], name: &str, is_dir: bool) -> bool {
+    excludes.iter().any(|exclude| exclude.matches(name, is_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn excluded(pattern: &str, name: &str, is_dir: bool) -> bool {
+        let exclude = pattern.parse::<EmbedExclude>().unwrap();
+        exclude.matches(name, is_dir)
+    }
+
+    #[test]
+    fn matches_names_at_any_depth() {
+        assert!(excluded("node_modules", "node_modules", true));
+        assert!(excluded(
+            "node_modules",
+            "web/node_modules/pkg/index.js",
+            false
+        ));
+        assert!(excluded("*.map", "dist/app.js.map", false));
+        assert!(!excluded("*.map", "dist/app.js", false));
+        assert!(excluded(".git", ".git/HEAD", false));
+    }
+
+    #[test]
+    fn matches_anchored_patterns() {
+        assert!(excluded("assets/raw", "assets/raw/image.psd", false));
+        assert!(excluded("/assets/raw", "assets/raw", true));
+        assert!(!excluded("assets/raw", "lib/assets/raw/image.psd", false));
+        assert!(excluded("assets/*.psd", "assets/image.psd", false));
+        assert!(!excluded("assets/*.psd", "assets/nested/image.psd", false));
+        assert!(excluded(
+            "assets/**/*.psd",
+            "assets/nested/image.psd",
+            false
+        ));
+    }
+
+    #[test]
+    fn maps_embedded_paths_to_destinations() {
+        let embed_as = "assets:/www/".parse::<EmbedAs>().unwrap();
+        assert_eq!(embed_as.source, PathBuf::from("assets"));
+        assert_eq!(embed_as.dest, "www");
+        let name = |path: &str| embed_as.name_for(Path::new(path)).unwrap();
+        assert_eq!(name("assets/index.html"), "www/index.html");
+        assert_eq!(name("assets/css/app.css"), "www/css/app.css");
+        assert_eq!(name("assets"), "www");
+        assert!(embed_as.name_for(Path::new("other/file.txt")).is_err());
+
+        let file = "C:\\data\\config.json:settings.json"
+            .parse::<EmbedAs>()
+            .unwrap();
+        assert_eq!(file.source, PathBuf::from("C:\\data\\config.json"));
+        assert_eq!(file.dest, "settings.json");
+
+        // Directories may be stored at the root, but files always need a name
+        let root = "assets:/".parse::<EmbedAs>().unwrap();
+        assert_eq!(
+            root.name_for(Path::new("assets/index.html")).unwrap(),
+            "index.html"
+        );
+        assert!(root.name_for(Path::new("assets")).is_err());
+
+        assert!("assets".parse::<EmbedAs>().is_err());
+        assert!(":www".parse::<EmbedAs>().is_err());
+        assert!("assets:../www".parse::<EmbedAs>().is_err());
+    }
+
+    #[test]
+    fn matches_directory_only_patterns() {
+        assert!(excluded("cache/", "cache", true));
+        assert!(excluded("cache/", "lib/cache/data.json", false));
+        assert!(!excluded("cache/", "lib/cache", false));
+        assert!("/".parse::<EmbedExclude>().is_err());
+    }
+}
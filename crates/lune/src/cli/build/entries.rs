@@ -0,0 +1,57 @@
+use std::{path::PathBuf, str::FromStr};
+
+/**
+    A named entry point for a standalone binary, given using `--entry name=path`.
+
+    The built binary runs the entry point whose name is given as its first
+    argument, and falls back to the main input file when given no arguments.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildEntry {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl BuildEntry {
+    /**
+        Creates a new named entry point, validating its name.
+
+        Names may only contain ASCII letters, digits, dashes and underscores, and
+        must not start with a dash, so that they are never mistaken for flags.
+    */
+    pub fn new(name: impl Into<String>, path: impl Into<PathBuf>) -> Result<Self, String> {
+        let name = name.into();
+        if name.is_empty() {
+            return Err("entry point name must not be empty".to_string());
+        }
+        if name.starts_with('-') {
+            return Err(format!(
+                "entry point name '{name}' must not start with a dash"
+            ));
+        }
+        if !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        {
+            return Err(format!(
+                "entry point name '{name}' may only contain letters, digits, dashes and underscores"
+            ));
+        }
+        Ok(Self {
+            name,
+            path: path.into(),
+        })
+    }
+}
+
+impl FromStr for BuildEntry {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((name, path)) if !path.is_empty() => Self::new(name.trim(), path),
+            _ => Err(format!(
+                "invalid entry point '{s}', expected the format name=path"
+            )),
+        }
+    }
+}
@@ -1,8 +1,23 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    path::{Component, Path, PathBuf},
+};
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use async_fs as fs;
 use futures_lite::prelude::*;
+use lune_utils::path::clean_path_and_make_absolute;
+
+use super::target::BuildTarget;
+
+/**
+    Returns whether the given path has a source file extension, such as `.lua` or `.luau`.
+*/
+pub fn has_source_file_ext(path: impl AsRef<Path>) -> bool {
+    path.as_ref()
+        .extension()
+        .is_some_and(|ext| matches!(ext.to_str(), Some("lua" | "luau")))
+}
 
 /**
     Removes the source file extension from the given path, if it has one.
@@ -10,10 +25,7 @@ use futures_lite::prelude::*;
     A source file extension is an extension such as `.lua` or `.luau`.
 */
 pub fn remove_source_file_ext(path: &Path) -> PathBuf {
-    if path
-        .extension()
-        .is_some_and(|ext| matches!(ext.to_str(), Some("lua" | "luau")))
-    {
+    if has_source_file_ext(path) {
         path.with_extension("")
     } else {
         path.to_path_buf()
@@ -21,13 +33,183 @@ pub fn remove_source_file_ext(path: &Path) -> PathBuf {
 }
 
 /**
-    Writes the given bytes to a file at the specified path,
-    and makes sure it has permissions to be executed.
+    Returns the path that the binary for the given target is written to, given the output path.
+
+    When building for more than one target, the target is appended to the file name, such
+    as `app-linux-x86_64` or `app-windows-x86_64.exe`, so that outputs never overwrite each other.
 */
-pub async fn write_executable_file_to(
-    path: impl AsRef<Path>,
-    bytes: impl AsRef<[u8]>,
-) -> Result<(), std::io::Error> {
+pub fn output_path_for(output: &Path, target: &BuildTarget, multiple_targets: bool) -> PathBuf {
+    if !multiple_targets {
+        return output.with_extension(target.exe_extension());
+    }
+    let mut path = output.with_extension("");
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(format!("-{target}"));
+    path.set_file_name(file_name);
+    path.with_extension(target.exe_extension())
+}
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16LE_BOM: &[u8] = b"\xFF\xFE";
+const UTF16BE_BOM: &[u8] = b"\xFE\xFF";
+
+/**
+    Decodes the source code of a script to UTF-8 before compiling it, stripping
+    a leading UTF-8 byte order mark, and transcoding scripts that start with a
+    UTF-16 byte order mark, such as ones saved by some editors on Windows.
+
+    Source code without a byte order mark is returned unchanged.
+*/
+pub fn decode_source(bytes: Vec<u8>) -> Result<Vec<u8>> {
+    if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+        return Ok(rest.to_vec());
+    }
+    let (encoding, rest, from_bytes): (_, _, fn([u8; 2]) -> u16) =
+        if let Some(rest) = bytes.strip_prefix(UTF16LE_BOM) {
+            ("UTF-16LE", rest, u16::from_le_bytes)
+        } else if let Some(rest) = bytes.strip_prefix(UTF16BE_BOM) {
+            ("UTF-16BE", rest, u16::from_be_bytes)
+        } else {
+            return Ok(bytes);
+        };
+
+    if rest.len() % 2 != 0 {
+        bail!(
+            "file starts with a {encoding} byte order mark, but has an odd number of bytes, \
+            meaning that it is likely not {encoding} text - try saving it as UTF-8 instead"
+        );
+    }
+    let units = rest
+        .chunks_exact(2)
+        .map(|pair| from_bytes([pair[0], pair[1]]));
+    match char::decode_utf16(units).collect::<Result<String, _>>() {
+        Ok(source) => Ok(source.into_bytes()),
+        Err(e) => bail!(
+            "file starts with a {encoding} byte order mark, but contains the unpaired surrogate {:#06x}, \
+            meaning that it is likely not {encoding} text - try saving it as UTF-8 instead",
+            e.unpaired_surrogate()
+        ),
+    }
+}
+
+/**
+    Derives the name that an embedded file is stored under in the standalone archive.
+
+    Names are relative to the given base directory, or the current working directory
+    if none was given, and always use forward slashes as separators. Paths that are
+    not inside of the base directory would escape the archive root, and are rejected.
+*/
+pub fn embedded_file_name(path: &Path, base: Option<&Path>) -> Result<String> {
+    let base = clean_path_and_make_absolute(base.unwrap_or(Path::new(".")));
+    let absolute = clean_path_and_make_absolute(path);
+
+    let Ok(relative) = absolute.strip_prefix(&base) else {
+        bail!(
+            "embedded path '{}' is outside of the base directory '{}', \
+            use --embed-base to choose a directory that contains it",
+            path.display(),
+            base.display()
+        );
+    };
+
+    let mut parts = Vec::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy()),
+            Component::CurDir => {}
+            _ => bail!(
+                "embedded path '{}' escapes the archive root",
+                path.display()
+            ),
+        }
+    }
+    if parts.is_empty() {
+        bail!(
+            "embedded path '{}' can not be the base directory itself",
+            path.display()
+        );
+    }
+
+    Ok(parts.join("/"))
+}
+
+/**
+    Tracks the names of files embedded in a standalone archive, and their source paths.
+
+    Names are compared case-insensitively, since the same archive
+    may be built or read on case-insensitive filesystems.
+*/
+#[derive(Debug, Default)]
+pub struct EmbeddedNames {
+    sources: HashMap<String, (String, PathBuf)>,
+    entry_key: String,
+}
+
+impl EmbeddedNames {
+    /**
+        Creates a new set of embedded names, reserving the
+        name of the main entry point for the given source path.
+    */
+    pub fn new(entry_name: &str, entry_path: &Path) -> Self {
+        let mut this = Self {
+            entry_key: entry_name.to_lowercase(),
+            ..Self::default()
+        };
+        this.sources.insert(
+            this.entry_key.clone(),
+            (entry_name.to_string(), entry_path.to_path_buf()),
+        );
+        this
+    }
+
+    /**
+        Adds a file with the given name and source path.
+
+        Returns `false` if the exact same file was already added under the same name.
+
+        # Errors
+
+        Errors if a different source path was already added under the same name.
+    */
+    pub fn insert(&mut self, name: &str, path: &Path) -> Result<bool> {
+        match self.sources.entry(name.to_lowercase()) {
+            Entry::Vacant(entry) => {
+                entry.insert((name.to_string(), path.to_path_buf()));
+                Ok(true)
+            }
+            Entry::Occupied(entry) => {
+                let (existing_name, existing_path) = entry.get();
+                if existing_name == name
+                    && clean_path_and_make_absolute(existing_path)
+                        == clean_path_and_make_absolute(path)
+                {
+                    return Ok(false);
+                }
+                if *entry.key() == self.entry_key {
+                    bail!(
+                        "embedded file '{}' would be stored as '{existing_name}', which is \
+                        where the input file '{}' is stored - embed it under another name, \
+                        or store the input file under another name using --entry-name",
+                        path.display(),
+                        existing_path.display(),
+                    );
+                }
+                bail!(
+                    "embedded files '{}' and '{}' would both be stored as '{}' in the archive",
+                    existing_path.display(),
+                    path.display(),
+                    existing_name
+                );
+            }
+        }
+    }
+}
+
+/**
+    Creates a file at the specified path, or truncates it if it already
+    exists, and makes sure it has permissions to be executed.
+*/
+pub async fn create_executable_file(path: impl AsRef<Path>) -> Result<fs::File, std::io::Error> {
     let mut options = fs::OpenOptions::new();
     options.write(true).create(true).truncate(true);
 
@@ -37,9 +219,96 @@ pub async fn write_executable_file_to(
         options.mode(0o755); // Read & execute for all, write for owner
     }
 
-    let mut file = options.open(path).await?;
+    options.open(path).await
+}
+
+/**
+    Writes the given bytes to a file at the specified path,
+    and makes sure it has permissions to be executed.
+*/
+pub async fn write_executable_file_to(
+    path: impl AsRef<Path>,
+    bytes: impl AsRef<[u8]>,
+) -> Result<(), std::io::Error> {
+    let mut file = create_executable_file(path).await?;
     file.write_all(bytes.as_ref()).await?;
     file.flush().await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_files_stored_as_the_entry_point() {
+        let mut names = EmbeddedNames::new("init.luau", Path::new("main.luau"));
+        assert!(!names.insert("init.luau", Path::new("main.luau")).unwrap());
+        let err = names
+            .insert("Init.luau", Path::new("lib/init.luau"))
+            .unwrap_err();
+        assert!(err.to_string().contains("--entry-name"), "{err}");
+
+        let mut names = EmbeddedNames::new("main.luau", Path::new("main.luau"));
+        assert!(
+            names
+                .insert("init.luau", Path::new("lib/init.luau"))
+                .unwrap()
+        );
+        let err = names
+            .insert("lib.luau", Path::new("lib.luau"))
+            .and_then(|_| names.insert("lib.luau", Path::new("other/lib.luau")))
+            .unwrap_err();
+        assert!(!err.to_string().contains("--entry-name"), "{err}");
+    }
+
+    #[test]
+    fn appends_targets_to_output_paths() {
+        let linux: BuildTarget = "linux-x86_64".parse().unwrap();
+        let windows: BuildTarget = "windows-x86_64".parse().unwrap();
+        let output = Path::new("dist/app");
+        assert_eq!(output_path_for(output, &linux, false), output);
+        assert_eq!(
+            output_path_for(output, &windows, false),
+            Path::new("dist/app.exe")
+        );
+        assert_eq!(
+            output_path_for(output, &linux, true),
+            Path::new("dist/app-linux-x86_64")
+        );
+        assert_eq!(
+            output_path_for(Path::new("dist/app.exe"), &windows, true),
+            Path::new("dist/app-windows-x86_64.exe")
+        );
+    }
+
+    #[test]
+    fn decodes_sources_with_byte_order_marks() {
+        let source = "print('héllo')";
+        let utf16 = |bom: &[u8], to_bytes: fn(u16) -> [u8; 2]| {
+            let mut bytes = bom.to_vec();
+            bytes.extend(source.encode_utf16().flat_map(to_bytes));
+            bytes
+        };
+
+        assert_eq!(decode_source(source.into()).unwrap(), source.as_bytes());
+        assert_eq!(
+            decode_source([UTF8_BOM, source.as_bytes()].concat()).unwrap(),
+            source.as_bytes()
+        );
+        assert_eq!(
+            decode_source(utf16(UTF16LE_BOM, u16::to_le_bytes)).unwrap(),
+            source.as_bytes()
+        );
+        assert_eq!(
+            decode_source(utf16(UTF16BE_BOM, u16::to_be_bytes)).unwrap(),
+            source.as_bytes()
+        );
+
+        let err = decode_source([UTF16LE_BOM, b"abc"].concat()).unwrap_err();
+        assert!(err.to_string().contains("odd number of bytes"), "{err}");
+        let err = decode_source([UTF16BE_BOM, b"\xD8\x00\x00a"].concat()).unwrap_err();
+        assert!(err.to_string().contains("UTF-16BE"), "{err}");
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use async_fs as fs;
+use serde::Deserialize;
+
+use super::{BuildCommand, embed::EmbedAs, entries::BuildEntry};
+
+/**
+    A build manifest, specifying the same options as the `build` command flags.
+
+    Relative paths in the manifest are resolved relative to the directory
+    containing the manifest, rather than the current working directory.
+*/
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct BuildManifest {
+    #[serde(alias = "entry")]
+    input: Option<PathBuf>,
+    entry_name: Option<String>,
+    output: Option<PathBuf>,
+    target: Option<String>,
+    targets: Vec<String>,
+    embed: Vec<PathBuf>,
+    /// Destinations in the archive, keyed by the file or directory stored there
+    embed_as: BTreeMap<PathBuf, String>,
+    embed_exclude: Vec<String>,
+    embed_base: Option<PathBuf>,
+    compression: Option<String>,
+    save_compression: bool,
+    embed_size_warning: Option<u64>,
+    embed_mode: Option<String>,
+    config: Option<PathBuf>,
+    download_retries: Option<u32>,
+    no_download: bool,
+    base_exe: Option<PathBuf>,
+    check_requires: Option<bool>,
+    version_flag: Option<bool>,
+    remove_globals: Vec<String>,
+    native: Vec<PathBuf>,
+    entries: BTreeMap<String, PathBuf>,
+    minify: bool,
+    compress_binary: bool,
+}
+
+impl BuildManifest {
+    /**
+        Reads and parses the build manifest at the given path.
+    */
+    pub async fn read(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .await
+            .with_context(|| format!("failed to read build manifest '{}'", path.display()))?;
+        let mut manifest: Self = toml::from_str(&contents)
+            .with_context(|| format!("failed to parse build manifest '{}'", path.display()))?;
+
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let paths = manifest
+            .input
+            .iter_mut()
+            .chain(manifest.output.iter_mut())
+            .chain(manifest.embed.iter_mut())
+            .chain(manifest.embed_base.iter_mut())
+            .chain(manifest.config.iter_mut())
+            .chain(manifest.base_exe.iter_mut())
+            .chain(manifest.native.iter_mut())
+            .chain(manifest.entries.values_mut());
+        for path in paths {
+            *path = dir.join(&*path);
+        }
+        manifest.embed_as = manifest
+            .embed_as
+            .into_iter()
+            .map(|(source, dest)| (dir.join(source), dest))
+            .collect();
+
+        Ok(manifest)
+    }
+
+    /**
+        Fills in any options not given to the command using values from this manifest,
+        meaning that flags given on the command line always take precedence.
+    */
+    pub fn apply_to(self, mut cmd: BuildCommand) -> Result<BuildCommand> {
+        // Same as the flags, which conflict with each other on the command line
+        if self.no_download && self.download_retries.is_some() {
+            bail!("download-retries can not be used together with no-download in manifest");
+        }
+        cmd.input = cmd.input.or(self.input);
+        cmd.entry_name = cmd.entry_name.or(self.entry_name);
+        cmd.output = cmd.output.or(self.output);
+        if cmd.targets.is_empty() {
+            cmd.targets = self
+                .target
+                .iter()
+                .chain(&self.targets)
+                .map(|target| target.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|e| anyhow!("invalid target in manifest: {e}"))?;
+        }
+        if cmd.embed.is_empty() {
+            cmd.embed = self.embed;
+        }
+        if cmd.embed_as.is_empty() {
+            cmd.embed_as = self
+                .embed_as
+                .into_iter()
+                .map(|(source, dest)| EmbedAs::new(source, &dest))
+                .collect::<Result<_, _>>()
+                .map_err(|e| anyhow!("invalid embed mapping in manifest: {e}"))?;
+        }
+        if cmd.embed_excludes.is_empty() {
+            cmd.embed_excludes = self
+                .embed_exclude
+                .iter()
+                .map(|pattern| pattern.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|e| anyhow!("invalid exclude pattern in manifest: {e}"))?;
+        }
+        cmd.embed_base = cmd.embed_base.or(self.embed_base);
+        if cmd.compression.is_none()
+            && let Some(compression) = self.compression
+        {
+            cmd.compression = Some(
+                compression
+                    .parse()
+                    .map_err(|e| anyhow!("invalid compression in manifest: {e}"))?,
+            );
+        }
+        cmd.embed_size_warning = cmd.embed_size_warning.or(self.embed_size_warning);
+        if cmd.embed_mode.is_none()
+            && let Some(embed_mode) = self.embed_mode
+        {
+            cmd.embed_mode = Some(
+                embed_mode
+                    .parse()
+                    .map_err(|e| anyhow!("invalid embed mode in manifest: {e}"))?,
+            );
+        }
+        cmd.config = cmd.config.or(self.config);
+        // A download option given on the command line also overrides the
+        // conflicting one in the manifest, instead of failing the build
+        if !cmd.no_download {
+            cmd.download_retries = cmd.download_retries.or(self.download_retries);
+        }
+        if cmd.download_retries.is_none() {
+            cmd.no_download = cmd.no_download || self.no_download;
+        }
+        cmd.base_exe = cmd.base_exe.or(self.base_exe);
+        cmd.check_requires = cmd.check_requires.or(self.check_requires);
+        cmd.version_flag = cmd.version_flag.or(self.version_flag);
+        if cmd.remove_globals.is_empty() {
+            cmd.remove_globals = self.remove_globals;
+        }
+        if cmd.native.is_empty() {
+            cmd.native = self.native;
+        }
+        if cmd.entries.is_empty() {
+            cmd.entries = self
+                .entries
+                .into_iter()
+                .map(|(name, path)| BuildEntry::new(name, path))
+                .collect::<Result<_, _>>()
+                .map_err(|e| anyhow!("invalid entry point in manifest: {e}"))?;
+        }
+        cmd.minify = cmd.minify || self.minify;
+        cmd.compress_binary = cmd.compress_binary || self.compress_binary;
+        cmd.save_compression = cmd.save_compression || self.save_compression;
+        Ok(cmd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    fn apply(manifest: &str, args: &[&str]) -> Result<BuildCommand> {
+        let manifest: BuildManifest = toml::from_str(manifest)?;
+        let cmd = BuildCommand::try_parse_from(["build"].iter().chain(args))?;
+        manifest.apply_to(cmd)
+    }
+
+    #[test]
+    fn rejects_conflicting_download_options() {
+        assert!(apply("no-download = true\ndownload-retries = 5", &[]).is_err());
+
+        let cmd = apply("download-retries = 5", &["--no-download"]).unwrap();
+        assert!(cmd.no_download);
+        assert_eq!(cmd.download_retries, None);
+
+        let cmd = apply("no-download = true", &["--download-retries", "5"]).unwrap();
+        assert!(!cmd.no_download);
+        assert_eq!(cmd.download_retries, Some(5));
+    }
+}
@@ -1,119 +1,335 @@
 use std::{path::PathBuf, process::ExitCode};
 
-use anyhow::{Context, Result, bail};
-use async_fs as fs;
+use anyhow::{Result, bail};
 use clap::Parser;
-use console::style;
 
-use crate::standalone::metadata::Metadata;
+use crate::standalone::metadata::EmbedMode;
 
+mod api;
 mod base_exe;
+mod compression;
+mod embed;
+mod entries;
 mod files;
+mod manifest;
+mod output;
+mod packing;
+mod requires;
 mod result;
 mod target;
 
-use self::base_exe::get_or_download_base_executable;
-use self::files::{remove_source_file_ext, write_executable_file_to};
+pub use self::api::{BuildOptions, BuildOutput, build_standalone};
+
+use self::api::BuildPlan;
+use self::compression::BuildCompression;
+use self::embed::{EmbedAs, EmbedExclude};
+use self::entries::BuildEntry;
+use self::manifest::BuildManifest;
+use self::output::{BuildEvent, BuildOutputFormat, DryRunFile};
+use self::requires::format_require_tree;
 use self::target::BuildTarget;
 
 /// Build a standalone executable
 #[derive(Debug, Clone, Parser)]
 pub struct BuildCommand {
-    /// The path to the input file
-    pub input: PathBuf,
+    /// The path to the input file - may instead be given in the manifest
+    pub input: Option<PathBuf>,
+
+    /// The name to store the input file under in the executable, which runs when
+    /// no named entry point is given - defaults to `init.luau`, and may be changed
+    /// to embed a different file as `init.luau`
+    #[clap(long)]
+    pub entry_name: Option<String>,
+
+    /// A TOML manifest to read build options from, with
+    /// any options given as flags taking precedence
+    #[clap(short, long)]
+    pub manifest: Option<PathBuf>,
 
     /// The path to the output file - defaults to the
     /// input file path with an executable extension
     #[clap(short, long)]
     pub output: Option<PathBuf>,
 
-    /// The target to compile for in the format `os-arch` -
-    /// defaults to the os and arch of the current system
-    #[clap(short, long)]
-    pub target: Option<BuildTarget>,
+    /// The target to compile for in the format `os-arch`, or `linux-arch-musl`
+    /// for static Linux binaries - defaults to the current system, and may be
+    /// given more than once to build for several targets, in which case the
+    /// target is appended to the name of each output file - musl targets are not
+    /// in the official releases, and need `--base-exe` or `LUNE_BASE_EXE_URL`
+    #[clap(short, long = "target")]
+    pub targets: Vec<BuildTarget>,
 
-    /// A list of files or directories to embed in the executable
+    /// A list of files, directories or glob patterns to embed in the executable
     #[clap(short, long)]
     pub embed: Vec<PathBuf>,
+
+    /// Files or directories to embed under a different name in the archive, in the
+    /// format `src:dest`, such as `assets:/www` to store `assets/index.html` as
+    /// `www/index.html`, or `config.json:data/settings.json` for a single file
+    #[clap(long)]
+    pub embed_as: Vec<EmbedAs>,
+
+    /// Gitignore-style patterns for embedded files to leave out, such as `node_modules`
+    /// or `*.map`, matched against the names that files are stored under
+    #[clap(long = "embed-exclude")]
+    pub embed_excludes: Vec<EmbedExclude>,
+
+    /// The directory that embedded file names are relative to -
+    /// defaults to the current working directory
+    #[clap(long)]
+    pub embed_base: Option<PathBuf>,
+
+    /// The compression method to use for embedded files, either `stored`, `deflate`
+    /// or `zstd` - defaults to the method saved for the target, or `stored` if none
+    #[clap(long)]
+    pub compression: Option<BuildCompression>,
+
+    /// Save the method given using `--compression` as the default
+    /// for later builds for the same target
+    #[clap(long)]
+    pub save_compression: bool,
+
+    /// Warn about any embedded file larger than this many megabytes - defaults to 50
+    #[clap(long)]
+    pub embed_size_warning: Option<u64>,
+
+    /// How to store embedded files in the executable - either `trailer`, appending
+    /// them to the end, or `section`, storing them in a dedicated binary section
+    /// so that the executable stays well-formed for tools that inspect or strip it,
+    /// which is only supported for Linux targets - defaults to `trailer`
+    #[clap(long)]
+    pub embed_mode: Option<EmbedMode>,
+
+    /// A JSON file to embed in the executable, which will
+    /// be available to the script as `process.buildConfig`
+    #[clap(long)]
+    pub config: Option<PathBuf>,
+
+    /// How many times to retry downloading a base executable for the target
+    /// if it fails - defaults to the `LUNE_DOWNLOAD_RETRIES` environment
+    /// variable if set, and otherwise to 3 retries
+    #[clap(long, conflicts_with = "no_download")]
+    pub download_retries: Option<u32>,
+
+    /// Never download a base executable, failing immediately if the one for the
+    /// target is not already cached, instead of trying to reach the network or
+    /// any mirror - useful for offline machines, together with `--base-exe`
+    #[clap(long)]
+    pub no_download: bool,
+
+    /// A base executable to build from instead of downloading one, such as a Lune
+    /// binary fetched ahead of time - the target is detected from it, and any given
+    /// target for a different system is ignored. Downloads may instead use a mirror
+    /// of the GitHub releases given in the `LUNE_BASE_EXE_URL` environment variable
+    #[clap(long)]
+    pub base_exe: Option<PathBuf>,
+
+    /// Whether to check that all requires resolve to embedded
+    /// files or standard libraries, failing the build if not - defaults to true
+    #[clap(long, action = clap::ArgAction::Set)]
+    pub check_requires: Option<bool>,
+
+    /// Whether the built executable should print the version of Lune
+    /// it was built with when given `--lune-version` as its first argument - defaults to true
+    #[clap(long, action = clap::ArgAction::Set)]
+    pub version_flag: Option<bool>,
+
+    /// Globals to remove from the built binary, such as `task.spawn`, or standard
+    /// libraries given by their require path, such as `@lune/process`
+    #[clap(long = "remove-global")]
+    pub remove_globals: Vec<String>,
+
+    /// Native libraries to embed, which are extracted to a temporary directory
+    /// given in `process.env.LUNE_NATIVE_DIR` before the script runs
+    #[clap(long)]
+    pub native: Vec<PathBuf>,
+
+    /// Additional named entry points in the format `name=path`, where the built
+    /// executable runs the entry point given as its first argument, and the
+    /// input file when given no arguments
+    #[clap(long = "entry")]
+    pub entries: Vec<BuildEntry>,
+
+    /// Strip debug info from the compiled bytecode, making the executable smaller
+    /// and hiding the structure of the source code, at the cost of runtime
+    /// errors no longer including line numbers
+    #[clap(long)]
+    pub minify: bool,
+
+    /// Compress the base executable using UPX, which must be installed, making the
+    /// executable much smaller to distribute - it decompresses itself into memory
+    /// every time it runs, so startup gets slower, and this is not supported for
+    /// macOS targets or together with `--embed-mode section`
+    #[clap(long)]
+    pub compress_binary: bool,
+
+    /// Print the name and size of every file that would be embedded, and
+    /// exit without downloading the base executable or writing any output -
+    /// reported as a single `dry-run` event when using `--output-format json`
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Print the require graph of the embedded files as a tree, and exit without
+    /// building - this can not be used together with `--output-format json`
+    #[clap(long)]
+    pub print_graph: bool,
+
+    /// How to report progress, either `human` for styled text, or `json` for
+    /// newline-delimited JSON events on stdout, including any error that
+    /// made the build fail, for use by other tools - defaults to `human`
+    #[clap(long)]
+    pub output_format: Option<BuildOutputFormat>,
 }
 
 impl BuildCommand {
     pub async fn run(self) -> Result<ExitCode> {
-        // Derive target spec to use, or default to the current host system
-        let target = self.target.unwrap_or_else(BuildTarget::current_system);
-
-        // Derive paths to use, and make sure the output path is
-        // not the same as the input, so that we don't overwrite it
-        let output_path = self
-            .output
-            .clone()
-            .unwrap_or_else(|| remove_source_file_ext(&self.input));
-        let output_path = output_path.with_extension(target.exe_extension());
-        if output_path == self.input {
-            if self.output.is_some() {
-                bail!("output path cannot be the same as input path");
+        // Errors are reported as events too when using JSON, so
+        // that tools never need to parse any of the styled output
+        let output = self.output_format.unwrap_or_default();
+        match self.build(output).await {
+            Err(e) if output == BuildOutputFormat::Json => {
+                output.emit(&BuildEvent::Error {
+                    message: format!("{e:#}"),
+                });
+                Ok(ExitCode::FAILURE)
             }
+            result => result,
+        }
+    }
+
+    async fn build(mut self, output: BuildOutputFormat) -> Result<ExitCode> {
+        // Fill in any options not given as flags from the manifest, if any
+        if let Some(manifest_path) = self.manifest.take() {
+            self = BuildManifest::read(&manifest_path).await?.apply_to(self)?;
+        }
+        let (print_graph, dry_run) = (self.print_graph, self.dry_run);
+        if print_graph && output == BuildOutputFormat::Json {
             bail!(
-                "output path cannot be the same as input path, please specify a different output path"
+                "--print-graph prints a tree meant for humans, and can not be used with --output-format json"
             );
         }
+        let options = self.into_options(output)?;
 
-        // Try to read the given input file
-        // FUTURE: We should try and resolve a full require file graph using the input
-        // path here instead, see the notes in the `standalone` module for more details
-        let source_code = fs::read(&self.input)
-            .await
-            .context("failed to read input file")?;
-
-        // Collect extra files to embed
-        let mut extra_files = Vec::new();
-        for path in &self.embed {
-            if path.is_dir() {
-                // If directory, walk it recursively
-                for entry in walkdir::WalkDir::new(path) {
-                    let entry = entry?;
-                    if entry.file_type().is_file() {
-                        let file_path = entry.path();
-                        let content = fs::read(file_path).await?;
-                        // Store path as relative to CWD (or just as provided if relative)
-                        // Use to_string_lossy and replace / with \ for zip compatibility?
-                        // Zip uses forward slashes.
-                        let name = file_path.to_string_lossy().replace('\\', "/");
-                        extra_files.push((name, content));
-                    }
-                }
-            } else if path.is_file() {
-                let content = fs::read(path).await?;
-                let name = path.to_string_lossy().replace('\\', "/");
-                extra_files.push((name, content));
-            } else {
-                eprintln!(
-                    "{}: Path '{}' does not exist or is not readable, skipping...",
-                    style("Warning").yellow().bold(),
-                    path.display()
+        // Print the require graph or list the files instead of building, if
+        // requested, using the same files and checks as for a real build
+        if print_graph || dry_run {
+            let plan = BuildPlan::collect(&options).await?;
+            let archive_files = plan.archive_files();
+            if print_graph {
+                print!(
+                    "{}",
+                    format_require_tree(plan.entry_name(), &archive_files, &plan.aliases.dirs)
                 );
+            } else {
+                plan.check_requires(&options)?;
+                match output {
+                    BuildOutputFormat::Human => print_dry_run(&archive_files, plan.payload_size),
+                    BuildOutputFormat::Json => output.emit(&BuildEvent::DryRun {
+                        files: archive_files
+                            .iter()
+                            .map(|&(name, contents)| DryRunFile {
+                                name,
+                                bytes: contents.len(),
+                            })
+                            .collect(),
+                        bytes: plan.payload_size,
+                    }),
+                }
             }
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        let built = build_standalone(options).await?;
+        for built in &built {
+            output.emit(&BuildEvent::Done {
+                output: &built.path,
+                bytes: built.bytes,
+                embedded_files: built.embedded_files,
+            });
+        }
+        if output == BuildOutputFormat::Human {
+            print_summary(&built);
         }
 
-        // Derive the base executable path based on the arguments provided
-        let base_exe_path = get_or_download_base_executable(target).await?;
+        Ok(ExitCode::SUCCESS)
+    }
 
-        // Read the contents of the lune interpreter as our starting point
-        println!(
-            "Compiling standalone binary from {}",
-            style(self.input.display()).green()
-        );
-        let patched_bin = Metadata::create_env_patched_bin(base_exe_path, source_code, extra_files)
-            .await
-            .context("failed to create patched binary")?;
+    /**
+        Converts the flags of this command into options for [`build_standalone`],
+        reporting progress and warnings using the given output format.
+    */
+    fn into_options(self, output: BuildOutputFormat) -> Result<BuildOptions> {
+        let Some(input) = self.input else {
+            bail!("no input file given, either as an argument or in a build manifest");
+        };
+        Ok(BuildOptions {
+            input,
+            entry_name: self.entry_name,
+            output: self.output,
+            targets: self.targets,
+            embed: self.embed,
+            embed_as: self.embed_as,
+            embed_excludes: self.embed_excludes,
+            embed_base: self.embed_base,
+            compression: self.compression,
+            save_compression: self.save_compression,
+            embed_size_warning: self.embed_size_warning,
+            embed_mode: self.embed_mode,
+            config: self.config,
+            download_retries: self.download_retries,
+            no_download: self.no_download,
+            base_exe: self.base_exe,
+            check_requires: self.check_requires,
+            version_flag: self.version_flag,
+            remove_globals: self.remove_globals,
+            native: self.native,
+            entries: self.entries,
+            minify: self.minify,
+            compress_binary: self.compress_binary,
+            progress: Some(output),
+        })
+    }
+}
+
+fn print_dry_run(files: &[(&str, &[u8])], payload_size: usize) {
+    let name_width = files.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    println!("Files that would be embedded:");
+    for (name, contents) in files {
+        println!("    {name:<name_width$}  {:>12} bytes", contents.len());
+    }
+    println!(
+        "{} files, {payload_size} bytes in total before compression",
+        files.len()
+    );
+}
 
-        // And finally write the patched binary to the output file
+fn print_summary(built: &[BuildOutput]) {
+    let path_width = built
+        .iter()
+        .map(|built| built.path.display().to_string().len())
+        .max()
+        .unwrap_or(0);
+    let noun = if built.len() == 1 {
+        "binary"
+    } else {
+        "binaries"
+    };
+    println!("Built {} standalone {noun}:", built.len());
+    for built in built {
         println!(
-            "Writing standalone binary to {}",
-            style(output_path.display()).blue()
+            "    {:<path_width$}  {:>12} bytes  ({}, {} embedded files)",
+            built.path.display().to_string(),
+            built.bytes,
+            built.target,
+            built.embedded_files
         );
-        write_executable_file_to(output_path, patched_bin).await?; // Read & execute for all, write for owner
+    }
 
-        Ok(ExitCode::SUCCESS)
+    // Warnings are found once for all targets, and were already printed as they were found
+    if let Some(warnings) = built.first().map(|built| built.warnings.len())
+        && warnings > 0
+    {
+        let noun = if warnings == 1 { "warning" } else { "warnings" };
+        println!("{warnings} {noun} found while building, see above");
     }
 }
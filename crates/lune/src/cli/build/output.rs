@@ -0,0 +1,249 @@
+use std::{fmt, path::Path, str::FromStr};
+
+use console::style;
+use serde::Serialize;
+
+/**
+    How progress is reported while building a standalone binary
+*/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BuildOutputFormat {
+    /// Styled text, meant to be read by humans
+    #[default]
+    Human,
+    /// Newline-delimited JSON events, meant to be read by other tools
+    Json,
+}
+
+impl BuildOutputFormat {
+    /**
+        All output formats that can be selected when building.
+    */
+    pub const ALL: &'static [Self] = &[Self::Human, Self::Json];
+
+    /**
+        Reports the given event on stdout, either as styled text,
+        or as a single line of JSON when using the JSON format.
+    */
+    pub fn emit(self, event: &BuildEvent) {
+        match self {
+            Self::Human => {
+                if let Some(message) = event.human_message() {
+                    println!("{message}");
+                }
+            }
+            Self::Json => {
+                println!(
+                    "{}",
+                    serde_json::to_string(event).expect("events are always serializable")
+                );
+            }
+        }
+    }
+}
+
+impl fmt::Display for BuildOutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Human => write!(f, "human"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl FromStr for BuildOutputFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "human" | "text" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "invalid output format, expected one of: {}",
+                Self::ALL
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+/**
+    A stage of building a standalone binary, reported using [`BuildOutputFormat::emit`].
+
+    Events are serialized with their stage in a `stage` field, such
+    as `{"stage":"compiling","input":"main.luau"}` for [`Self::Compiling`].
+*/
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "stage", rename_all = "kebab-case")]
+pub enum BuildEvent<'a> {
+    /// The base executable for the target is not cached, and is being downloaded
+    Downloading { target: String, release: String },
+    /// The base executable was downloaded and added to the cache
+    Downloaded { target: String },
+    /// The input file and embedded files are being compiled into a binary
+    Compiling { input: &'a Path },
+    /// The binary is being written to the output file
+    Writing {
+        output: &'a Path,
+        target: String,
+        compression: String,
+    },
+    /// The files that would be embedded, listed using `--dry-run` instead of building
+    DryRun {
+        files: Vec<DryRunFile<'a>>,
+        /// The size of all files in total, before compression
+        bytes: usize,
+    },
+    /// A problem was found that does not stop the build, such as a missing embedded path
+    Warning { message: String },
+    /// The build finished, and the binary of the given size was written,
+    /// with the given number of files embedded alongside the input file
+    Done {
+        output: &'a Path,
+        bytes: u64,
+        embedded_files: usize,
+    },
+    /// The build failed with the given error
+    Error { message: String },
+}
+
+impl BuildEvent<'_> {
+    /**
+        Returns the styled text that is printed for this event, if any.
+    */
+    fn human_message(&self) -> Option<String> {
+        match self {
+            Self::Downloading { target, release } => Some(format!(
+                "Requested target '{target}' does not exist in cache\nDownloading {release}..."
+            )),
+            Self::Downloaded { .. } => {
+                Some("Downloaded successfully and added to cache".to_string())
+            }
+            Self::Compiling { input } => Some(format!(
+                "Compiling standalone binary from {}",
+                style(input.display()).green()
+            )),
+            Self::Writing {
+                output,
+                target,
+                compression,
+            } => Some(format!(
+                "Writing standalone binary to {} (target: {target}, compression: {compression})",
+                style(output.display()).blue()
+            )),
+            // Dry runs are printed separately, and warnings to stderr as they are found
+            Self::DryRun { .. } | Self::Warning { .. } | Self::Done { .. } | Self::Error { .. } => {
+                None
+            }
+        }
+    }
+}
+
+/**
+    A file that would be embedded, reported in [`BuildEvent::DryRun`].
+*/
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunFile<'a> {
+    pub name: &'a str,
+    pub bytes: usize,
+}
+
+/**
+    Warnings found while building, such as embedded paths that do not exist.
+
+    Warnings are always kept, so that they can be returned once the build is done,
+    and are also reported as they are found if an output format is given - printed
+    to stderr as styled text, or emitted as [`BuildEvent::Warning`] events in JSON.
+*/
+#[derive(Debug, Default)]
+pub struct BuildWarnings {
+    messages: Vec<String>,
+    output: Option<BuildOutputFormat>,
+}
+
+impl BuildWarnings {
+    pub fn new(output: Option<BuildOutputFormat>) -> Self {
+        Self {
+            messages: Vec::new(),
+            output,
+        }
+    }
+
+    /**
+        Adds a warning, reporting it if warnings are reported as they are found.
+    */
+    pub fn push(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        match self.output {
+            Some(BuildOutputFormat::Human) => {
+                eprintln!("{}: {message}", style("Warning").yellow().bold());
+            }
+            Some(BuildOutputFormat::Json) => {
+                BuildOutputFormat::Json.emit(&BuildEvent::Warning {
+                    message: message.clone(),
+                });
+            }
+            None => {}
+        }
+        self.messages.push(message);
+    }
+
+    pub fn into_messages(self) -> Vec<String> {
+        self.messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_events_with_their_stage() {
+        let json = |event: &BuildEvent| serde_json::to_string(event).unwrap();
+        assert_eq!(
+            json(&BuildEvent::Compiling {
+                input: Path::new("main.luau")
+            }),
+            r#"{"stage":"compiling","input":"main.luau"}"#
+        );
+        assert_eq!(
+            json(&BuildEvent::Done {
+                output: Path::new("main"),
+                bytes: 1024,
+                embedded_files: 2
+            }),
+            r#"{"stage":"done","output":"main","bytes":1024,"embedded_files":2}"#
+        );
+        assert_eq!(
+            json(&BuildEvent::DryRun {
+                files: vec![DryRunFile {
+                    name: "init.luau",
+                    bytes: 12
+                }],
+                bytes: 12
+            }),
+            r#"{"stage":"dry-run","files":[{"name":"init.luau","bytes":12}],"bytes":12}"#
+        );
+        assert_eq!(
+            json(&BuildEvent::Warning {
+                message: "embedded path 'missing.txt' does not exist".to_string()
+            }),
+            r#"{"stage":"warning","message":"embedded path 'missing.txt' does not exist"}"#
+        );
+        assert_eq!(
+            json(&BuildEvent::Error {
+                message: "failed to read input file".to_string()
+            }),
+            r#"{"stage":"error","message":"failed to read input file"}"#
+        );
+    }
+
+    #[test]
+    fn parses_output_formats() {
+        assert_eq!("json".parse(), Ok(BuildOutputFormat::Json));
+        assert_eq!(" Human ".parse(), Ok(BuildOutputFormat::Human));
+        assert!("yaml".parse::<BuildOutputFormat>().is_err());
+    }
+}
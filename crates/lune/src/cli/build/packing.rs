@@ -0,0 +1,125 @@
+use std::{
+    env,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+
+use anyhow::{Context, Result, bail};
+use blocking::unblock;
+
+use crate::standalone::metadata::EmbedMode;
+
+use super::target::{BuildTarget, BuildTargetOS};
+
+/**
+    The program used to compress base executables, unless overridden
+    using the `LUNE_UPX` environment variable.
+*/
+const UPX_PROGRAM: &str = "upx";
+
+/**
+    A base executable compressed using UPX, stored in a temporary file that is removed when dropped.
+
+    Only the base executable is compressed, before the payload is appended to it, so that
+    the metadata at the end of the binary can still be found and read at runtime. The
+    binary decompresses itself into memory every time it starts, which makes it smaller
+    to distribute, but adds some startup time in exchange.
+*/
+#[derive(Debug)]
+pub struct PackedExecutable {
+    path: PathBuf,
+}
+
+impl PackedExecutable {
+    /**
+        Makes sure that the given target and embed mode can be used with a compressed base executable.
+    */
+    pub fn check_supported(target: &BuildTarget, embed_mode: EmbedMode) -> Result<()> {
+        if target.os == BuildTargetOS::MacOS {
+            bail!(
+                "--compress-binary is not supported for macOS targets, \
+                since UPX can not reliably compress macOS executables"
+            );
+        }
+        if embed_mode == EmbedMode::Section {
+            bail!(
+                "--compress-binary can not be used together with --embed-mode section, \
+                since sections can not be added to a compressed executable"
+            );
+        }
+        Ok(())
+    }
+
+    /**
+        Compresses the given base executable for the given target using UPX.
+
+        # Errors
+
+        Errors if UPX is not installed, or if it failed to compress the executable.
+    */
+    pub async fn pack(base_exe: &Path, target: &BuildTarget) -> Result<Self> {
+        let packed = Self {
+            path: env::temp_dir().join(format!(
+                "lune-packed-{}-{target}{}",
+                process::id(),
+                target.exe_suffix()
+            )),
+        };
+
+        // UPX refuses to overwrite existing files, so any leftover
+        // output from an earlier build needs to be removed first
+        let _ = std::fs::remove_file(&packed.path);
+
+        let program = env::var_os("LUNE_UPX").unwrap_or_else(|| UPX_PROGRAM.into());
+        let (input, output) = (base_exe.to_path_buf(), packed.path.clone());
+        let result = unblock(move || {
+            Command::new(program)
+                .arg("--best")
+                .arg("--quiet")
+                .arg("-o")
+                .arg(output)
+                .arg(input)
+                .output()
+        })
+        .await;
+
+        match result {
+            Err(e) if e.kind() == ErrorKind::NotFound => bail!(
+                "--compress-binary requires UPX, which could not be found - install it \
+                from https://upx.github.io and make sure that it is available in PATH, \
+                or give the path to it in the LUNE_UPX environment variable"
+            ),
+            Err(e) => Err(e).context("failed to run UPX"),
+            Ok(output) if !output.status.success() => bail!(
+                "UPX failed to compress the base executable:\n{}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Ok(_) => Ok(packed),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PackedExecutable {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_unsupported_targets_and_embed_modes() {
+        let linux: BuildTarget = "linux-x86_64".parse().unwrap();
+        let macos: BuildTarget = "macos-aarch64".parse().unwrap();
+        assert!(PackedExecutable::check_supported(&linux, EmbedMode::Trailer).is_ok());
+        assert!(PackedExecutable::check_supported(&linux, EmbedMode::Section).is_err());
+        assert!(PackedExecutable::check_supported(&macos, EmbedMode::Trailer).is_err());
+    }
+}
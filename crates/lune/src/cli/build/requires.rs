@@ -0,0 +1,882 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use async_fs as fs;
+
+use lune_utils::path::constants::{FILE_EXTENSIONS, FILE_NAME_CONFIG, FILE_NAME_INIT};
+
+use super::output::BuildWarnings;
+
+/**
+    A call to `require` with a string literal, found in Luau source code.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequireCall {
+    pub specifier: String,
+    pub line: usize,
+}
+
+/**
+    Finds all calls to `require` using string literals in the given Luau source code.
+
+    This is a simple scan that skips comments and string literals, and not a full parse,
+    meaning that requires using computed values can not be found. Those will instead be
+    resolved at runtime as usual, and are never checked during builds.
+*/
+pub fn find_requires(source: &str) -> Vec<RequireCall> {
+    let mut scanner = SourceScanner::new(source);
+    let mut calls = Vec::new();
+    while let Some(byte) = scanner.peek(0) {
+        match byte {
+            b'-' if scanner.peek(1) == Some(b'-') => {
+                scanner.pos += 2;
+                match scanner.long_bracket_level() {
+                    Some(level) => scanner.skip_long_bracket(level),
+                    None => scanner.skip_line(),
+                }
+            }
+            b'[' if let Some(level) = scanner.long_bracket_level() => {
+                scanner.skip_long_bracket(level);
+            }
+            b'"' | b'\'' | b'`' => {
+                scanner.read_string();
+            }
+            byte if byte.is_ascii_alphanumeric() || byte == b'_' => {
+                let line = scanner.line;
+                if scanner.read_word() == "require"
+                    && let Some(specifier) = scanner.read_require_argument()
+                {
+                    calls.push(RequireCall {
+                        specifier: specifier.to_string(),
+                        line,
+                    });
+                }
+            }
+            _ => scanner.advance(),
+        }
+    }
+    calls
+}
+
+/**
+    Walks over Luau source code for [`find_requires`], keeping track of the current line.
+
+    All tokens that matter here start and end with ASCII characters, so the
+    source is scanned byte by byte, and slices always fall on char boundaries.
+*/
+struct SourceScanner<'a> {
+    source: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> SourceScanner<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.source.as_bytes().get(self.pos + offset).copied()
+    }
+
+    fn advance(&mut self) {
+        if self.peek(0) == Some(b'\n') {
+            self.line += 1;
+        }
+        self.pos += 1;
+    }
+
+    fn skip_line(&mut self) {
+        while self.peek(0).is_some_and(|byte| byte != b'\n') {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek(0).is_some_and(|byte| byte.is_ascii_whitespace()) {
+            self.advance();
+        }
+    }
+
+    /**
+        Returns the level of the long bracket, such as `[[` or `[==[`, at the current position.
+    */
+    fn long_bracket_level(&self) -> Option<usize> {
+        if self.peek(0) != Some(b'[') {
+            return None;
+        }
+        let mut level = 0;
+        while self.peek(level + 1) == Some(b'=') {
+            level += 1;
+        }
+        (self.peek(level + 1) == Some(b'[')).then_some(level)
+    }
+
+    /**
+        Skips a long string or comment, up to and including its closing bracket of the same level.
+    */
+    fn skip_long_bracket(&mut self, level: usize) {
+        let close = format!("]{}]", "=".repeat(level));
+        self.pos += level + 2;
+        let end = self.source[self.pos..]
+            .find(&close)
+            .map_or(self.source.len(), |offset| self.pos + offset + close.len());
+        self.line += self.source[self.pos..end].matches('\n').count();
+        self.pos = end;
+    }
+
+    /**
+        Reads a quoted string literal, returning its contents without any escapes processed.
+
+        Unterminated strings end at the end of their line, same as they would when parsing.
+    */
+    fn read_string(&mut self) -> &'a str {
+        let Some(quote) = self.peek(0) else {
+            return "";
+        };
+        self.pos += 1;
+        let start = self.pos;
+        while let Some(byte) = self.peek(0) {
+            match byte {
+                b'\\' => {
+                    self.pos += 1;
+                    if self.peek(0).is_some() {
+                        self.advance();
+                    }
+                }
+                b'\n' => return &self.source[start..self.pos],
+                byte if byte == quote => {
+                    self.pos += 1;
+                    return &self.source[start..self.pos - 1];
+                }
+                _ => self.pos += 1,
+            }
+        }
+        &self.source[start..]
+    }
+
+    fn read_word(&mut self) -> &'a str {
+        let start = self.pos;
+        while self
+            .peek(0)
+            .is_some_and(|byte| byte.is_ascii_alphanumeric() || byte == b'_')
+        {
+            self.pos += 1;
+        }
+        &self.source[start..self.pos]
+    }
+
+    /**
+        Reads the string literal given to a `require` that was just read, either
+        in parentheses or directly after it, returning `None` if there is none.
+    */
+    fn read_require_argument(&mut self) -> Option<&'a str> {
+        self.skip_whitespace();
+        if self.peek(0) == Some(b'(') {
+            self.pos += 1;
+            self.skip_whitespace();
+        }
+        match self.peek(0) {
+            Some(b'"' | b'\'' | b'`') => Some(self.read_string()),
+            _ => None,
+        }
+    }
+}
+
+/**
+    The resolved target of a require, relative to the root of a standalone archive.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequireTarget {
+    /// A standard library, such as `@lune/fs`
+    Std(String),
+    /// An alias that can only be resolved using a `.luaurc` file
+    Alias(String),
+    /// A module path in the archive, without any file extension
+    Module(String),
+}
+
+impl RequireTarget {
+    /**
+        Resolves a require specifier, relative to the archive file that requires it.
+
+        Returns `None` if the specifier is invalid, or would escape the archive root.
+    */
+    pub fn resolve(from_file: &str, specifier: &str) -> Option<Self> {
+        let module = module_path_of(from_file);
+        if let Some(rest) = specifier.strip_prefix("@self/") {
+            return join_module_path(&module, rest).map(Self::Module);
+        }
+        if specifier.starts_with("@lune/") {
+            return Some(Self::Std(specifier.to_string()));
+        }
+        if specifier.starts_with('@') {
+            return Some(Self::Alias(specifier.to_string()));
+        }
+        if specifier.starts_with("./") || specifier.starts_with("../") {
+            let parent = parent_module_path(&module);
+            return join_module_path(&parent, specifier).map(Self::Module);
+        }
+        None
+    }
+
+    /**
+        Resolves an alias target to a module path in the archive, using the given
+        aliases, which map alias names to the archive directories they point to.
+
+        Targets that are not aliases, or that use unknown aliases, are returned unchanged.
+    */
+    pub fn with_aliases(self, aliases: &BTreeMap<String, String>) -> Self {
+        let Self::Alias(specifier) = &self else {
+            return self;
+        };
+        let specifier = specifier.trim_start_matches('@');
+        let (alias, rest) = specifier.split_once('/').unwrap_or((specifier, ""));
+        aliases
+            .get(&alias.to_ascii_lowercase())
+            .and_then(|dir| join_module_path(dir, rest))
+            .map_or(self, Self::Module)
+    }
+
+    /**
+        Returns all file names that this require target could resolve to, in the
+        same order that they would be searched for by `require` at runtime.
+    */
+    pub fn candidates(&self) -> Vec<String> {
+        let Self::Module(module) = self else {
+            return Vec::new();
+        };
+        let mut candidates = Vec::new();
+        for ext in FILE_EXTENSIONS {
+            candidates.push(format!("{module}.{ext}"));
+        }
+        for ext in FILE_EXTENSIONS {
+            if module.is_empty() {
+                candidates.push(format!("{FILE_NAME_INIT}.{ext}"));
+            } else {
+                candidates.push(format!("{module}/{FILE_NAME_INIT}.{ext}"));
+            }
+        }
+        candidates
+    }
+}
+
+impl fmt::Display for RequireTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Std(s) | Self::Alias(s) | Self::Module(s) => s.fmt(f),
+        }
+    }
+}
+
+/**
+    A require that could not be resolved to any file in a standalone archive.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedRequire {
+    pub file: String,
+    pub line: usize,
+    pub specifier: String,
+}
+
+impl fmt::Display for UnresolvedRequire {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: require(\"{}\")",
+            self.file, self.line, self.specifier
+        )
+    }
+}
+
+/**
+    The archive directory that stores the directories of aliases pointing
+    outside of the archive root, each under a subdirectory named after the alias.
+*/
+const EXTERNAL_ALIASES_DIR: &str = ".lune-aliases";
+
+/**
+    The aliases in a `.luaurc` file, and where the directories they point to are stored.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct ConfigAliases {
+    /// Alias names, mapped to the archive directories that they point to
+    pub dirs: BTreeMap<String, String>,
+    /// Archive directories of aliases outside of the archive root, mapped to their paths on disk
+    pub external: BTreeMap<String, PathBuf>,
+}
+
+impl ConfigAliases {
+    /**
+        Returns the path on disk of the file with the given archive name, where
+        the archive root is stored at the given root directory on disk.
+    */
+    pub fn disk_path(&self, root: &Path, name: &str) -> PathBuf {
+        for (dir_name, path) in &self.external {
+            if let Some(rest) = name.strip_prefix(dir_name.as_str())
+                && let Some(rest) = rest.strip_prefix('/')
+            {
+                return path.join(rest);
+            }
+        }
+        root.join(name)
+    }
+}
+
+/**
+    Reads the aliases in the `.luaurc` file in the given directory, if there is one.
+
+    The directory must be the one that is stored as the archive root, meaning the
+    directory of the input file. Aliases that point outside of it are stored in the
+    archive under a synthetic directory instead, so that they resolve the same way at
+    runtime, and aliases that do not point to a directory are skipped with a warning.
+
+    # Errors
+
+    Errors if the `.luaurc` file exists but could not be read, or is not valid JSON,
+    with the line and column of the first error, since aliases would be missing otherwise.
+*/
+pub async fn read_config_aliases(
+    dir: &Path,
+    warnings: &mut BuildWarnings,
+) -> Result<ConfigAliases> {
+    let path = dir.join(FILE_NAME_CONFIG);
+    let contents = match fs::read(&path).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(ConfigAliases::default()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("failed to read '{}'", path.display()));
+        }
+    };
+    let config = match serde_json::from_slice::<serde_json::Value>(&contents) {
+        Ok(config) => config,
+        Err(e) => bail!(
+            "failed to parse '{}' at line {}, column {}: {e}",
+            path.display(),
+            e.line(),
+            e.column()
+        ),
+    };
+
+    let mut aliases = ConfigAliases::default();
+    let Some(config_aliases) = config.get("aliases") else {
+        return Ok(aliases);
+    };
+    let Some(config_aliases) = config_aliases.as_object() else {
+        bail!(
+            "failed to parse '{}': aliases must be an object",
+            path.display()
+        );
+    };
+    for (alias, target) in config_aliases {
+        let alias = alias.to_ascii_lowercase();
+        let target = target.as_str().filter(|target| !target.starts_with('@'));
+        let Some(target) = target.filter(|target| dir.join(target).is_dir()) else {
+            warnings.push(format!(
+                "Alias '{alias}' in '{}' does not point to a directory, skipping...",
+                path.display()
+            ));
+            continue;
+        };
+        let dir_name = match join_module_path("", target) {
+            Some(dir_name) if !Path::new(target).has_root() => dir_name,
+            _ => {
+                let dir_name = format!("{EXTERNAL_ALIASES_DIR}/{alias}");
+                aliases.external.insert(dir_name.clone(), dir.join(target));
+                dir_name
+            }
+        };
+        aliases.dirs.insert(alias, dir_name);
+    }
+    Ok(aliases)
+}
+
+/**
+    Checks that all requires in the given archive files resolve to other files in
+    the archive, or to standard libraries, returning any requires that do not.
+
+    Aliases are resolved using the given aliases first, and other aliases
+    can only be checked when a `.luaurc` file is not embedded - if one
+    is, those aliases are assumed to be resolvable at runtime.
+*/
+pub fn check_requires(
+    files: &[(&str, &[u8])],
+    aliases: &BTreeMap<String, String>,
+) -> Vec<UnresolvedRequire> {
+    let has_config = files
+        .iter()
+        .any(|(name, _)| name.rsplit('/').next() == Some(FILE_NAME_CONFIG));
+    let has_file = |candidate: &str| files.iter().any(|(name, _)| *name == candidate);
+
+    let mut unresolved = Vec::new();
+    for (name, contents) in files {
+        if !is_source_file(name) {
+            continue;
+        }
+        let source = String::from_utf8_lossy(contents);
+        for call in find_requires(&source) {
+            let target = RequireTarget::resolve(name, &call.specifier)
+                .map(|target| target.with_aliases(aliases));
+            let resolved = match target {
+                Some(RequireTarget::Std(_)) => true,
+                Some(RequireTarget::Alias(_)) => has_config,
+                Some(target) => target.candidates().iter().any(|c| has_file(c)),
+                None => false,
+            };
+            if !resolved {
+                unresolved.push(UnresolvedRequire {
+                    file: name.to_string(),
+                    line: call.line,
+                    specifier: call.specifier,
+                });
+            }
+        }
+    }
+    unresolved
+}
+
+/**
+    A file that was found by following the requires of other files, and that
+    should be embedded in a standalone archive under the given name.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequiredFile {
+    pub name: String,
+    pub path: PathBuf,
+    pub contents: Vec<u8>,
+}
+
+/**
+    Follows the requires in the given archive files, transitively, returning
+    all files they require that are not already in the archive but exist on disk.
+
+    The given root directory is where the archive root is on disk, meaning that
+    a file required as `lib/util` is searched for as `root/lib/util.luau`, using
+    the same search order as `require` does at runtime. Requires that can not be
+    found are skipped here, and are instead reported by [`check_requires`].
+    Requires using the given aliases are followed into their directories, including
+    directories outside of the root, which are read from where they are on disk.
+*/
+pub async fn discover_required_files(
+    root: &Path,
+    files: &[(&str, &[u8])],
+    aliases: &ConfigAliases,
+) -> Result<Vec<RequiredFile>> {
+    let mut known = files
+        .iter()
+        .map(|(name, _)| (*name).to_string())
+        .collect::<HashSet<_>>();
+    let mut queue = files
+        .iter()
+        .filter(|(name, _)| is_source_file(name))
+        .map(|(name, contents)| ((*name).to_string(), contents.to_vec()))
+        .collect::<Vec<_>>();
+
+    let mut discovered = Vec::new();
+    while let Some((name, contents)) = queue.pop() {
+        for call in find_requires(&String::from_utf8_lossy(&contents)) {
+            let Some(target @ RequireTarget::Module(_)) =
+                RequireTarget::resolve(&name, &call.specifier)
+                    .map(|target| target.with_aliases(&aliases.dirs))
+            else {
+                continue;
+            };
+            // Known files include ones already discovered, which
+            // means that cyclic requires are only followed once
+            for candidate in target.candidates() {
+                if known.contains(&candidate) {
+                    break;
+                }
+                let path = aliases.disk_path(root, &candidate);
+                if !fs::metadata(&path).await.is_ok_and(|meta| meta.is_file()) {
+                    continue;
+                }
+                let contents = fs::read(&path).await.with_context(|| {
+                    format!("failed to read required file '{}'", path.display())
+                })?;
+                known.insert(candidate.clone());
+                queue.push((candidate.clone(), contents.clone()));
+                discovered.push(RequiredFile {
+                    name: candidate,
+                    path,
+                    contents,
+                });
+                break;
+            }
+        }
+    }
+    Ok(discovered)
+}
+
+/**
+    Formats the require graph of the given archive files as a tree rooted at the main
+    entry point, with the given name, with required modules indented below their requirers.
+
+    Modules are only expanded the first time they appear, and requires leading back to
+    a module that is still being expanded further up the tree are marked as cycles.
+*/
+pub fn format_require_tree(
+    entry_name: &str,
+    files: &[(&str, &[u8])],
+    aliases: &BTreeMap<String, String>,
+) -> String {
+    let mut tree = format!("{entry_name}\n");
+    let mut stack = vec![entry_name.to_string()];
+    let mut expanded = HashSet::new();
+    expanded.insert(entry_name.to_string());
+    write_require_children(files, aliases, &mut tree, &mut stack, &mut expanded);
+    tree
+}
+
+fn write_require_children(
+    files: &[(&str, &[u8])],
+    aliases: &BTreeMap<String, String>,
+    tree: &mut String,
+    stack: &mut Vec<String>,
+    expanded: &mut HashSet<String>,
+) {
+    let name = stack.last().expect("stack is never empty").clone();
+    let Some((_, contents)) = files.iter().find(|(file, _)| *file == name) else {
+        return;
+    };
+    let indent = "  ".repeat(stack.len());
+    let has_config = files
+        .iter()
+        .any(|(file, _)| file.rsplit('/').next() == Some(FILE_NAME_CONFIG));
+
+    for call in find_requires(&String::from_utf8_lossy(contents)) {
+        let target = RequireTarget::resolve(&name, &call.specifier)
+            .map(|target| target.with_aliases(aliases));
+        let found = target.as_ref().and_then(|target| {
+            target
+                .candidates()
+                .into_iter()
+                .find(|candidate| files.iter().any(|(file, _)| file == candidate))
+        });
+        let (label, expand) = match (&target, found) {
+            (Some(RequireTarget::Std(spec)), _) => (spec.clone(), false),
+            (Some(RequireTarget::Alias(spec)), _) if has_config => {
+                (format!("{spec} (alias)"), false)
+            }
+            (_, Some(file)) if stack.contains(&file) => (format!("{file} (cycle)"), false),
+            (_, Some(file)) if expanded.contains(&file) => (format!("{file} (see above)"), false),
+            (_, Some(file)) => (file, true),
+            (_, None) => (format!("{} (unresolved)", call.specifier), false),
+        };
+        let _ = writeln!(tree, "{indent}{label}");
+        if expand {
+            expanded.insert(label.clone());
+            stack.push(label);
+            write_require_children(files, aliases, tree, stack, expanded);
+            stack.pop();
+        }
+    }
+}
+
+/**
+    Returns whether the given archive file name is a Luau source file.
+*/
+pub fn is_source_file(name: &str) -> bool {
+    name.rsplit_once('.')
+        .is_some_and(|(_, ext)| FILE_EXTENSIONS.contains(&ext))
+}
+
+/**
+    Converts an archive file name into its module path, stripping file
+    extensions and `init` segments, such that `lib/init.luau` becomes `lib`.
+
+    The main entry point at the root of the archive is a special case, and
+    keeps its name, since requires in it are relative to the archive root.
+*/
+fn module_path_of(file: &str) -> String {
+    let stem = match file.rsplit_once('.') {
+        Some((stem, ext)) if FILE_EXTENSIONS.contains(&ext) => stem,
+        _ => file,
+    };
+    match stem.strip_suffix(FILE_NAME_INIT) {
+        Some(dir) if dir.ends_with('/') => dir.trim_end_matches('/').to_string(),
+        _ => stem.to_string(),
+    }
+}
+
+fn parent_module_path(module: &str) -> String {
+    match module.rsplit_once('/') {
+        Some((parent, _)) => parent.to_string(),
+        None => String::new(),
+    }
+}
+
+fn join_module_path(base: &str, relative: &str) -> Option<String> {
+    let mut parts = base
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>();
+    for part in relative.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_string_literal_requires() {
+        let source = "local a = require(\"./a\")\n\
+            local b = require('@lune/fs') -- require(\"./commented\")\n\
+            -- local c = require(\"./c\")\n\
+            local d = require(`../d`)\n\
+            local e = require(path)";
+        let specifiers = find_requires(source)
+            .into_iter()
+            .map(|call| (call.specifier, call.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            specifiers,
+            vec![
+                ("./a".to_string(), 1),
+                ("@lune/fs".to_string(), 2),
+                ("../d".to_string(), 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_requires_in_comments_and_strings() {
+        let source = "--[[\n\
+            local a = require(\"./commented\")\n\
+            ]] local b = require(\"./b\")\n\
+            --[==[ require(\"./commented\") ]] ]==]\n\
+            local c = \"--\" .. require(\"./c\")\n\
+            local d = \"require('./string')\" .. [[ require('./long') ]]\n\
+            local e = 'it\\'s' .. require \"./e\"";
+        let specifiers = find_requires(source)
+            .into_iter()
+            .map(|call| (call.specifier, call.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            specifiers,
+            vec![
+                ("./b".to_string(), 3),
+                ("./c".to_string(), 5),
+                ("./e".to_string(), 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_relative_to_requiring_module() {
+        let resolve = |from, spec| RequireTarget::resolve(from, spec).map(|t| t.to_string());
+        assert_eq!(resolve("init.luau", "./lib"), Some("lib".to_string()));
+        assert_eq!(resolve("lib/a.luau", "./b"), Some("lib/b".to_string()));
+        assert_eq!(resolve("lib/init.luau", "./b"), Some("b".to_string()));
+        assert_eq!(
+            resolve("lib/init.luau", "@self/b"),
+            Some("lib/b".to_string())
+        );
+        assert_eq!(resolve("lib/a.luau", "../c"), Some("c".to_string()));
+        assert_eq!(resolve("init.luau", "../escape"), None);
+    }
+
+    #[test]
+    fn formats_require_tree_with_cycles() {
+        let files: &[(&str, &[u8])] = &[
+            ("init.luau", b"require(\"./lib\")\nrequire(\"./missing\")"),
+            (
+                "lib/init.luau",
+                b"require(\"@self/util\")\nrequire(\"@lune/fs\")",
+            ),
+            (
+                "lib/util.luau",
+                b"require(\"@self/../init\")\nrequire(\"../lib\")",
+            ),
+        ];
+        assert_eq!(
+            format_require_tree("init.luau", files, &BTreeMap::new()),
+            "init.luau\n\
+            \x20 lib/init.luau\n\
+            \x20   lib/util.luau\n\
+            \x20     lib/init.luau (cycle)\n\
+            \x20     lib/init.luau (cycle)\n\
+            \x20   @lune/fs\n\
+            \x20 ./missing (unresolved)\n"
+        );
+    }
+
+    #[test]
+    fn discovers_transitive_requires() {
+        let root = std::env::temp_dir().join(format!("lune-requires-test-{}", std::process::id()));
+        let tree = [
+            ("utils.luau", "require(\"./lib\")"),
+            ("lib/init.luau", "require(\"@self/a\")"),
+            (
+                "lib/a.luau",
+                "require(\"../utils\")\nrequire(\"./embedded\")",
+            ),
+            ("lib/embedded.luau", "return {}"),
+            ("unused.luau", "return {}"),
+        ];
+        for (name, contents) in tree {
+            let path = root.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let files: &[(&str, &[u8])] = &[
+            ("init.luau", b"require(\"./utils\")\nrequire(\"./missing\")"),
+            ("lib/embedded.luau", b"return {}"),
+        ];
+        let discovered = futures_lite::future::block_on(discover_required_files(
+            &root,
+            files,
+            &ConfigAliases::default(),
+        ));
+        let mut names = discovered
+            .unwrap()
+            .into_iter()
+            .map(|file| file.name)
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["lib/a.luau", "lib/init.luau", "utils.luau"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn reports_unresolved_requires() {
+        let files: &[(&str, &[u8])] = &[
+            ("init.luau", b"require(\"./lib\")\nrequire(\"./missing\")"),
+            (
+                "lib/init.luau",
+                b"require(\"@self/util\")\nrequire(\"@lune/fs\")",
+            ),
+            ("lib/util.luau", b"require(\"@alias/thing\")"),
+        ];
+        let unresolved = check_requires(files, &BTreeMap::new())
+            .into_iter()
+            .map(|u| u.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            unresolved,
+            vec![
+                "init.luau:2: require(\"./missing\")".to_string(),
+                "lib/util.luau:1: require(\"@alias/thing\")".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_known_aliases() {
+        let aliases = BTreeMap::from([("pkg".to_string(), "packages/lib".to_string())]);
+        let resolve = |spec| {
+            RequireTarget::resolve("init.luau", spec).map(|target| target.with_aliases(&aliases))
+        };
+        assert_eq!(
+            resolve("@pkg/util"),
+            Some(RequireTarget::Module("packages/lib/util".to_string()))
+        );
+        assert_eq!(
+            resolve("@PKG"),
+            Some(RequireTarget::Module("packages/lib".to_string()))
+        );
+        assert_eq!(
+            resolve("@other/util"),
+            Some(RequireTarget::Alias("@other/util".to_string()))
+        );
+
+        let files: &[(&str, &[u8])] = &[
+            (
+                "init.luau",
+                b"require(\"@pkg/util\")\nrequire(\"@pkg/missing\")",
+            ),
+            ("packages/lib/util.luau", b"return {}"),
+        ];
+        let unresolved = check_requires(files, &aliases)
+            .into_iter()
+            .map(|u| u.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            unresolved,
+            vec!["init.luau:2: require(\"@pkg/missing\")".to_string()]
+        );
+    }
+
+    #[test]
+    fn reads_and_follows_config_aliases() {
+        let root = std::env::temp_dir().join(format!("lune-aliases-test-{}", std::process::id()));
+        let project = root.join("project");
+        let tree = [
+            (
+                "project/.luaurc",
+                r#"{ "aliases": { "Lib": "./lib", "shared": "../shared", "missing": "./missing" } }"#,
+            ),
+            ("project/lib/init.luau", "return {}"),
+            ("shared/util.luau", "require(\"./helper\")"),
+            ("shared/helper.luau", "return {}"),
+        ];
+        for (name, contents) in tree {
+            let path = root.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let aliases = futures_lite::future::block_on(read_config_aliases(
+            &project,
+            &mut BuildWarnings::default(),
+        ))
+        .unwrap();
+        assert_eq!(
+            aliases.dirs,
+            BTreeMap::from([
+                ("lib".to_string(), "lib".to_string()),
+                ("shared".to_string(), ".lune-aliases/shared".to_string()),
+            ])
+        );
+
+        let files: &[(&str, &[u8])] =
+            &[("init.luau", b"require(\"@lib\")\nrequire(\"@shared/util\")")];
+        let discovered =
+            futures_lite::future::block_on(discover_required_files(&project, files, &aliases));
+        let mut names = discovered
+            .unwrap()
+            .into_iter()
+            .map(|file| file.name)
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                ".lune-aliases/shared/helper.luau",
+                ".lune-aliases/shared/util.luau",
+                "lib/init.luau"
+            ]
+        );
+
+        // Malformed configs are errors, pointing to where parsing failed
+        std::fs::write(project.join(".luaurc"), "{\n  \"aliases\": {,\n}").unwrap();
+        let err = futures_lite::future::block_on(read_config_aliases(
+            &project,
+            &mut BuildWarnings::default(),
+        ))
+        .unwrap_err();
+        assert!(err.to_string().contains("at line 2, column 15"), "{err}");
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use thiserror::Error;
 
 use super::target::BuildTarget;
@@ -13,6 +15,31 @@ pub enum BuildError {
     ZippedBinaryNotFound(String),
     #[error("failed to download lune binary: {0}")]
     Download(String),
+    #[error("failed to download lune binary from {url} after {attempts} attempt(s): {error}")]
+    DownloadFailed {
+        url: String,
+        attempts: u32,
+        error: String,
+    },
+    #[error(
+        "invalid release url '{url}', make sure that the mirror given in LUNE_BASE_EXE_URL is a valid url: {error}"
+    )]
+    InvalidReleaseUrl { url: String, error: String },
+    #[error(
+        "official lune releases do not include a base executable for target '{0}' - \
+        give one using --base-exe, or a mirror that provides it using LUNE_BASE_EXE_URL"
+    )]
+    NoOfficialRelease(BuildTarget),
+    #[error(
+        "failed to detect the target of base executable '{}', it must be a lune binary for a supported target",
+        .0.display()
+    )]
+    UnknownBaseExecutable(PathBuf),
+    #[error(
+        "base executable for target '{0}' is not cached, and downloading is disabled using --no-download - \
+        run the build once while online to cache it, or give a base executable using --base-exe"
+    )]
+    NotCached(BuildTarget),
     #[error("failed to unzip lune binary: {0}")]
     Unzip(#[from] zip::result::ZipError),
     #[error("io error: {0}")]
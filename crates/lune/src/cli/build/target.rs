@@ -1,6 +1,7 @@
 use std::{env::consts::ARCH, fmt, path::PathBuf, str::FromStr, sync::LazyLock};
 
 use directories::BaseDirs;
+use object::{Architecture, BinaryFormat, Object};
 
 static HOME_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
     BaseDirs::new()
@@ -11,6 +12,8 @@ static HOME_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
 
 pub static CACHE_DIR: LazyLock<PathBuf> = LazyLock::new(|| HOME_DIR.join(".lune").join("target"));
 
+const RELEASE_BASE_URL: &str = "https://github.com/lune-org/lune/releases/download";
+
 /**
     A target operating system supported by Lune
 */
@@ -46,6 +49,15 @@ impl BuildTargetOS {
             _ => "",
         }
     }
+
+    fn max_binary_size(self) -> u64 {
+        // NOTE: These are approximate - standalone binaries are read fully into
+        // memory on startup, and Windows refuses to load executables over 2GB
+        match self {
+            Self::Windows => 2 * 1024 * 1024 * 1024,
+            _ => 4 * 1024 * 1024 * 1024,
+        }
+    }
 }
 
 impl fmt::Display for BuildTargetOS {
@@ -109,17 +121,58 @@ impl FromStr for BuildTargetArch {
     }
 }
 
+/**
+    A target C library for Linux, which Lune supports linking against
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildTargetLibc {
+    Gnu,
+    Musl,
+}
+
+impl BuildTargetLibc {
+    fn current_system() -> Self {
+        if cfg!(target_env = "musl") {
+            Self::Musl
+        } else {
+            Self::Gnu
+        }
+    }
+}
+
+impl fmt::Display for BuildTargetLibc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gnu => write!(f, "gnu"),
+            Self::Musl => write!(f, "musl"),
+        }
+    }
+}
+
+impl FromStr for BuildTargetLibc {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "gnu" | "glibc" => Ok(Self::Gnu),
+            "musl" => Ok(Self::Musl),
+            _ => Err("invalid target libc"),
+        }
+    }
+}
+
 /**
     A full target description that Lune supports (OS + Arch)
 
     This is used to determine the target to build for standalone binaries,
     and to download the correct base executable for cross-compilation.
 
-    The target may be parsed from and displayed in the form `os-arch`.
+    The target may be parsed from and displayed in the form `os-arch`, with
+    an optional `-libc` suffix for Linux targets, which defaults to `gnu`.
     Examples of valid targets are:
 
     - `linux-aarch64`
     - `linux-x86_64`
+    - `linux-x86_64-musl`
     - `macos-aarch64`
     - `macos-x86_64`
     - `windows-x86_64`
@@ -128,18 +181,72 @@ impl FromStr for BuildTargetArch {
 pub struct BuildTarget {
     pub os: BuildTargetOS,
     pub arch: BuildTargetArch,
+    /// The C library to link against, only present for Linux targets
+    pub libc: Option<BuildTargetLibc>,
 }
 
 impl BuildTarget {
+    const ALL_OS: [BuildTargetOS; 3] = [
+        BuildTargetOS::Linux,
+        BuildTargetOS::MacOS,
+        BuildTargetOS::Windows,
+    ];
+    const ALL_ARCH: [BuildTargetArch; 2] = [BuildTargetArch::X86_64, BuildTargetArch::Aarch64];
+    const ALL_LIBC: [BuildTargetLibc; 2] = [BuildTargetLibc::Gnu, BuildTargetLibc::Musl];
+
     pub fn current_system() -> Self {
+        let os = BuildTargetOS::current_system();
         Self {
-            os: BuildTargetOS::current_system(),
+            os,
             arch: BuildTargetArch::current_system(),
+            libc: (os == BuildTargetOS::Linux).then(BuildTargetLibc::current_system),
         }
     }
 
     pub fn is_current_system(&self) -> bool {
-        self.os == BuildTargetOS::current_system() && self.arch == BuildTargetArch::current_system()
+        *self == Self::current_system()
+    }
+
+    /**
+        Returns all targets that Lune supports, in their canonical form.
+    */
+    pub fn all() -> Vec<Self> {
+        let mut targets = Vec::new();
+        for os in Self::ALL_OS {
+            for arch in Self::ALL_ARCH {
+                if os == BuildTargetOS::Linux {
+                    for libc in Self::ALL_LIBC {
+                        targets.push(Self {
+                            os,
+                            arch,
+                            libc: Some(libc),
+                        });
+                    }
+                } else {
+                    targets.push(Self {
+                        os,
+                        arch,
+                        libc: None,
+                    });
+                }
+            }
+        }
+        targets
+    }
+
+    /**
+        Returns the Rust target triple for this target, such as `x86_64-unknown-linux-musl`.
+    */
+    pub fn rust_target_triple(&self) -> String {
+        match (self.os, self.libc) {
+            (BuildTargetOS::Windows, _) => format!("{}-pc-windows-msvc", self.arch),
+            (BuildTargetOS::MacOS, _) => format!("{}-apple-darwin", self.arch),
+            (BuildTargetOS::Linux, libc) => format!(
+                "{}-unknown-linux-{}",
+                self.arch,
+                libc.unwrap_or(BuildTargetLibc::Gnu)
+            ),
+        }
     }
 
     pub fn exe_extension(&self) -> &'static str {
@@ -150,27 +257,257 @@ impl BuildTarget {
         self.os.exe_suffix()
     }
 
+    /**
+        Returns the approximate maximum size of an executable
+        that can be loaded on this target, in bytes.
+    */
+    pub fn max_binary_size(&self) -> u64 {
+        self.os.max_binary_size()
+    }
+
+    /**
+        Returns the file name of the base executable for this target,
+        as found inside of release archives, such as `lune.exe`.
+    */
+    pub fn base_exe_name(&self) -> String {
+        format!("lune{}", self.exe_suffix())
+    }
+
+    /**
+        Returns the name of the release archive for this target
+        and the given Lune version, without any file extension.
+    */
+    pub fn release_name(&self, version: &str) -> String {
+        format!("lune-{version}-{self}")
+    }
+
+    /**
+        Returns `true` if the official Lune releases include a base executable
+        for this target - musl builds are not released, and must be given using
+        `--base-exe`, or downloaded from a mirror that provides them.
+    */
+    pub fn has_official_release(&self) -> bool {
+        self.libc != Some(BuildTargetLibc::Musl)
+    }
+
+    /**
+        Returns the URL to download the release archive from, for
+        this target and the given Lune version.
+    */
+    pub fn release_url(&self, version: &str) -> String {
+        self.release_url_from(RELEASE_BASE_URL, version)
+    }
+
+    /**
+        Returns the URL to download the release archive from, for this target and the
+        given Lune version, from a mirror with the same layout as the GitHub releases.
+    */
+    pub fn release_url_from(&self, base_url: &str, version: &str) -> String {
+        format!(
+            "{}/v{version}/{}.zip",
+            base_url.trim_end_matches('/'),
+            self.release_name(version)
+        )
+    }
+
+    /**
+        Detects the target of the given executable from its headers,
+        returning `None` if it is not for a target that Lune supports.
+
+        Linux executables do not record which C library they link
+        against, so they are always detected as using glibc.
+    */
+    pub fn detect_from_exe(exe: &[u8]) -> Option<Self> {
+        let file = object::File::parse(exe).ok()?;
+        let os = match file.format() {
+            BinaryFormat::Elf => BuildTargetOS::Linux,
+            BinaryFormat::MachO => BuildTargetOS::MacOS,
+            BinaryFormat::Pe => BuildTargetOS::Windows,
+            _ => return None,
+        };
+        let arch = match file.architecture() {
+            Architecture::X86_64 => BuildTargetArch::X86_64,
+            Architecture::Aarch64 => BuildTargetArch::Aarch64,
+            _ => return None,
+        };
+        Some(Self {
+            os,
+            arch,
+            libc: (os == BuildTargetOS::Linux).then_some(BuildTargetLibc::Gnu),
+        })
+    }
+
+    /**
+        Returns the file name used for this target in the cache directory.
+    */
+    pub fn cache_file_name(&self) -> String {
+        format!("{self}{}", self.exe_suffix())
+    }
+
     pub fn cache_path(&self) -> PathBuf {
-        CACHE_DIR.join(format!("{self}{}", self.os.exe_extension()))
+        CACHE_DIR.join(self.cache_file_name())
     }
 }
 
 impl fmt::Display for BuildTarget {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}-{}", self.os, self.arch)
+        write!(f, "{}-{}", self.os, self.arch)?;
+        // NOTE: The gnu libc is the default, and is left out so that names of
+        // releases and cached executables stay the same as they always were
+        match self.libc {
+            Some(BuildTargetLibc::Musl) => write!(f, "-musl"),
+            _ => Ok(()),
+        }
     }
 }
 
 impl FromStr for BuildTarget {
-    type Err = &'static str;
+    type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (left, right) = s
-            .split_once('-')
-            .ok_or("target must be in the form `os-arch`")?;
+        let invalid = |reason: &str| {
+            let supported = Self::all()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{reason} in target '{s}', supported targets are: {supported}")
+        };
+
+        let mut parts = s.split('-');
+        let (Some(os), Some(arch)) = (parts.next(), parts.next()) else {
+            return Err(invalid("missing architecture"));
+        };
+        let libc = parts.next();
+        if parts.next().is_some() {
+            return Err(invalid("too many components"));
+        }
+
+        let os = os.parse::<BuildTargetOS>().map_err(invalid)?;
+        let arch = arch.parse::<BuildTargetArch>().map_err(invalid)?;
+        let libc = match (os, libc) {
+            (BuildTargetOS::Linux, None) => Some(BuildTargetLibc::Gnu),
+            (BuildTargetOS::Linux, Some(libc)) => Some(libc.parse().map_err(invalid)?),
+            (_, None) => None,
+            (_, Some(_)) => return Err(invalid("libc is only supported for linux")),
+        };
+
+        Ok(Self { os, arch, libc })
+    }
+}
 
-        let os = left.parse()?;
-        let arch = right.parse()?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(s: &str) -> BuildTarget {
+        s.parse().expect("valid target")
+    }
+
+    #[test]
+    fn parses_and_displays_targets() {
+        for s in [
+            "linux-aarch64",
+            "linux-x86_64",
+            "macos-aarch64",
+            "macos-x86_64",
+            "windows-x86_64",
+        ] {
+            assert_eq!(target(s).to_string(), s);
+        }
+        assert_eq!(target("win-x64").to_string(), "windows-x86_64");
+        assert_eq!(target("darwin-arm64").to_string(), "macos-aarch64");
+        assert!("linux".parse::<BuildTarget>().is_err());
+        assert!("plan9-x86_64".parse::<BuildTarget>().is_err());
+    }
+
+    #[test]
+    fn parses_libc_variants() {
+        assert_eq!(target("linux-x86_64-musl").to_string(), "linux-x86_64-musl");
+        assert_eq!(target("linux-aarch64-gnu"), target("linux-aarch64"));
+        assert_eq!(target("linux-aarch64-gnu").to_string(), "linux-aarch64");
+        assert!("linux-x86_64-uclibc".parse::<BuildTarget>().is_err());
+        assert!("linux-x86_64-musl-extra".parse::<BuildTarget>().is_err());
+
+        let err = "windows-x86_64-musl".parse::<BuildTarget>().unwrap_err();
+        assert!(err.contains("only supported for linux"));
+        assert!(err.contains("linux-aarch64-musl"));
+    }
+
+    #[test]
+    fn rust_target_triples() {
+        assert_eq!(
+            target("linux-x86_64").rust_target_triple(),
+            "x86_64-unknown-linux-gnu"
+        );
+        assert_eq!(
+            target("linux-aarch64-musl").rust_target_triple(),
+            "aarch64-unknown-linux-musl"
+        );
+        assert_eq!(
+            target("windows-aarch64").rust_target_triple(),
+            "aarch64-pc-windows-msvc"
+        );
+        assert_eq!(
+            target("macos-x86_64").rust_target_triple(),
+            "x86_64-apple-darwin"
+        );
+        for target in BuildTarget::all() {
+            assert_eq!(target.to_string().parse::<BuildTarget>(), Ok(target));
+        }
+    }
+
+    #[test]
+    fn release_urls_from_mirrors() {
+        assert_eq!(
+            target("macos-aarch64").release_url_from("https://mirror.example.com/lune/", "0.10.4"),
+            "https://mirror.example.com/lune/v0.10.4/lune-0.10.4-macos-aarch64.zip"
+        );
+    }
+
+    #[test]
+    fn detects_targets_of_executables() {
+        let exe = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+        let detected = BuildTarget::detect_from_exe(&exe).unwrap();
+        let current = BuildTarget::current_system();
+        assert_eq!((detected.os, detected.arch), (current.os, current.arch));
+        assert_eq!(BuildTarget::detect_from_exe(b"not an executable"), None);
+    }
+
+    #[test]
+    fn base_exe_names() {
+        assert_eq!(target("windows-x86_64").base_exe_name(), "lune.exe");
+        assert_eq!(target("linux-x86_64").base_exe_name(), "lune");
+        assert_eq!(target("macos-aarch64").base_exe_name(), "lune");
+    }
+
+    #[test]
+    fn exe_extensions() {
+        assert_eq!(target("windows-aarch64").exe_extension(), "exe");
+        assert_eq!(target("linux-aarch64").exe_extension(), "");
+        assert_eq!(
+            target("windows-aarch64").cache_file_name(),
+            "windows-aarch64.exe"
+        );
+        assert_eq!(target("linux-aarch64").cache_file_name(), "linux-aarch64");
+        assert_eq!(target("linux-x86_64-musl").exe_extension(), "");
+        assert_eq!(
+            target("linux-x86_64-musl").cache_file_name(),
+            "linux-x86_64-musl"
+        );
+    }
 
-        Ok(Self { os, arch })
+    #[test]
+    fn release_urls() {
+        assert_eq!(
+            target("linux-x86_64").release_url("0.10.4"),
+            "https://github.com/lune-org/lune/releases/download/v0.10.4/lune-0.10.4-linux-x86_64.zip"
+        );
+        assert_eq!(
+            target("linux-x86_64-musl").release_url("0.10.4"),
+            "https://github.com/lune-org/lune/releases/download/v0.10.4/lune-0.10.4-linux-x86_64-musl.zip"
+        );
+        assert!(target("linux-x86_64").has_official_release());
+        assert!(target("windows-x86_64").has_official_release());
+        assert!(!target("linux-x86_64-musl").has_official_release());
     }
 }
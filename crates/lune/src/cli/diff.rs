@@ -0,0 +1,87 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use anyhow::{Context, Result};
+use async_fs as fs;
+use clap::Parser;
+use console::style;
+
+use crate::{
+    fs::{ZipFileEntry, ZipFileSystem},
+    standalone::metadata::Metadata,
+};
+
+/// Compare the embedded files of two standalone executables
+#[derive(Debug, Clone, Parser)]
+pub struct DiffCommand {
+    /// The path to the first standalone executable
+    pub a: PathBuf,
+
+    /// The path to the second standalone executable
+    pub b: PathBuf,
+}
+
+impl DiffCommand {
+    pub async fn run(self) -> Result<ExitCode> {
+        let a = read_embedded_entries(&self.a).await?;
+        let b = read_embedded_entries(&self.b).await?;
+
+        let (mut added, mut removed, mut changed) = (0, 0, 0);
+        for (name, entry_a) in &a {
+            match b.get(name) {
+                None => {
+                    removed += 1;
+                    println!("{} {name}", style("-").red().bold());
+                }
+                Some(entry_b) if entry_a.crc32 != entry_b.crc32 || entry_a.size != entry_b.size => {
+                    changed += 1;
+                    println!(
+                        "{} {name} ({} bytes, crc {:08x} -> {} bytes, crc {:08x})",
+                        style("~").yellow().bold(),
+                        entry_a.size,
+                        entry_a.crc32,
+                        entry_b.size,
+                        entry_b.crc32,
+                    );
+                }
+                Some(_) => {}
+            }
+        }
+        for (name, entry_b) in &b {
+            if !a.contains_key(name) {
+                added += 1;
+                println!(
+                    "{} {name} ({} bytes)",
+                    style("+").green().bold(),
+                    entry_b.size
+                );
+            }
+        }
+
+        // Same as the diff command line tool, differences result in a non-zero exit code
+        if added + removed + changed == 0 {
+            println!("Embedded files are identical");
+            Ok(ExitCode::SUCCESS)
+        } else {
+            println!("{added} added, {removed} removed, {changed} changed");
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+async fn read_embedded_entries(path: &Path) -> Result<BTreeMap<String, ZipFileEntry>> {
+    let bytes = fs::read(path)
+        .await
+        .with_context(|| format!("failed to read '{}'", path.display()))?;
+    let meta = Metadata::from_exe_bytes(bytes)
+        .with_context(|| format!("'{}' is not a standalone executable", path.display()))?;
+    let zip_fs = ZipFileSystem::new(meta.zip_data)?;
+    Ok(zip_fs
+        .file_entries()?
+        .into_iter()
+        .map(|entry| (entry.name.clone(), entry))
+        .collect())
+}
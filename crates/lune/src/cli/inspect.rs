@@ -0,0 +1,73 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use anyhow::{Context, Result};
+use async_fs as fs;
+use clap::Parser;
+use console::style;
+
+use crate::{fs::ZipFileSystem, standalone::metadata::Metadata};
+
+/// Print the format version, entry points and embedded files of a standalone executable
+#[derive(Debug, Clone, Parser)]
+pub struct InspectCommand {
+    /// The path to the standalone executable
+    pub binary: PathBuf,
+}
+
+impl InspectCommand {
+    pub async fn run(self) -> Result<ExitCode> {
+        let bytes = fs::read(&self.binary)
+            .await
+            .with_context(|| format!("failed to read '{}'", self.binary.display()))?;
+        if !Metadata::is_standalone_exe(&bytes) {
+            eprintln!(
+                "'{}' is not a standalone executable built using Lune",
+                self.binary.display()
+            );
+            return Ok(ExitCode::FAILURE);
+        }
+
+        let meta = Metadata::from_exe_bytes(&bytes).with_context(|| {
+            format!(
+                "failed to read standalone executable '{}'",
+                self.binary.display()
+            )
+        })?;
+        let entries = ZipFileSystem::new(meta.zip_data.clone())?.file_entries()?;
+
+        println!(
+            "{} {}",
+            style("Standalone executable").bold(),
+            self.binary.display()
+        );
+        println!("Format version: v{}", meta.format_version);
+        println!(
+            "Lune version: {}",
+            meta.lune_version.as_deref().unwrap_or("unknown")
+        );
+        println!("Checksum: {:08x}", crc32fast::hash(&meta.zip_data));
+
+        // Without any arguments, the main entry point always runs, and named
+        // entry points run when their name is given as the first argument
+        println!("\n{}", style("Entry points:").bold());
+        println!("    {} {}", style("(default)").dim(), meta.entry_name);
+        for (name, file) in &meta.entries {
+            println!("    {} {file}", style(name).green());
+        }
+
+        let total = entries.iter().map(|entry| entry.size).sum::<u64>();
+        println!(
+            "\n{}",
+            style(format!(
+                "Embedded files ({} files, {total} bytes):",
+                entries.len()
+            ))
+            .bold()
+        );
+        for entry in &entries {
+            println!("    {} ({} bytes)", entry.name, entry.size);
+        }
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
@@ -4,6 +4,8 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 
 pub(crate) mod build;
+pub(crate) mod diff;
+pub(crate) mod inspect;
 pub(crate) mod list;
 pub(crate) mod repl;
 pub(crate) mod run;
@@ -12,8 +14,8 @@ pub(crate) mod tauri;
 pub(crate) mod utils;
 
 pub use self::{
-    build::BuildCommand, list::ListCommand, repl::ReplCommand, run::RunCommand,
-    setup::SetupCommand, tauri::TauriCommand,
+    build::BuildCommand, diff::DiffCommand, inspect::InspectCommand, list::ListCommand,
+    repl::ReplCommand, run::RunCommand, setup::SetupCommand, tauri::TauriCommand,
 };
 
 #[derive(Debug, Clone, Subcommand)]
@@ -22,6 +24,8 @@ pub enum CliSubcommand {
     List(ListCommand),
     Setup(SetupCommand),
     Build(BuildCommand),
+    Diff(DiffCommand),
+    Inspect(InspectCommand),
     Repl(ReplCommand),
     Tauri(TauriCommand),
 }
@@ -77,6 +81,8 @@ impl Cli {
             CliSubcommand::List(cmd) => cmd.run().await,
             CliSubcommand::Setup(cmd) => cmd.run().await,
             CliSubcommand::Build(cmd) => cmd.run().await,
+            CliSubcommand::Diff(cmd) => cmd.run().await,
+            CliSubcommand::Inspect(cmd) => cmd.run().await,
             CliSubcommand::Repl(cmd) => cmd.run().await,
             CliSubcommand::Tauri(cmd) => cmd.run().await,
         }
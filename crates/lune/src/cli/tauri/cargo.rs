@@ -0,0 +1,283 @@
+/*!
+    Running `cargo build` for generated Tauri apps, with progress reporting.
+
+    Cargo is run with `--message-format=json`, and its messages are used to show
+    a spinner with the crate that was most recently compiled, instead of the full
+    cargo output. Output is only shown if the build fails, in which case the
+    compiler errors and the last lines that cargo wrote to stderr are printed.
+*/
+
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, BufReader},
+    path::Path,
+    process::{Command, Stdio},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use console::{Term, style};
+use serde::Deserialize;
+
+/// How many of the last lines that cargo wrote to stderr are shown when a build fails
+const STDERR_TAIL_LINES: usize = 30;
+
+const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const SPINNER_INTERVAL: Duration = Duration::from_millis(80);
+
+/**
+    A single JSON message written to stdout by cargo, when using `--message-format=json`.
+
+    Only the messages needed for progress and errors are parsed, all others are ignored.
+*/
+#[derive(Debug, Deserialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+enum CargoMessage {
+    CompilerArtifact {
+        target: CargoTarget,
+    },
+    CompilerMessage {
+        message: CargoDiagnostic,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoTarget {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoDiagnostic {
+    level: String,
+    rendered: Option<String>,
+}
+
+/**
+    Progress of a running build, shared with the thread that draws the spinner.
+*/
+#[derive(Debug, Default)]
+struct BuildProgress {
+    compiled: usize,
+    current: Option<String>,
+}
+
+impl BuildProgress {
+    fn message(&self) -> String {
+        match &self.current {
+            Some(name) => format!(
+                "Compiling... {} crates done, last was {name}",
+                self.compiled
+            ),
+            None => "Compiling...".to_string(),
+        }
+    }
+}
+
+/**
+    Makes sure that cargo can be run, so that a missing Rust toolchain is
+    reported before any project files are generated, instead of at the end.
+*/
+pub fn check_cargo_installed() -> Result<()> {
+    let status = Command::new("cargo")
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| spawn_error(e, "cargo --version"))?;
+    if !status.success() {
+        bail!(
+            "Cargo is installed, but `cargo --version` failed with exit code: {:?}",
+            status.code()
+        );
+    }
+    Ok(())
+}
+
+/**
+    Converts an error from starting cargo into one that explains
+    how to install it, if cargo could not be found at all.
+*/
+fn spawn_error(e: io::Error, command: &str) -> anyhow::Error {
+    if e.kind() == io::ErrorKind::NotFound {
+        anyhow!(
+            "Could not find cargo, which is needed to build Tauri apps\n\
+            Install Rust and cargo using rustup from https://rustup.rs, and make sure \
+            that cargo is available in PATH, then try again"
+        )
+    } else {
+        anyhow!(e).context(format!("Failed to run {command}"))
+    }
+}
+
+/**
+    Runs `cargo build --release` in the given directory, showing a
+    spinner while it runs, and the errors from cargo if it fails.
+*/
+pub fn cargo_build_release(dir: &Path) -> Result<()> {
+    let mut child = Command::new("cargo")
+        .arg("build")
+        .arg("--release")
+        .arg("--message-format=json")
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| spawn_error(e, "cargo build"))?;
+
+    // Cargo blocks if either of its pipes fill up, so stderr
+    // is drained on its own thread, keeping only the last lines
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let stderr_tail = thread::spawn(move || {
+        let mut tail = VecDeque::with_capacity(STDERR_TAIL_LINES);
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            if tail.len() == STDERR_TAIL_LINES {
+                tail.pop_front();
+            }
+            tail.push_back(line);
+        }
+        tail
+    });
+
+    let progress = Arc::new(Mutex::new(BuildProgress::default()));
+    let spinner = Spinner::start(Arc::clone(&progress));
+
+    let mut errors = Vec::new();
+    let stdout = child.stdout.take().expect("stdout is piped");
+    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+        match serde_json::from_str::<CargoMessage>(&line) {
+            Ok(CargoMessage::CompilerArtifact { target }) => {
+                let mut progress = progress.lock().unwrap();
+                progress.compiled += 1;
+                progress.current = Some(target.name);
+            }
+            Ok(CargoMessage::CompilerMessage { message }) if message.level.starts_with("error") => {
+                errors.extend(message.rendered);
+            }
+            Ok(_) | Err(_) => {}
+        }
+    }
+
+    let status = child.wait().context("Failed to run cargo build")?;
+    let stderr_tail = stderr_tail.join().unwrap_or_default();
+    spinner.stop();
+
+    if !status.success() {
+        for error in &errors {
+            eprint!("{error}");
+        }
+        if !stderr_tail.is_empty() {
+            eprintln!(
+                "  {} Last {} lines of cargo output:",
+                style("→").dim(),
+                stderr_tail.len()
+            );
+            for line in &stderr_tail {
+                eprintln!("{line}");
+            }
+        }
+        bail!("Cargo build failed with exit code: {:?}", status.code());
+    }
+
+    let compiled = progress.lock().unwrap().compiled;
+    println!("  {} Compiled {compiled} crates", style("→").dim());
+
+    Ok(())
+}
+
+/**
+    A spinner drawn on stderr by a separate thread, showing the current build progress.
+
+    Nothing is drawn if stderr is not a terminal, such as when it is redirected to a file.
+*/
+struct Spinner {
+    stopped: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Spinner {
+    fn start(progress: Arc<Mutex<BuildProgress>>) -> Self {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let term = Term::stderr();
+        if !term.is_term() {
+            return Self {
+                stopped,
+                thread: None,
+            };
+        }
+
+        let thread = thread::spawn({
+            let stopped = Arc::clone(&stopped);
+            move || {
+                for frame in SPINNER_FRAMES.iter().cycle() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let message = progress.lock().unwrap().message();
+                    let line = format!("  {} {}", style(frame).cyan(), message);
+                    let line = console::truncate_str(&line, term.size().1 as usize, "…");
+                    let _ = term.clear_line();
+                    let _ = term.write_str(&line);
+                    thread::sleep(SPINNER_INTERVAL);
+                }
+                let _ = term.clear_line();
+            }
+        });
+
+        Self {
+            stopped,
+            thread: Some(thread),
+        }
+    }
+
+    fn stop(self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explains_missing_cargo() {
+        let missing = spawn_error(io::ErrorKind::NotFound.into(), "cargo build");
+        assert!(missing.to_string().contains("https://rustup.rs"));
+
+        let denied = spawn_error(io::ErrorKind::PermissionDenied.into(), "cargo build");
+        assert_eq!(denied.to_string(), "Failed to run cargo build");
+    }
+
+    #[test]
+    fn parses_cargo_messages() {
+        let artifact = r#"{"reason":"compiler-artifact","package_id":"serde 1.0.0","target":{"kind":["lib"],"name":"serde"},"fresh":false}"#;
+        assert!(matches!(
+            serde_json::from_str::<CargoMessage>(artifact).unwrap(),
+            CargoMessage::CompilerArtifact { target } if target.name == "serde"
+        ));
+
+        let error = r#"{"reason":"compiler-message","message":{"level":"error","rendered":"error: oops\n"}}"#;
+        assert!(matches!(
+            serde_json::from_str::<CargoMessage>(error).unwrap(),
+            CargoMessage::CompilerMessage { message }
+                if message.level == "error" && message.rendered.as_deref() == Some("error: oops\n")
+        ));
+
+        let script = r#"{"reason":"build-script-executed","package_id":"tauri 2.0.0"}"#;
+        assert!(matches!(
+            serde_json::from_str::<CargoMessage>(script).unwrap(),
+            CargoMessage::Other
+        ));
+    }
+}
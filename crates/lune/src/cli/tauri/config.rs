@@ -0,0 +1,429 @@
+/*!
+    Evaluation of Luau scripts to find the config given to `tauri.new`.
+
+    Instead of searching the source code for config fields, the script is run
+    in a separate Luau VM where `@lune/tauri` records the first config given to
+    `tauri.new` and never opens any windows. This means that configs assembled
+    from variables, across several statements, or in required modules all work.
+
+    Other standard libraries are replaced with inert stubs, so that the script
+    can not have any side effects while building, and calling them returns
+    more stubs instead of real values - config values derived from them will
+    fall back to their defaults, same as if they were not given at all. Any
+    other values of the wrong type are errors, same as at runtime.
+*/
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, anyhow, bail};
+use lune_utils::{
+    fs::{FileSystem, StdFileSystem},
+    tauri::{ConfigIcon, ConfigOptions, check_config_keys, check_identifier},
+};
+use mlua::prelude::*;
+
+use super::icons::TauriIcon;
+
+// NOTE: Keep this in sync with the default policy in lune-std-tauri
+pub const DEFAULT_CSP: &str = "default-src 'self' ipc: http://ipc.localhost; img-src 'self' asset: http://asset.localhost data:; style-src 'self' 'unsafe-inline'";
+
+/**
+    How long a script may run for before evaluation gives up, so that
+    scripts with top-level loops can not hang the build forever.
+*/
+const EVALUATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub struct TauriConfig {
+    pub name: String,
+    pub identifier: String,
+    pub version: String,
+    pub icon: Option<TauriIcon>,
+    pub html: Option<String>,
+    pub window_title: String,
+    pub window_width: u32,
+    pub window_height: u32,
+    pub window_x: Option<i32>,
+    pub window_y: Option<i32>,
+    pub window_center: bool,
+    pub window_min_width: Option<u32>,
+    pub window_min_height: Option<u32>,
+    pub window_resizable: bool,
+    pub window_decorations: bool,
+    pub window_transparent: bool,
+    pub window_always_on_top: bool,
+    pub window_fullscreen: bool,
+    pub csp: Option<String>,
+}
+
+impl TauriConfig {
+    /**
+        Reads a config from the table given to `tauri.new`, using the same
+        defaults as `lune-std-tauri` for any fields that were not given.
+    */
+    fn from_table(config: Option<&LuaTable>) -> Result<Self> {
+        if let Some(config) = config {
+            check_config_keys(config)?;
+        }
+        let options = ConfigOptions::new(config, "").with_absent(is_stub);
+
+        let name = options
+            .option("name")?
+            .unwrap_or_else(|| "Lune App".to_string());
+        let identifier = options
+            .option("identifier")?
+            .unwrap_or_else(|| "org.lune.app".to_string());
+        check_identifier(&identifier)?;
+
+        // An empty policy would silently disable the CSP entirely, same as at runtime
+        let csp = match config.map(|config| config.get::<LuaValue>("csp")) {
+            None | Some(Ok(LuaValue::Nil)) => None,
+            Some(Ok(LuaValue::String(csp))) if !csp.to_string_lossy().trim().is_empty() => {
+                Some(csp.to_string_lossy())
+            }
+            _ => bail!("csp must be a non-empty string"),
+        };
+
+        let icon = match options.icon("icon")? {
+            Some(ConfigIcon::Path(path)) => Some(TauriIcon::File(path)),
+            Some(ConfigIcon::Text { text, background }) => {
+                Some(TauriIcon::text(text, background.as_deref())?)
+            }
+            None => None,
+        };
+
+        let window = options.table("window")?;
+        let window = options.nested(window.as_ref(), "window.");
+
+        Ok(Self {
+            version: options
+                .option("version")?
+                .unwrap_or_else(|| "0.1.0".to_string()),
+            icon,
+            html: options.option("html")?,
+            window_title: window.option("title")?.unwrap_or_else(|| name.clone()),
+            window_width: window.size("width", 800)?,
+            window_height: window.size("height", 600)?,
+            window_x: window.option("x")?,
+            window_y: window.option("y")?,
+            window_center: window.option("center")?.unwrap_or(false),
+            window_min_width: window.option("min_width")?,
+            window_min_height: window.option("min_height")?,
+            window_resizable: window.option("resizable")?.unwrap_or(true),
+            window_decorations: window.option("decorations")?.unwrap_or(true),
+            window_transparent: window.option("transparent")?.unwrap_or(false),
+            window_always_on_top: window.option("always_on_top")?.unwrap_or(false),
+            window_fullscreen: window.option("fullscreen")?.unwrap_or(false),
+            name,
+            identifier,
+            csp,
+        })
+    }
+
+    /**
+        Returns the name of the generated cargo package, which is
+        also the name of the built executable, without any extension.
+    */
+    pub fn package_name(&self) -> String {
+        self.name.replace(' ', "-").to_lowercase()
+    }
+}
+
+/**
+    Evaluates the script at the given path, with the given contents, and
+    returns the config given to the first call to `tauri.new` in it.
+
+    Scripts that never call `tauri.new` get the default config, and any errors
+    thrown after the config was recorded are ignored, since stubbed libraries
+    may not behave the same as the real ones did.
+*/
+pub fn evaluate_tauri_config(path: &Path, script: &str) -> Result<TauriConfig> {
+    let sandbox = Sandbox::new()?;
+    let result = sandbox.run(path, script);
+    let captured = sandbox.captured.borrow_mut().take();
+
+    match (result, captured) {
+        (_, Some(config)) => TauriConfig::from_table(Some(&config)),
+        (Ok(()), None) => TauriConfig::from_table(None),
+        (Err(e), None) => Err(anyhow!(
+            "failed to evaluate '{}' to find its tauri.new config:\n{e}",
+            path.display()
+        )),
+    }
+}
+
+struct Sandbox {
+    lua: Lua,
+    captured: Rc<RefCell<Option<LuaTable>>>,
+    modules: Rc<RefCell<HashMap<PathBuf, LuaValue>>>,
+}
+
+impl Sandbox {
+    fn new() -> LuaResult<Self> {
+        let lua = Lua::new();
+        lua.sandbox(true)?;
+
+        let started = Instant::now();
+        lua.set_interrupt(move |_| {
+            if started.elapsed() > EVALUATION_TIMEOUT {
+                return Err(LuaError::runtime(format!(
+                    "script did not finish within {} seconds",
+                    EVALUATION_TIMEOUT.as_secs()
+                )));
+            }
+            Ok(LuaVmState::Continue)
+        });
+
+        Ok(Self {
+            lua,
+            captured: Rc::default(),
+            modules: Rc::default(),
+        })
+    }
+
+    fn run(&self, path: &Path, script: &str) -> LuaResult<()> {
+        let tauri = self.create_tauri_stub()?;
+        let env = create_env(&self.lua, path, tauri, Rc::clone(&self.modules))?;
+        self.lua
+            .load(script)
+            .set_name(format!("@{}", path.display()))
+            .set_environment(env)
+            .exec()
+    }
+
+    /**
+        Creates the stubbed `@lune/tauri` library, where `tauri.new`
+        records its config and returns an app that does nothing.
+    */
+    fn create_tauri_stub(&self) -> LuaResult<LuaTable> {
+        let stub = create_stub(&self.lua)?;
+        let captured = Rc::clone(&self.captured);
+        let tauri = self.lua.create_table()?;
+        tauri.set("version", "stub")?;
+        tauri.set(
+            "new",
+            self.lua
+                .create_function(move |lua, config: Option<LuaTable>| {
+                    let mut captured = captured.borrow_mut();
+                    if captured.is_none() {
+                        *captured = Some(match config {
+                            Some(config) => config,
+                            None => lua.create_table()?,
+                        });
+                    }
+                    Ok(stub.clone())
+                })?,
+        )?;
+        tauri.set_metatable(Some(stub_metatable(&self.lua)?))?;
+        Ok(tauri)
+    }
+}
+
+/**
+    Creates the environment for a module at the given path, with a `require` that
+    resolves relative modules from it, and returns stubs for all other libraries.
+*/
+fn create_env(
+    lua: &Lua,
+    path: &Path,
+    tauri: LuaTable,
+    modules: Rc<RefCell<HashMap<PathBuf, LuaValue>>>,
+) -> LuaResult<LuaTable> {
+    let from = path.to_path_buf();
+    let require = lua.create_function(move |lua, specifier: String| {
+        if specifier == "@lune/tauri" {
+            return Ok(LuaValue::Table(tauri.clone()));
+        }
+        if !(specifier.starts_with("./")
+            || specifier.starts_with("../")
+            || specifier.starts_with("@self/"))
+        {
+            return Ok(LuaValue::Table(create_stub(lua)?));
+        }
+
+        let target = StdFileSystem
+            .resolve(&from, &specifier)
+            .map_err(|e| LuaError::runtime(format!("could not require '{specifier}': {e}")))?;
+        if let Some(module) = modules.borrow().get(&target) {
+            return Ok(module.clone());
+        }
+
+        let source = StdFileSystem.read(&target).into_lua_err()?;
+        let env = create_env(lua, &target, tauri.clone(), Rc::clone(&modules))?;
+        let module = lua
+            .load(source)
+            .set_name(format!("@{}", target.display()))
+            .set_environment(env)
+            .eval::<LuaValue>()?;
+        modules.borrow_mut().insert(target, module.clone());
+        Ok(module)
+    })?;
+
+    let env = lua.create_table()?;
+    env.set("require", require)?;
+    let meta = lua.create_table()?;
+    meta.set("__index", lua.globals())?;
+    env.set_metatable(Some(meta))?;
+    Ok(env)
+}
+
+/**
+    Creates an inert stub, which returns itself when indexed or called.
+*/
+fn create_stub(lua: &Lua) -> LuaResult<LuaTable> {
+    let stub = lua.create_table()?;
+    stub.set_metatable(Some(stub_metatable(lua)?))?;
+    Ok(stub)
+}
+
+/**
+    Checks if the given value is a stub, or a stubbed library, which config
+    values derived from stubbed libraries are, and are treated as not given.
+*/
+fn is_stub(value: &LuaValue) -> bool {
+    let LuaValue::Table(table) = value else {
+        return false;
+    };
+    table
+        .metatable()
+        .is_some_and(|meta| meta.raw_get::<bool>(STUB_MARKER).unwrap_or(false))
+}
+
+/// Set in the metatable of all stubs, so that values derived from them can be told apart
+const STUB_MARKER: &str = "__lune_stub";
+
+fn stub_metatable(lua: &Lua) -> LuaResult<LuaTable> {
+    let meta = lua.create_table()?;
+    meta.raw_set(STUB_MARKER, true)?;
+    meta.set(
+        "__index",
+        lua.create_function(|lua, _: LuaMultiValue| create_stub(lua))?,
+    )?;
+    meta.set(
+        "__call",
+        lua.create_function(|lua, _: LuaMultiValue| create_stub(lua))?,
+    )?;
+    Ok(meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(script: &str) -> Result<TauriConfig> {
+        evaluate_tauri_config(Path::new("main.luau"), script)
+    }
+
+    #[test]
+    fn evaluates_computed_configs() {
+        let config = evaluate(
+            "-- width = 1 in a comment should not matter\n\
+            local tauri = require('@lune/tauri')\n\
+            local fs = require('@lune/fs')\n\
+            local base = 'com.example'\n\
+            local options = { name = 'My App', window = {} }\n\
+            options.identifier = base .. '.app'\n\
+            options.window.width = 640 * 2\n\
+            options.window.resizable = false\n\
+            local app = tauri.new(options)\n\
+            app:listen('event', function() end)\n\
+            app:run()\n\
+            fs.writeFile('out.txt', 'never written')",
+        )
+        .unwrap();
+        assert_eq!(config.name, "My App");
+        assert_eq!(config.identifier, "com.example.app");
+        assert_eq!(config.window_title, "My App");
+        assert_eq!(config.window_width, 1280);
+        assert_eq!(config.window_height, 600);
+        assert!(!config.window_resizable);
+        assert!(config.window_decorations);
+    }
+
+    #[test]
+    fn reads_window_position() {
+        let config = evaluate(
+            "require('@lune/tauri').new({ window = { x = 100, y = -50, center = true } })",
+        )
+        .unwrap();
+        assert_eq!(config.window_x, Some(100));
+        assert_eq!(config.window_y, Some(-50));
+        assert!(config.window_center);
+        assert!(!evaluate("local x = 1").unwrap().window_center);
+    }
+
+    #[test]
+    fn rejects_options_of_the_wrong_type() {
+        let new = |options: &str| evaluate(&format!("require('@lune/tauri').new({options})"));
+        assert!(new("{ window = { width = '800' } }").is_err());
+        assert!(new("{ window = { resizable = 'no' } }").is_err());
+        assert!(new("{ window = { x = 1.5 } }").is_err());
+        assert!(new("{ window = { min_width = -1 } }").is_err());
+        assert!(new("{ window = true }").is_err());
+        assert!(new("{ name = 5 }").is_err());
+        assert!(new("{ icon = 5 }").is_err());
+        assert!(new("{ icon = true }").is_err());
+        assert!(new("{ identifer = 'com.example.app' }").is_err());
+        assert!(new("{ identifier = 'app' }").is_err());
+
+        // Values derived from stubbed libraries still fall back to their defaults
+        let config = evaluate(
+            "local fs = require('@lune/fs')\n\
+            require('@lune/tauri').new({ name = fs.name, window = { width = fs.width } })",
+        )
+        .unwrap();
+        assert_eq!(config.name, "Lune App");
+        assert_eq!(config.window_width, 800);
+    }
+
+    #[test]
+    fn reads_text_icons() {
+        let config = evaluate(
+            "require('@lune/tauri').new({ icon = { text = 'L', background = '#3366ff' } })",
+        )
+        .unwrap();
+        assert_eq!(
+            config.icon,
+            Some(TauriIcon::Text {
+                text: "L".to_string(),
+                background: [0x33, 0x66, 0xFF, 0xFF],
+            })
+        );
+
+        let config =
+            evaluate("require('@lune/tauri').new({ icon = { path = 'icon.png', text = 'L' } })")
+                .unwrap();
+        assert_eq!(config.icon, Some(TauriIcon::File("icon.png".to_string())));
+
+        assert!(
+            evaluate("require('@lune/tauri').new({ icon = { text = 'L', background = 'blue' } })")
+                .is_err()
+        );
+        assert!(evaluate("require('@lune/tauri').new({ icon = {} })").is_err());
+    }
+
+    #[test]
+    fn captures_only_the_first_config() {
+        let config = evaluate(
+            "local tauri = require(\"@lune/tauri\")\n\
+            tauri.new({ name = \"First\" })\n\
+            tauri.new({ name = \"Second\" })\n\
+            error(\"errors after the config is captured are ignored\")",
+        )
+        .unwrap();
+        assert_eq!(config.name, "First");
+    }
+
+    #[test]
+    fn reports_errors_before_capture() {
+        assert!(evaluate("error(\"oops\")").is_err());
+        assert!(evaluate("require('@lune/tauri').new({ csp = '' })").is_err());
+        assert_eq!(evaluate("local x = 1").unwrap().name, "Lune App");
+    }
+}
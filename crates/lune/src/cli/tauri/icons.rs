@@ -0,0 +1,531 @@
+/*!
+    Generation of the icons for built Tauri apps.
+
+    A single source image, either the PNG given as `icon` in `tauri.new`, a
+    glyph rendered from the text given as `icon = { text = "L" }`, or a
+    placeholder, is resized into the multi-resolution `icon.ico` that `tauri-build`
+    embeds as the Windows resource of the executable, and the PNG icons that
+    Tauri uses for windows on other platforms.
+
+    Text is rendered using a small built-in pixel font, since no font files
+    are bundled with Lune, meaning that only letters, digits and a few symbols
+    are supported - emoji and other characters need to be given as a PNG file.
+*/
+
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use async_fs as fs;
+use ico::{IconDir, IconDirEntry, IconImage, ResourceType};
+
+/// Sizes of the images in `icon.ico`, where the largest is used by Explorer
+const ICO_SIZES: &[u32] = &[16, 32, 48, 256];
+
+/// File names and sizes of the PNG icons that Tauri expects
+const PNG_ICONS: &[(&str, u32)] = &[
+    ("32x32.png", 32),
+    ("128x128.png", 128),
+    ("128x128@2x.png", 256),
+    ("icon.png", 512),
+];
+
+/// Size and color of the placeholder icon, used for apps that do not specify one
+const PLACEHOLDER_SIZE: u32 = 256;
+const PLACEHOLDER_COLOR: [u8; 4] = [0x00, 0x80, 0xFF, 0xFF];
+
+/// The most characters that fit on a text icon while staying legible at small sizes
+const MAX_ICON_TEXT_LEN: usize = 3;
+
+/// Size of the glyphs in the built-in font, in pixels
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+
+/**
+    Glyphs of the built-in pixel font, one row per byte, with
+    the leftmost pixel of each row in the highest of the five bits.
+*/
+#[rustfmt::skip]
+const GLYPHS: &[(char, [u8; GLYPH_HEIGHT as usize])] = &[
+    ('A', [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('B', [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110]),
+    ('C', [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110]),
+    ('D', [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100]),
+    ('E', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111]),
+    ('F', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('G', [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111]),
+    ('H', [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('I', [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('J', [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100]),
+    ('K', [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001]),
+    ('L', [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111]),
+    ('M', [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001]),
+    ('N', [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001]),
+    ('O', [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('P', [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('Q', [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101]),
+    ('R', [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001]),
+    ('S', [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110]),
+    ('T', [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100]),
+    ('U', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('V', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100]),
+    ('W', [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010]),
+    ('X', [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001]),
+    ('Y', [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100]),
+    ('Z', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111]),
+    ('0', [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110]),
+    ('1', [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('2', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111]),
+    ('3', [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110]),
+    ('4', [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010]),
+    ('5', [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110]),
+    ('6', [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110]),
+    ('7', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000]),
+    ('8', [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110]),
+    ('9', [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100]),
+    ('!', [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100]),
+    ('?', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100]),
+    ('+', [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000]),
+    ('-', [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000]),
+    ('#', [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010]),
+    ('&', [0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101]),
+    ('@', [0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110]),
+    ('.', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100]),
+];
+
+/**
+    The icon given as `icon` in `tauri.new`.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TauriIcon {
+    /// A PNG image at the given path, relative to the script
+    File(String),
+    /// A placeholder showing the given text on a solid background
+    Text { text: String, background: [u8; 4] },
+}
+
+impl TauriIcon {
+    /**
+        Creates a text icon, using the default placeholder color if no background was given.
+
+        The background is a hex color in the format `#rgb`, `#rrggbb` or `#rrggbbaa`.
+    */
+    pub fn text(text: String, background: Option<&str>) -> Result<Self> {
+        let background = match background {
+            Some(color) => parse_hex_color(color).with_context(|| {
+                format!("icon background '{color}' is not a color such as '#3366ff'")
+            })?,
+            None => PLACEHOLDER_COLOR,
+        };
+        Ok(Self::Text { text, background })
+    }
+}
+
+/**
+    Returns the paths of all generated icons, relative to the project directory.
+*/
+pub fn icon_paths() -> Vec<String> {
+    PNG_ICONS
+        .iter()
+        .map(|(name, _)| format!("icons/{name}"))
+        .chain(std::iter::once("icons/icon.ico".to_string()))
+        .collect()
+}
+
+/**
+    Generates all icons in the `icons` directory of the project, from the given
+    icon, with file paths relative to `input_dir`, or a placeholder if none was given.
+*/
+pub async fn generate_icons(dir: &Path, icon: Option<&TauriIcon>, input_dir: &Path) -> Result<()> {
+    let image = match icon {
+        Some(TauriIcon::File(path)) => {
+            let path: PathBuf = input_dir.join(path);
+            let bytes = fs::read(&path)
+                .await
+                .with_context(|| format!("Failed to read icon at {}", path.display()))?;
+            RgbaImage::decode_png(&bytes)
+                .with_context(|| format!("Failed to decode icon at {}", path.display()))?
+        }
+        Some(TauriIcon::Text { text, background }) => RgbaImage::render_text(text, *background)?,
+        None => RgbaImage::placeholder(),
+    };
+    let image = image.into_square();
+
+    let icons_dir = dir.join("icons");
+    fs::create_dir_all(&icons_dir).await?;
+
+    for (name, size) in PNG_ICONS {
+        let png = image.resize(*size).encode_png()?;
+        fs::write(icons_dir.join(name), png).await?;
+    }
+
+    fs::write(icons_dir.join("icon.ico"), image.encode_ico()?).await?;
+
+    Ok(())
+}
+
+/**
+    An image with 8-bit RGBA pixels, stored row by row.
+*/
+#[derive(Debug, Clone)]
+struct RgbaImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    fn decode_png(bytes: &[u8]) -> Result<Self> {
+        let mut decoder = png::Decoder::new(Cursor::new(bytes));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        buffer.truncate(info.buffer_size());
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => buffer
+                .chunks_exact(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 0xFF])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+                .collect(),
+            png::ColorType::Grayscale => buffer.iter().flat_map(|&g| [g, g, g, 0xFF]).collect(),
+            png::ColorType::Indexed => bail!("indexed colors were not expanded"),
+        };
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    fn placeholder() -> Self {
+        let pixel_count = (PLACEHOLDER_SIZE * PLACEHOLDER_SIZE) as usize;
+        Self {
+            width: PLACEHOLDER_SIZE,
+            height: PLACEHOLDER_SIZE,
+            pixels: PLACEHOLDER_COLOR.repeat(pixel_count),
+        }
+    }
+
+    /**
+        Renders the given text centered on a square of the given background color, using
+        the built-in pixel font, in white or black depending on which has more contrast.
+
+        Glyphs are scaled up by a whole number of pixels so that their edges stay sharp,
+        and are then smoothed when the image is resized to the sizes of the icons.
+    */
+    fn render_text(text: &str, background: [u8; 4]) -> Result<Self> {
+        let glyphs = text
+            .trim()
+            .chars()
+            .map(|c| {
+                let upper = c.to_ascii_uppercase();
+                GLYPHS
+                    .iter()
+                    .find(|(glyph, _)| *glyph == upper)
+                    .map(|(_, rows)| rows)
+                    .with_context(|| {
+                        format!(
+                            "icon text can not contain '{c}', only letters, digits and the \
+                            symbols ! ? + - # & @ . are supported - use a PNG file instead"
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if glyphs.is_empty() || glyphs.len() > MAX_ICON_TEXT_LEN {
+            bail!(
+                "icon text must be between 1 and {MAX_ICON_TEXT_LEN} characters long, got '{text}'"
+            );
+        }
+
+        // Glyphs are separated by a single empty column, and together take up
+        // at most about two thirds of the icon in either direction
+        let columns = glyphs.len() as u32 * (GLYPH_WIDTH + 1) - 1;
+        let scale =
+            (PLACEHOLDER_SIZE * 2 / 3 / columns).min(PLACEHOLDER_SIZE * 2 / 3 / GLYPH_HEIGHT);
+        let offset_x = (PLACEHOLDER_SIZE - columns * scale) / 2;
+        let offset_y = (PLACEHOLDER_SIZE - GLYPH_HEIGHT * scale) / 2;
+
+        let [red, green, blue, _] = background.map(f64::from);
+        let luminance = (0.299 * red + 0.587 * green + 0.114 * blue) / 255.0;
+        let foreground = if luminance > 0.6 {
+            [0x00, 0x00, 0x00, 0xFF]
+        } else {
+            [0xFF, 0xFF, 0xFF, 0xFF]
+        };
+
+        let mut image = Self::placeholder();
+        for pixel in image.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&background);
+        }
+        for (index, rows) in glyphs.iter().enumerate() {
+            let glyph_x = offset_x + index as u32 * (GLYPH_WIDTH + 1) * scale;
+            for (row, bits) in rows.iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                        continue;
+                    }
+                    let left = glyph_x + column * scale;
+                    let top = offset_y + row as u32 * scale;
+                    image.fill_rect(left, top, scale, foreground);
+                }
+            }
+        }
+        Ok(image)
+    }
+
+    /**
+        Fills the square of the given size, with its top left corner at the given position.
+    */
+    fn fill_rect(&mut self, x: u32, y: u32, size: u32, color: [u8; 4]) {
+        for row in y..(y + size).min(self.height) {
+            for column in x..(x + size).min(self.width) {
+                let index = ((row * self.width + column) * 4) as usize;
+                self.pixels[index..index + 4].copy_from_slice(&color);
+            }
+        }
+    }
+
+    /**
+        Pads the image with transparent pixels until it is square, keeping it
+        centered, so that non-square icons are not stretched when resized.
+    */
+    fn into_square(self) -> Self {
+        if self.width == self.height {
+            return self;
+        }
+
+        let size = self.width.max(self.height);
+        let offset_x = (size - self.width) / 2;
+        let offset_y = (size - self.height) / 2;
+
+        let mut pixels = vec![0; (size * size * 4) as usize];
+        for (y, row) in self
+            .pixels
+            .chunks_exact(self.width as usize * 4)
+            .enumerate()
+        {
+            let start = (((y as u32 + offset_y) * size + offset_x) * 4) as usize;
+            pixels[start..start + row.len()].copy_from_slice(row);
+        }
+
+        Self {
+            width: size,
+            height: size,
+            pixels,
+        }
+    }
+
+    /**
+        Resizes the image to the given square size using a box filter, which
+        averages all source pixels covered by each of the resized pixels.
+
+        Colors are weighted by their alpha, so that fully transparent
+        pixels do not bleed their color into the edges of the icon.
+    */
+    fn resize(&self, size: u32) -> Self {
+        let scale_x = f64::from(self.width) / f64::from(size);
+        let scale_y = f64::from(self.height) / f64::from(size);
+
+        let mut pixels = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            let (y0, y1) = (f64::from(y) * scale_y, f64::from(y + 1) * scale_y);
+            for x in 0..size {
+                let (x0, x1) = (f64::from(x) * scale_x, f64::from(x + 1) * scale_x);
+
+                let mut sum = [0.0; 4];
+                let mut total_weight = 0.0;
+                for sy in (y0.floor() as u32)..(y1.ceil() as u32).min(self.height) {
+                    let weight_y = (y1.min(f64::from(sy + 1)) - y0.max(f64::from(sy))).max(0.0);
+                    for sx in (x0.floor() as u32)..(x1.ceil() as u32).min(self.width) {
+                        let weight_x = (x1.min(f64::from(sx + 1)) - x0.max(f64::from(sx))).max(0.0);
+                        let weight = weight_x * weight_y;
+
+                        let index = ((sy * self.width + sx) * 4) as usize;
+                        let pixel = &self.pixels[index..index + 4];
+                        let alpha = f64::from(pixel[3]) * weight;
+                        sum[0] += f64::from(pixel[0]) * alpha;
+                        sum[1] += f64::from(pixel[1]) * alpha;
+                        sum[2] += f64::from(pixel[2]) * alpha;
+                        sum[3] += alpha;
+                        total_weight += weight;
+                    }
+                }
+
+                if sum[3] > 0.0 {
+                    pixels.extend_from_slice(&[
+                        (sum[0] / sum[3]).round() as u8,
+                        (sum[1] / sum[3]).round() as u8,
+                        (sum[2] / sum[3]).round() as u8,
+                        (sum[3] / total_weight).round() as u8,
+                    ]);
+                } else {
+                    pixels.extend_from_slice(&[0, 0, 0, 0]);
+                }
+            }
+        }
+
+        Self {
+            width: size,
+            height: size,
+            pixels,
+        }
+    }
+
+    fn encode_png(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+        Ok(bytes)
+    }
+
+    fn encode_ico(&self) -> Result<Vec<u8>> {
+        let mut icon_dir = IconDir::new(ResourceType::Icon);
+        for size in ICO_SIZES {
+            let resized = self.resize(*size);
+            let image = IconImage::from_rgba_data(resized.width, resized.height, resized.pixels);
+            icon_dir.add_entry(IconDirEntry::encode(&image)?);
+        }
+
+        let mut bytes = Vec::new();
+        icon_dir.write(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+/**
+    Parses a hex color in the format `#rgb`, `#rrggbb` or `#rrggbbaa`.
+*/
+fn parse_hex_color(color: &str) -> Option<[u8; 4]> {
+    let hex = color.trim().strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize, len: usize| u8::from_str_radix(&hex[i * len..(i + 1) * len], 16).ok();
+    match hex.len() {
+        3 => Some([
+            channel(0, 1)? * 0x11,
+            channel(1, 1)? * 0x11,
+            channel(2, 1)? * 0x11,
+            0xFF,
+        ]),
+        6 => Some([channel(0, 2)?, channel(1, 2)?, channel(2, 2)?, 0xFF]),
+        8 => Some([
+            channel(0, 2)?,
+            channel(1, 2)?,
+            channel(2, 2)?,
+            channel(3, 2)?,
+        ]),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pads_and_resizes_images() {
+        // A 3x1 image, with an opaque red pixel and two transparent green pixels
+        let image = RgbaImage {
+            width: 3,
+            height: 1,
+            pixels: vec![0xFF, 0, 0, 0xFF, 0, 0xFF, 0, 0, 0, 0xFF, 0, 0],
+        }
+        .into_square();
+        assert_eq!((image.width, image.height), (3, 3));
+        assert_eq!(&image.pixels[0..4], &[0, 0, 0, 0]);
+        assert_eq!(&image.pixels[12..16], &[0xFF, 0, 0, 0xFF]);
+
+        // Transparent pixels should not change the color, only the alpha
+        let resized = image.resize(1);
+        assert_eq!(resized.pixels, vec![0xFF, 0, 0, 0x1C]);
+
+        let resized = image.resize(6);
+        assert_eq!(resized.pixels.len(), 6 * 6 * 4);
+        assert_eq!(&resized.pixels[12 * 4..12 * 4 + 4], &[0xFF, 0, 0, 0xFF]);
+    }
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(parse_hex_color("#3366ff"), Some([0x33, 0x66, 0xFF, 0xFF]));
+        assert_eq!(parse_hex_color("#36F"), Some([0x33, 0x66, 0xFF, 0xFF]));
+        assert_eq!(parse_hex_color("#3366ff80"), Some([0x33, 0x66, 0xFF, 0x80]));
+        assert_eq!(parse_hex_color("3366ff"), None);
+        assert_eq!(parse_hex_color("#33"), None);
+        assert_eq!(parse_hex_color("#ggg"), None);
+    }
+
+    #[test]
+    fn renders_text_icons() {
+        let background = [0x33, 0x66, 0xFF, 0xFF];
+        let image = RgbaImage::render_text("l", background).unwrap();
+        assert_eq!(
+            (image.width, image.height),
+            (PLACEHOLDER_SIZE, PLACEHOLDER_SIZE)
+        );
+
+        // Corners keep the background, and the glyph is drawn in white on dark colors
+        assert_eq!(&image.pixels[0..4], &background);
+        let white = image
+            .pixels
+            .chunks_exact(4)
+            .filter(|pixel| *pixel == [0xFF; 4])
+            .count();
+        assert!(white > 0);
+
+        // The vertical bar of the L is in the left half, so the right edge of the
+        // center row is the background, while the bottom bar spans to the right
+        let center = PLACEHOLDER_SIZE / 2;
+        let pixel = |x: u32, y: u32| {
+            let index = ((y * PLACEHOLDER_SIZE + x) * 4) as usize;
+            &image.pixels[index..index + 4]
+        };
+        assert_eq!(pixel(center + 40, center), &background);
+        assert_eq!(pixel(center - 40, center), &[0xFF; 4]);
+
+        let light = RgbaImage::render_text("OK", [0xFF, 0xEE, 0x88, 0xFF]).unwrap();
+        assert!(
+            light
+                .pixels
+                .chunks_exact(4)
+                .any(|pixel| pixel == [0, 0, 0, 0xFF])
+        );
+
+        assert!(RgbaImage::render_text("", background).is_err());
+        assert!(RgbaImage::render_text("LUNE", background).is_err());
+        assert!(RgbaImage::render_text("🌙", background).is_err());
+    }
+
+    #[test]
+    fn encodes_multi_resolution_icons() {
+        let png = RgbaImage::placeholder().encode_png().unwrap();
+        let image = RgbaImage::decode_png(&png).unwrap();
+        assert_eq!(
+            (image.width, image.height),
+            (PLACEHOLDER_SIZE, PLACEHOLDER_SIZE)
+        );
+
+        let ico = image.encode_ico().unwrap();
+        let icon_dir = IconDir::read(Cursor::new(ico)).unwrap();
+        let sizes = icon_dir
+            .entries()
+            .iter()
+            .map(IconDirEntry::width)
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, ICO_SIZES);
+    }
+}
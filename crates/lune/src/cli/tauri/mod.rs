@@ -0,0 +1,506 @@
+use std::{
+    env::consts::EXE_SUFFIX,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use anyhow::{Context, Result, bail};
+use async_fs as fs;
+use clap::Parser;
+use console::style;
+
+mod cargo;
+mod config;
+mod icons;
+
+use self::cargo::{cargo_build_release, check_cargo_installed};
+use self::config::{DEFAULT_CSP, TauriConfig, evaluate_tauri_config};
+use self::icons::{generate_icons, icon_paths};
+
+/// Build a Tauri application
+#[derive(Debug, Clone, Parser)]
+pub struct TauriCommand {
+    #[clap(subcommand)]
+    pub subcommand: TauriSubcommand,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub enum TauriSubcommand {
+    /// Build a Tauri app from a Luau script
+    Build(TauriBuildCommand),
+    /// Run a Tauri app from a Luau script, without compiling it
+    #[cfg(feature = "std-tauri")]
+    Dev(TauriDevCommand),
+}
+
+/// Build a Tauri application from a Luau script
+#[derive(Debug, Clone, Parser)]
+pub struct TauriBuildCommand {
+    /// The path to the input Luau script
+    pub input: PathBuf,
+
+    /// The path to the output executable
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Keep the generated Tauri project after building, for debugging
+    #[clap(long)]
+    pub keep_temp: bool,
+}
+
+/// Run a Tauri application from a Luau script, without compiling it
+#[cfg(feature = "std-tauri")]
+#[derive(Debug, Clone, Parser)]
+pub struct TauriDevCommand {
+    /// The path to the input Luau script
+    pub input: PathBuf,
+
+    /// Arguments to pass to the script, stored in process.args
+    pub script_args: Vec<String>,
+}
+
+impl TauriCommand {
+    pub async fn run(self) -> Result<ExitCode> {
+        match self.subcommand {
+            TauriSubcommand::Build(cmd) => cmd.run().await,
+            #[cfg(feature = "std-tauri")]
+            TauriSubcommand::Dev(cmd) => cmd.run().await,
+        }
+    }
+}
+
+#[cfg(feature = "std-tauri")]
+impl TauriDevCommand {
+    pub async fn run(self) -> Result<ExitCode> {
+        println!(
+            "{} Tauri dev from {}",
+            style("Starting").cyan().bold(),
+            style(self.input.display()).green()
+        );
+
+        // The script is evaluated first, same as when building, to find its frontend
+        let script_content = fs::read_to_string(&self.input)
+            .await
+            .context("Failed to read input script")?;
+        let config = evaluate_tauri_config(&self.input, &script_content)?;
+
+        println!(
+            "  {} App: {} ({})",
+            style("→").dim(),
+            style(&config.name).yellow(),
+            &config.identifier
+        );
+
+        // The frontend is served straight from disk, relative to the script, so
+        // that changes to it only need a reload instead of restarting the app
+        if let Some(html_path) = &config.html {
+            let input_dir = self.input.parent().unwrap_or(Path::new("."));
+            let frontend = input_dir.join(html_path);
+            if !frontend.exists() {
+                bail!("Frontend '{}' does not exist", frontend.display());
+            }
+            println!(
+                "  {} Serving {}",
+                style("→").dim(),
+                style(frontend.display()).dim()
+            );
+            lune_std_tauri::set_frontend_path(frontend);
+        }
+
+        // Run the script using the same runtime as `lune run`, where
+        // calling app:run() opens the window and blocks until it closes
+        let mut rt = lune::Runtime::new()?.with_args(self.script_args);
+        let result = rt.run_file(&self.input).await;
+
+        Ok(match result {
+            Err(err) => {
+                eprintln!("{err}");
+                ExitCode::FAILURE
+            }
+            Ok(values) => ExitCode::from(values.status()),
+        })
+    }
+}
+
+impl TauriBuildCommand {
+    pub async fn run(self) -> Result<ExitCode> {
+        println!(
+            "{} Tauri build from {}",
+            style("Starting").cyan().bold(),
+            style(self.input.display()).green()
+        );
+
+        // Cargo is only needed at the very end, but checking for it first
+        // means that nothing is generated when it is not installed anyway
+        check_cargo_installed()?;
+
+        // 1. Read and evaluate the input script to extract tauri.new() config
+        let script_content = fs::read_to_string(&self.input)
+            .await
+            .context("Failed to read input script")?;
+
+        // Run the script with a stubbed tauri library that records its config
+        let config = evaluate_tauri_config(&self.input, &script_content)?;
+
+        println!(
+            "  {} App: {} ({})",
+            style("→").dim(),
+            style(&config.name).yellow(),
+            &config.identifier
+        );
+
+        // 2. Create temp directory for the Tauri project
+        let temp_dir = std::env::temp_dir().join(format!("lune-tauri-{}", uuid_simple()));
+        fs::create_dir_all(&temp_dir).await?;
+
+        println!(
+            "  {} Building in {}",
+            style("→").dim(),
+            style(temp_dir.display()).dim()
+        );
+
+        // The generated project is kept when the build fails, so that it can be inspected
+        let output_path = match self
+            .build_project(&temp_dir, &config, &script_content)
+            .await
+        {
+            Ok(output_path) => output_path,
+            Err(e) => {
+                eprintln!(
+                    "  {} The generated project was kept at {}",
+                    style("→").dim(),
+                    style(temp_dir.display()).dim()
+                );
+                return Err(e);
+            }
+        };
+
+        if self.keep_temp {
+            println!(
+                "  {} The generated project was kept at {}",
+                style("→").dim(),
+                style(temp_dir.display()).dim()
+            );
+        } else if let Err(e) = fs::remove_dir_all(&temp_dir).await {
+            eprintln!(
+                "{}: Failed to remove {}: {e}",
+                style("Warning").yellow().bold(),
+                temp_dir.display()
+            );
+        }
+
+        println!(
+            "{} Built successfully: {}",
+            style("✓").green().bold(),
+            style(output_path.display()).blue()
+        );
+
+        Ok(ExitCode::SUCCESS)
+    }
+
+    /**
+        Generates and builds the Tauri project in the given directory,
+        returning the path that the built executable was copied to.
+    */
+    async fn build_project(
+        &self,
+        temp_dir: &Path,
+        config: &TauriConfig,
+        script_content: &str,
+    ) -> Result<PathBuf> {
+        // 3. Generate Tauri project files, with paths in the config relative to the script
+        let input_dir = self.input.parent().unwrap_or(Path::new("."));
+        generate_cargo_toml(temp_dir, config).await?;
+        generate_main_rs(temp_dir, script_content).await?;
+        generate_tauri_conf(temp_dir, config).await?;
+        generate_capabilities(temp_dir).await?;
+        generate_icons(temp_dir, config.icon.as_ref(), input_dir).await?;
+
+        // 4. Copy HTML/assets if specified
+        if let Some(ref html_path) = config.html {
+            let html_src = input_dir.join(html_path);
+            let dist_dir = temp_dir.join("dist");
+            fs::create_dir_all(&dist_dir).await?;
+
+            if html_src.is_dir() {
+                copy_dir_recursive_sync(&html_src, &dist_dir)?;
+            } else {
+                let dest = dist_dir.join("index.html");
+                fs::copy(&html_src, &dest).await?;
+            }
+        } else {
+            // Create default index.html
+            let dist_dir = temp_dir.join("dist");
+            fs::create_dir_all(&dist_dir).await?;
+            fs::write(dist_dir.join("index.html"), DEFAULT_HTML).await?;
+        }
+
+        // 5. Run cargo build
+        println!(
+            "  {} Running cargo build (this may take a few minutes)...",
+            style("→").dim()
+        );
+        cargo_build_release(temp_dir)?;
+
+        // 6. Copy output binary, which is always built for the current system
+        let exe_name = format!("{}{EXE_SUFFIX}", config.package_name());
+        let built_exe = temp_dir.join("target").join("release").join(&exe_name);
+
+        let output_path = self
+            .output
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(&exe_name));
+
+        fs::copy(&built_exe, &output_path)
+            .await
+            .context("Failed to copy output binary")?;
+
+        Ok(output_path)
+    }
+}
+
+fn uuid_simple() -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let dur = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    format!("{:x}{:x}", dur.as_secs(), dur.subsec_nanos())
+}
+
+/**
+    Returns the directory containing the sources of the Lune crates, which
+    generated apps depend on to embed the same runtime as this Lune binary.
+*/
+fn lune_crates_dir() -> Result<PathBuf> {
+    let crates_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    if !crates_dir
+        .join("lune-std-tauri")
+        .join("Cargo.toml")
+        .is_file()
+    {
+        bail!(
+            "Could not find the Lune sources at {}\n\
+            Building Tauri apps requires Lune to be installed from source",
+            crates_dir.display()
+        );
+    }
+    Ok(crates_dir)
+}
+
+async fn generate_cargo_toml(dir: &Path, config: &TauriConfig) -> Result<()> {
+    let crates_dir = lune_crates_dir()?;
+    let content = format!(
+        r#"[package]
+name = "{}"
+version = "{}"
+edition = "2021"
+
+[dependencies]
+tauri = {{ version = "2", features = [] }}
+async-io = "2.4"
+lune = {{ path = {}, default-features = false, features = ["std"] }}
+lune-std-tauri = {{ path = {} }}
+
+[build-dependencies]
+tauri-build = {{ version = "2", features = [] }}
+"#,
+        config.package_name(),
+        config.version,
+        toml_path(&crates_dir.join("lune"))?,
+        toml_path(&crates_dir.join("lune-std-tauri"))?,
+    );
+    fs::write(dir.join("Cargo.toml"), content).await?;
+
+    // build.rs
+    fs::write(dir.join("build.rs"), "fn main() { tauri_build::build() }").await?;
+
+    Ok(())
+}
+
+/**
+    Formats a path as a quoted string that can be used in a Cargo.toml
+*/
+fn toml_path(path: &Path) -> Result<String> {
+    let path = path
+        .to_str()
+        .context("Lune sources must be at a valid UTF-8 path")?;
+    Ok(serde_json::to_string(path)?)
+}
+
+async fn generate_main_rs(dir: &Path, script: &str) -> Result<()> {
+    let src_dir = dir.join("src");
+    fs::create_dir_all(&src_dir).await?;
+
+    // The script is embedded and run using Lune, and once it calls app:run(),
+    // lune-std-tauri starts the app using the context generated for this project
+    // and connects its listeners to the real app handle in the setup hook
+    let content = r#"#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+
+use std::process::ExitCode;
+
+use lune::Runtime;
+
+const SCRIPT: &[u8] = include_bytes!("../script.luau");
+
+fn main() -> ExitCode {
+    lune_std_tauri::set_context(tauri::generate_context!());
+
+    let result = async_io::block_on(async {
+        let mut rt = Runtime::new()?.with_args(std::env::args_os().skip(1));
+        rt.run_custom("script.luau", SCRIPT).await
+    });
+
+    match result {
+        Ok(values) => ExitCode::from(values.status()),
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+"#;
+    fs::write(src_dir.join("main.rs"), content).await?;
+    fs::write(dir.join("script.luau"), script).await?;
+
+    Ok(())
+}
+
+async fn generate_tauri_conf(dir: &Path, config: &TauriConfig) -> Result<()> {
+    fs::write(dir.join("tauri.conf.json"), tauri_conf(config)?).await?;
+    Ok(())
+}
+
+/**
+    Creates the contents of `tauri.conf.json` for the given config, where all
+    values are escaped, since names and titles may be computed by the script.
+*/
+fn tauri_conf(config: &TauriConfig) -> Result<String> {
+    let mut window = serde_json::json!({
+        "title": config.window_title,
+        "width": config.window_width,
+        "height": config.window_height,
+        "resizable": config.window_resizable,
+        "decorations": config.window_decorations,
+        "transparent": config.window_transparent,
+        "alwaysOnTop": config.window_always_on_top,
+        "fullscreen": config.window_fullscreen,
+    });
+    // Centering takes precedence over any explicit position, same as at runtime
+    if config.window_center {
+        window["center"] = true.into();
+    } else if let (Some(x), Some(y)) = (config.window_x, config.window_y) {
+        window["x"] = x.into();
+        window["y"] = y.into();
+    }
+    if let Some(min_width) = config.window_min_width {
+        window["minWidth"] = min_width.into();
+    }
+    if let Some(min_height) = config.window_min_height {
+        window["minHeight"] = min_height.into();
+    }
+
+    let conf = serde_json::json!({
+        "productName": config.name,
+        "version": config.version,
+        "identifier": config.identifier,
+        "build": {
+            "frontendDist": "./dist"
+        },
+        "bundle": {
+            "active": false,
+            "icon": icon_paths()
+        },
+        "app": {
+            "withGlobalTauri": true,
+            "windows": [window],
+            "security": {
+                "csp": config.csp.as_deref().unwrap_or(DEFAULT_CSP)
+            }
+        }
+    });
+    Ok(serde_json::to_string_pretty(&conf)?)
+}
+
+async fn generate_capabilities(dir: &Path) -> Result<()> {
+    let cap_dir = dir.join("capabilities");
+    fs::create_dir_all(&cap_dir).await?;
+
+    let content = r#"{
+    "identifier": "default",
+    "description": "Default capability",
+    "windows": ["*"],
+    "permissions": [
+        "core:event:default",
+        "core:window:default"
+    ]
+}"#;
+    fs::write(cap_dir.join("default.json"), content).await?;
+    Ok(())
+}
+
+fn copy_dir_recursive_sync(src: &Path, dst: &Path) -> Result<()> {
+    std::fs::create_dir_all(dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let src_path = entry.path();
+        let dst_path = dst.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            copy_dir_recursive_sync(&src_path, &dst_path)?;
+        } else {
+            std::fs::copy(&src_path, &dst_path)?;
+        }
+    }
+    Ok(())
+}
+
+const DEFAULT_HTML: &str = r#"<!doctype html>
+<html>
+<head>
+    <style>
+        body { font-family: sans-serif; display: flex; justify-content: center; align-items: center; height: 100vh; margin: 0; background: #f0f0f0; }
+        h1 { color: #333; }
+    </style>
+</head>
+<body>
+    <h1>Hello from Lune!</h1>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_generated_tauri_conf() {
+        let config = TauriConfig {
+            name: "My \"Quoted\" App".to_string(),
+            identifier: "com.example.app".to_string(),
+            version: "0.1.0".to_string(),
+            icon: None,
+            html: None,
+            window_title: "C:\\Apps \"Main\"".to_string(),
+            window_width: 800,
+            window_height: 600,
+            window_x: Some(10),
+            window_y: Some(20),
+            window_center: false,
+            window_min_width: Some(320),
+            window_min_height: None,
+            window_resizable: true,
+            window_decorations: true,
+            window_transparent: false,
+            window_always_on_top: false,
+            window_fullscreen: false,
+            csp: None,
+        };
+        let conf: serde_json::Value = serde_json::from_str(&tauri_conf(&config).unwrap()).unwrap();
+        assert_eq!(conf["productName"], "My \"Quoted\" App");
+        let window = &conf["app"]["windows"][0];
+        assert_eq!(window["title"], "C:\\Apps \"Main\"");
+        assert_eq!(window["x"], 10);
+        assert_eq!(window["minWidth"], 320);
+        assert!(window.get("minHeight").is_none());
+        assert!(window.get("center").is_none());
+        assert_eq!(conf["app"]["security"]["csp"], DEFAULT_CSP);
+    }
+}
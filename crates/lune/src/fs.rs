@@ -1,43 +1,538 @@
 use std::{
+    collections::{BTreeSet, HashMap},
     fmt,
-    io::{Cursor, Read, Result as IoResult},
-    path::{Path, PathBuf},
-    sync::{Arc, Mutex},
+    io::{Cursor, Read, Result as IoResult, Seek},
+    path::{Component, Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime},
 };
 
-use lune_utils::fs::FileSystem;
-use zip::ZipArchive;
+use lune_utils::{
+    fs::{FileMetadata, FileSystem},
+    path::constants::FILE_NAME_CONFIG,
+};
+use zip::{
+    DateTime, SUPPORTED_COMPRESSION_METHODS, ZipArchive, ZipWriter, write::SimpleFileOptions,
+};
+
+/**
+    A file stored in a ZIP archive, as listed in its central directory.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipFileEntry {
+    pub name: String,
+    pub crc32: u32,
+    pub size: u64,
+}
+
+/**
+    Limits for how much data may be decompressed from a ZIP archive, so that a
+    crafted archive with highly compressed entries can not exhaust memory.
+
+    Sizes stored in the archive can not be trusted, so limits are checked
+    while decompressing, and reading stops as soon as one is exceeded.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZipLimits {
+    /// The maximum decompressed size of a single file, in bytes
+    pub max_file_size: u64,
+    /// The maximum decompressed size of all files read, in bytes
+    pub max_total_size: u64,
+}
+
+impl Default for ZipLimits {
+    fn default() -> Self {
+        Self {
+            max_file_size: 1024 * 1024 * 1024,
+            max_total_size: 4 * 1024 * 1024 * 1024,
+        }
+    }
+}
 
 /**
     A filesystem implementation that reads from a ZIP archive in memory.
+
+    The archive is immutable, so all write operations return an unsupported error,
+    and the contents of files are cached after they are first read, so that files
+    read many times, such as commonly required modules, are only decompressed once.
+
+    Path aliases, such as the ones in a `.luaurc` file, can be given using
+    [`ZipFileSystem::with_aliases`], so that aliased requires resolve to
+    embedded files even when the config file itself was not embedded.
+
+    Decompressed sizes are limited using [`ZipLimits`], with generous defaults
+    that can be changed using [`ZipFileSystem::with_limits`].
+
+    Every read uses its own cursor over the shared archive data, so that many
+    tasks can read different files at the same time without waiting on each other.
 */
 #[derive(Clone)]
 pub struct ZipFileSystem {
-    archive: Arc<Mutex<ZipArchive<Cursor<Vec<u8>>>>>,
+    archive: ZipArchive<Cursor<SharedBytes>>,
+    index: Arc<ZipIndex>,
+    cache: Arc<Mutex<HashMap<String, Arc<Vec<u8>>>>>,
+    aliases: Arc<HashMap<String, String>>,
+    config: Option<Arc<Vec<u8>>>,
+    root: Option<PathBuf>,
+    limits: ZipLimits,
+    decompressed: Arc<AtomicU64>,
 }
 
 impl ZipFileSystem {
+    /**
+        Opens the ZIP archive in the given data.
+
+        # Errors
+
+        Errors if the data is not a valid ZIP archive, or if any file in it uses
+        a compression method that this build can not decompress - which is checked
+        up front, so that it fails here instead of when the file is first read.
+    */
     pub fn new(data: Vec<u8>) -> IoResult<Self> {
-        let reader = Cursor::new(data);
-        let archive = ZipArchive::new(reader)?;
+        let reader = Cursor::new(SharedBytes(Arc::new(data)));
+        let mut archive = ZipArchive::new(reader)?;
+        check_compression_methods(&mut archive)?;
+        let index = ZipIndex::new(archive.file_names());
         Ok(Self {
-            archive: Arc::new(Mutex::new(archive)),
+            archive,
+            index: Arc::new(index),
+            cache: Arc::default(),
+            aliases: Arc::default(),
+            config: None,
+            root: None,
+            limits: ZipLimits::default(),
+            decompressed: Arc::default(),
         })
     }
 
-    fn normalize_path(path: &Path) -> String {
-        let path = if path.is_absolute() {
-            if let Ok(cwd) = std::env::current_dir() {
-                path.strip_prefix(&cwd).unwrap_or(path)
+    /**
+        Sets the limits for how much data may be decompressed from the archive.
+
+        Files read before calling this still count towards the total size.
+    */
+    #[must_use]
+    pub fn with_limits(mut self, limits: ZipLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /**
+        Sets the directory on disk that the archive root stands in for, so that
+        absolute paths inside of it are read relative to the archive root.
+
+        The require resolver makes module paths absolute using the current working
+        directory, same as when running files from disk, so giving that directory
+        here makes relative requires between embedded files resolve the same way
+        that they would for `lune run`. Absolute paths outside of the root are
+        still read relative to the archive root, same as without one.
+    */
+    #[must_use]
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+
+    /**
+        Adds path aliases, mapping alias names without the leading `@`,
+        such as `lib`, to the archive directories that they point to.
+
+        Paths starting with an alias, such as `@lib/util.luau`, are then read
+        from the directory of the alias, and aliases are matched ignoring case,
+        same as they are in `.luaurc` files. If the archive does not contain a
+        `.luaurc` file at its root, one listing the aliases is provided instead,
+        so that `require` can find them at runtime.
+    */
+    #[must_use]
+    pub fn with_aliases(mut self, aliases: impl IntoIterator<Item = (String, String)>) -> Self {
+        let aliases = aliases
+            .into_iter()
+            .filter_map(|(alias, target)| {
+                Some((alias.to_ascii_lowercase(), canonical_name(&target)?))
+            })
+            .collect::<HashMap<_, _>>();
+
+        self.config = if aliases.is_empty() || self.index.files.contains_key(FILE_NAME_CONFIG) {
+            None
+        } else {
+            let config_aliases = aliases
+                .iter()
+                .map(|(alias, target)| (alias.clone(), format!("./{target}").into()))
+                .collect::<serde_json::Map<_, _>>();
+            let config = serde_json::json!({ "aliases": config_aliases });
+            Some(Arc::new(config.to_string().into_bytes()))
+        };
+        self.aliases = Arc::new(aliases);
+        self
+    }
+
+    /**
+        Returns the names of all files in the archive, sorted, using the index
+        built when the archive was opened, without reading the archive at all.
+    */
+    pub fn list_all(&self) -> Vec<String> {
+        let mut names = self.index.files.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /**
+        Returns all files in the archive, sorted by name, without reading their contents.
+    */
+    pub fn file_entries(&self) -> IoResult<Vec<ZipFileEntry>> {
+        let mut archive = self.reader();
+        let mut entries = Vec::new();
+        for i in 0..archive.len() {
+            let file = archive.by_index_raw(i)?;
+            if file.is_dir() {
+                continue;
+            }
+            let Some(name) = canonical_name(file.name()) else {
+                continue;
+            };
+            entries.push(ZipFileEntry {
+                name,
+                crc32: file.crc32(),
+                size: file.size(),
+            });
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
+    /**
+        Returns a reader for the archive with its own cursor, which is cheap to
+        create, since the data and the parsed central directory are shared.
+    */
+    fn reader(&self) -> ZipArchive<Cursor<SharedBytes>> {
+        self.archive.clone()
+    }
+
+    /**
+        Reads the file at the given path, decompressing it only
+        if it has not already been read and cached before.
+    */
+    fn read_cached(&self, path: &Path) -> IoResult<Arc<Vec<u8>>> {
+        let name = self.normalize_path_or_err(path)?;
+        if let Some(config) = self.generated_config(&name) {
+            return Ok(Arc::clone(config));
+        }
+        if let Some(bytes) = self.cache.lock().unwrap().get(&name) {
+            return Ok(Arc::clone(bytes));
+        }
+
+        // One byte more than allowed is read, to know if the limit was exceeded
+        let remaining = self
+            .limits
+            .max_total_size
+            .saturating_sub(self.decompressed.load(Ordering::SeqCst));
+        let limit = self.limits.max_file_size.min(remaining);
+        let mut buffer = Vec::new();
+        {
+            let mut archive = self.reader();
+            let file = archive.by_name(self.index.stored_name(&name, path)?)?;
+            file.take(limit.saturating_add(1))
+                .read_to_end(&mut buffer)?;
+        }
+        if buffer.len() as u64 > limit {
+            let message = if limit == self.limits.max_file_size {
+                format!(
+                    "file '{}' is larger than the maximum of {} bytes",
+                    path.display(),
+                    self.limits.max_file_size
+                )
             } else {
-                path
+                format!(
+                    "reading file '{}' exceeds the maximum total of {} bytes read",
+                    path.display(),
+                    self.limits.max_total_size
+                )
+            };
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::FileTooLarge,
+                message,
+            ));
+        }
+
+        // Files may be read concurrently, but are only counted once when cached
+        let mut cache = self.cache.lock().unwrap();
+        let bytes = cache.entry(name).or_insert_with(|| {
+            self.decompressed
+                .fetch_add(buffer.len() as u64, Ordering::SeqCst);
+            Arc::new(buffer)
+        });
+        Ok(Arc::clone(bytes))
+    }
+
+    /**
+        Returns the contents of the generated `.luaurc` file, if the
+        given canonical name is the root config file and one was generated.
+    */
+    fn generated_config(&self, name: &str) -> Option<&Arc<Vec<u8>>> {
+        self.config.as_ref().filter(|_| name == FILE_NAME_CONFIG)
+    }
+
+    /**
+        Normalizes the given path to the name of an entry in the archive.
+
+        The archive is its own root directory, so absolute paths are relative to
+        it, after stripping the root given to [`ZipFileSystem::with_root`] if any,
+        and `.` and `..` are resolved logically. Paths escaping above the root
+        return `None`.
+
+        Paths starting with a known alias, such as `@lib/util.luau`,
+        are normalized relative to the directory of that alias.
+    */
+    fn normalize_path(&self, path: &Path) -> Option<String> {
+        let path = self
+            .root
+            .as_deref()
+            .and_then(|root| path.strip_prefix(root).ok())
+            .unwrap_or(path);
+        let relative = path
+            .components()
+            .filter(|component| !matches!(component, Component::Prefix(_) | Component::RootDir))
+            .collect::<PathBuf>();
+        let relative = relative.to_string_lossy();
+
+        let (first, rest) = relative.split_once(['/', '\\']).unwrap_or((&relative, ""));
+        let target = first
+            .strip_prefix('@')
+            .and_then(|alias| self.aliases.get(&alias.to_ascii_lowercase()));
+        match target {
+            Some(target) => canonical_name(&format!("{target}/{rest}")),
+            None => canonical_name(&relative),
+        }
+    }
+
+    fn normalize_path_or_err(&self, path: &Path) -> IoResult<String> {
+        self.normalize_path(path).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("path escapes the archive root: {}", path.display()),
+            )
+        })
+    }
+}
+
+/**
+    Canonicalizes the name of an entry in the archive, or a path to look it up by,
+    so that both always use forward slashes, even when the archive was created on
+    Windows, and so that `.` and `..` are resolved. Names escaping the root return `None`.
+
+    This must be used for all names, both when indexing and looking up entries.
+*/
+pub(crate) fn canonical_name(name: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    for part in name.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
             }
-        } else {
-            path
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/**
+    The names of entries added to an archive, keyed by their canonical names, used to
+    make sure that no two entries would end up being read under the same name.
+*/
+#[derive(Debug, Default)]
+pub(crate) struct ZipNames(HashMap<String, String>);
+
+impl ZipNames {
+    /**
+        Adds the given name, returning its canonical name.
+
+        # Errors
+
+        Errors with [`std::io::ErrorKind::AlreadyExists`] if a name that was already
+        added has the same canonical name, and with [`std::io::ErrorKind::InvalidInput`]
+        if the name is empty or escapes the archive root once normalized.
+    */
+    pub(crate) fn insert(&mut self, name: &str) -> IoResult<String> {
+        let Some(canonical) = canonical_name(name).filter(|name| !name.is_empty()) else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("'{name}' is not a valid archive name"),
+            ));
         };
-        let s = path.to_string_lossy().replace('\\', "/");
-        s.trim_start_matches('/').to_string()
+        if let Some(existing) = self.0.get(&canonical) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("'{existing}' and '{name}' would both be stored as '{canonical}'"),
+            ));
+        }
+        self.0.insert(canonical.clone(), name.to_string());
+        Ok(canonical)
+    }
+}
+
+/**
+    Builds a ZIP archive in memory, the writing counterpart to [`ZipFileSystem`].
+
+    Names are normalized the same way as when reading an archive, and stored in
+    their canonical form, meaning that `lib\util.luau` is stored as `lib/util.luau`,
+    and names that would be read as the same file are rejected instead of shadowing
+    each other.
+
+    Every entry is stored with the same modification time, so that building an
+    archive from the same files always gives the same bytes. This is the time in
+    the `SOURCE_DATE_EPOCH` environment variable if it is set, following
+    <https://reproducible-builds.org/specs/source-date-epoch/>, and the earliest
+    time that can be stored in an archive, 1980-01-01, otherwise.
+*/
+pub struct ZipArchiveBuilder {
+    writer: ZipWriter<Cursor<Vec<u8>>>,
+    names: ZipNames,
+    modified: DateTime,
+}
+
+impl Default for ZipArchiveBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ZipArchiveBuilder {
+    pub fn new() -> Self {
+        Self {
+            writer: ZipWriter::new(Cursor::new(Vec::new())),
+            names: ZipNames::default(),
+            modified: source_date(),
+        }
+    }
+
+    /**
+        Adds a file with the given name and contents, returning the name it was stored under.
+
+        Any modification time in the given options is replaced by the one used for all entries.
+
+        # Errors
+
+        Errors if the name is not valid, if it conflicts with an entry that was
+        already added, or if the contents could not be written to the archive.
+    */
+    pub fn add_file(
+        &mut self,
+        name: &str,
+        contents: &[u8],
+        options: SimpleFileOptions,
+    ) -> IoResult<String> {
+        let name = self.names.insert(name)?;
+        let options = options.last_modified_time(self.modified);
+        self.writer.start_file(name.as_str(), options)?;
+        std::io::Write::write_all(&mut self.writer, contents)?;
+        Ok(name)
+    }
+
+    /**
+        Finishes writing the archive, returning its bytes.
+    */
+    pub fn finish(self) -> IoResult<Vec<u8>> {
+        Ok(self.writer.finish()?.into_inner())
+    }
+}
+
+/**
+    Makes sure that every file in the archive uses a compression method that can be
+    decompressed, since the methods available depend on the features Lune was built with.
+*/
+fn check_compression_methods(archive: &mut ZipArchive<impl Read + Seek>) -> IoResult<()> {
+    for index in 0..archive.len() {
+        let file = archive.by_index_raw(index)?;
+        let method = file.compression();
+        if !SUPPORTED_COMPRESSION_METHODS.contains(&method) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!(
+                    "embedded file '{}' uses the compression method '{method}', \
+                    which this build of Lune can not decompress",
+                    file.name()
+                ),
+            ));
+        }
     }
+    Ok(())
+}
+
+/**
+    Converts a modification time stored in a ZIP archive to a system time.
+
+    ZIP archives store times without a timezone, so they are treated as UTC.
+*/
+fn zip_time_to_system_time(time: DateTime) -> Option<SystemTime> {
+    // Days since the unix epoch for the given civil date, see
+    // https://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let (year, month, day) = (
+        i64::from(time.year()),
+        i64::from(time.month()),
+        i64::from(time.day()),
+    );
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let seconds = days * 86_400
+        + i64::from(time.hour()) * 3_600
+        + i64::from(time.minute()) * 60
+        + i64::from(time.second());
+    let seconds = u64::try_from(seconds).ok()?;
+    SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(seconds))
+}
+
+/**
+    Converts seconds since the unix epoch to a time that can be stored in an
+    archive, returning `None` for times before 1980 or after 2107.
+*/
+fn unix_time_to_zip_time(seconds: u64) -> Option<DateTime> {
+    // Civil date for the given days since the unix epoch, see
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = i64::try_from(seconds / 86_400).ok()? + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    let time_of_day = seconds % 86_400;
+    DateTime::from_date_and_time(
+        u16::try_from(year).ok()?,
+        u8::try_from(month).ok()?,
+        u8::try_from(day).ok()?,
+        u8::try_from(time_of_day / 3_600).ok()?,
+        u8::try_from(time_of_day % 3_600 / 60).ok()?,
+        u8::try_from(time_of_day % 60).ok()?,
+    )
+    .ok()
+}
+
+/**
+    Returns the modification time for entries written by [`ZipArchiveBuilder`], from
+    `SOURCE_DATE_EPOCH` if it is set to a time that fits, or 1980-01-01 otherwise.
+*/
+fn source_date() -> DateTime {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.trim().parse().ok())
+        .and_then(unix_time_to_zip_time)
+        .unwrap_or_default()
 }
 
 impl fmt::Debug for ZipFileSystem {
@@ -46,47 +541,124 @@ impl fmt::Debug for ZipFileSystem {
     }
 }
 
-impl FileSystem for ZipFileSystem {
-    fn is_file(&self, path: &Path) -> bool {
-        let name = Self::normalize_path(path);
-        let mut archive = self.archive.lock().unwrap();
-        archive.by_name(&name).is_ok()
+/**
+    Contents of the archive or of a cached file, which can be read through a [`Cursor`].
+*/
+#[derive(Clone)]
+struct SharedBytes(Arc<Vec<u8>>);
+
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
     }
+}
 
-    fn is_dir(&self, path: &Path) -> bool {
-        let name = Self::normalize_path(path);
-        if name.is_empty() {
-            return true;
-        }
-        let mut archive = self.archive.lock().unwrap();
-        // Zip entries usually explicitly have directories,
-        // but sometimes they are implicit.
-        // Try finding exact directory entry (often ends with /)
-        if archive.by_name(&name).is_ok() {
-            return true; // Use more robust check if needed
-        }
-        if archive.by_name(&format!("{}/", name)).is_ok() {
-            return true;
-        }
-        // Fallback: check if any file starts with this prefix
-        let prefix = format!("{}/", name);
-        for i in 0..archive.len() {
-            if let Ok(file) = archive.by_index(i) {
-                if file.name().starts_with(&prefix) {
-                    return true;
+/**
+    An index of all files and directories in a ZIP archive, built once when
+    the archive is opened, so that lookups never need to scan or lock it.
+
+    Directories may have explicit entries ending with a slash, or only exist
+    implicitly as the prefix of the files in them - both are indexed here.
+    Files are indexed by their canonical names, mapped to their stored names.
+*/
+#[derive(Debug)]
+struct ZipIndex {
+    files: HashMap<String, String>,
+    dirs: HashMap<String, Vec<String>>,
+}
+
+impl ZipIndex {
+    fn new<'a>(file_names: impl Iterator<Item = &'a str>) -> Self {
+        let mut files = HashMap::new();
+        let mut dirs = HashMap::<String, BTreeSet<String>>::new();
+        dirs.insert(String::new(), BTreeSet::new());
+
+        for file_name in file_names {
+            let is_dir = file_name.ends_with(['/', '\\']);
+            let Some(name) = canonical_name(file_name).filter(|name| !name.is_empty()) else {
+                continue;
+            };
+            let parts = name.split('/').collect::<Vec<_>>();
+
+            // Every parent of an entry is a directory, and the last part is
+            // either a file or an explicit directory entry, depending on slashes
+            let mut parent = String::new();
+            for (i, part) in parts.iter().enumerate() {
+                dirs.entry(parent.clone())
+                    .or_default()
+                    .insert((*part).to_string());
+                if !parent.is_empty() {
+                    parent.push('/');
                 }
+                parent.push_str(part);
+                if i + 1 < parts.len() || is_dir {
+                    dirs.entry(parent.clone()).or_default();
+                }
+            }
+            if !is_dir {
+                files.insert(name, file_name.to_string());
             }
         }
-        false
+
+        // Archive order is arbitrary, children are sorted to match other filesystems
+        let dirs = dirs
+            .into_iter()
+            .map(|(dir, children)| (dir, children.into_iter().collect()))
+            .collect();
+
+        Self { files, dirs }
+    }
+
+    /**
+        Checks if a file or directory with the given canonical name exists.
+    */
+    fn contains(&self, name: &str) -> bool {
+        self.files.contains_key(name) || self.dirs.contains_key(name)
+    }
+
+    /**
+        Returns the name that the file with the given canonical name is stored under.
+    */
+    fn stored_name(&self, name: &str, path: &Path) -> IoResult<&str> {
+        match self.files.get(name) {
+            Some(stored) => Ok(stored),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("file not found: {}", path.display()),
+            )),
+        }
+    }
+}
+
+impl FileSystem for ZipFileSystem {
+    fn is_file(&self, path: &Path) -> bool {
+        self.normalize_path(path).is_some_and(|name| {
+            self.index.files.contains_key(&name) || self.generated_config(&name).is_some()
+        })
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.normalize_path(path)
+            .is_some_and(|name| self.index.dirs.contains_key(&name))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.normalize_path(path).is_some_and(|name| {
+            self.index.contains(&name) || self.generated_config(&name).is_some()
+        })
     }
 
     fn read(&self, path: &Path) -> IoResult<Vec<u8>> {
-        let name = Self::normalize_path(path);
-        let mut archive = self.archive.lock().unwrap();
-        let mut file = archive.by_name(&name)?;
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)?;
-        Ok(buffer)
+        let bytes = self.read_cached(path)?;
+        Ok(bytes.as_ref().clone())
+    }
+
+    fn open(&self, path: &Path) -> IoResult<Box<dyn Read + Send>> {
+        // Files in the archive borrow the reader they were opened from, so they
+        // can not be returned, but cached contents can be streamed without copying
+        // them, and reading them here also checks them against the size limits
+        let bytes = self.read_cached(path)?;
+        Ok(Box::new(Cursor::new(SharedBytes(bytes))))
     }
 
     fn read_to_string(&self, path: &Path) -> IoResult<String> {
@@ -96,59 +668,687 @@ impl FileSystem for ZipFileSystem {
     }
 
     fn read_dir(&self, path: &Path) -> IoResult<Vec<PathBuf>> {
-        let name = Self::normalize_path(path);
-        let prefix = if name.is_empty() {
-            String::new()
-        } else {
-            format!("{}/", name)
+        let name = self.normalize_path_or_err(path)?;
+        let Some(children) = self.index.dirs.get(&name) else {
+            return Ok(Vec::new());
         };
 
-        let mut archive = self.archive.lock().unwrap();
-        let mut entries = Vec::new();
-
-        // Iterate all files to find direct children
-        // This is O(N) for every read_dir, but fine for small archives.
-        // Optimizing this would require building a tree index.
-        let file_names: Vec<String> = (0..archive.len())
-            .filter_map(|i| archive.by_index(i).ok().map(|f| f.name().to_string()))
+        // Children are always joined using forward slashes, same as names
+        // in the archive, instead of the separator of the current platform
+        let base = path.to_string_lossy().replace('\\', "/");
+        let base = base.trim_end_matches('/');
+        let entries = children
+            .iter()
+            .map(|child| {
+                if name.is_empty() {
+                    PathBuf::from(child)
+                } else {
+                    PathBuf::from(format!("{base}/{child}"))
+                }
+            })
             .collect();
+        Ok(entries)
+    }
 
-        for file_name in file_names {
-            if !file_name.starts_with(&prefix) {
-                continue;
-            }
-            if file_name == prefix {
-                continue; // self
+    fn metadata(&self, path: &Path) -> IoResult<FileMetadata> {
+        let name = self.normalize_path_or_err(path)?;
+        if self.index.dirs.contains_key(&name) {
+            return Ok(FileMetadata {
+                size: 0,
+                is_dir: true,
+                modified: None,
+            });
+        }
+        if let Some(config) = self.generated_config(&name) {
+            return Ok(FileMetadata {
+                size: config.len() as u64,
+                is_dir: false,
+                modified: None,
+            });
+        }
+
+        // Only the header of the entry is read here, never its contents
+        let stored_name = self.index.stored_name(&name, path)?;
+        let mut archive = self.reader();
+        let index = archive.index_for_name(stored_name).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("file not found: {}", path.display()),
+            )
+        })?;
+        let file = archive.by_index_raw(index)?;
+        Ok(FileMetadata {
+            size: file.size(),
+            is_dir: false,
+            modified: file.last_modified().and_then(zip_time_to_system_time),
+        })
+    }
+
+    fn canonicalize(&self, path: &Path) -> IoResult<PathBuf> {
+        // Names in the archive are already canonical, and
+        // paths outside of it can never be spelled differently
+        let name = self.normalize_path_or_err(path)?;
+        if self.index.contains(&name) || self.generated_config(&name).is_some() {
+            Ok(PathBuf::from(name))
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("file not found: {}", path.display()),
+            ))
+        }
+    }
+
+    fn is_empty_dir(&self, path: &Path) -> IoResult<bool> {
+        let name = self.normalize_path_or_err(path)?;
+        match self.index.dirs.get(&name) {
+            Some(children) => Ok(children.is_empty()),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("directory not found: {}", path.display()),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{ErrorKind, Write};
+
+    use lune_utils::fs::StdFileSystem;
+
+    use super::*;
+
+    const TREE: &[(&str, &str)] = &[
+        ("main.luau", "require(\"./lib\")"),
+        ("lib/init.luau", "require(\"@self/util\")"),
+        ("lib/util.luau", "require(\"./helper\")"),
+        ("lib/helper.lua", "return {}"),
+        ("modules/a.luau", "return {}"),
+        ("ambiguous.luau", "return {}"),
+        ("ambiguous.lua", "return {}"),
+        ("empty/readme.txt", "not a module"),
+    ];
+
+    fn zip_tree() -> ZipFileSystem {
+        zip_tree_with(zip::CompressionMethod::Stored)
+    }
+
+    fn zip_tree_with(method: zip::CompressionMethod) -> ZipFileSystem {
+        let mut data = Vec::new();
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut data));
+        let options = zip::write::FileOptions::<()>::default().compression_method(method);
+        for (name, contents) in TREE {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        ZipFileSystem::new(data).unwrap()
+    }
+
+    fn std_tree() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("lune-fs-test-{}", std::process::id()));
+        for (name, contents) in TREE {
+            let path = root.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn resolves_requires_identically() {
+        let zip_fs = zip_tree();
+        let root = std_tree();
+
+        let cases = [
+            ("main.luau", "./lib"),
+            ("lib/init.luau", "@self/util"),
+            ("lib/util.luau", "./helper"),
+            ("lib/util.luau", "../modules/a"),
+            ("main.luau", "./ambiguous"),
+            ("main.luau", "./empty"),
+            ("main.luau", "./missing"),
+            ("main.luau", "@alias/thing"),
+        ];
+        let expected: [Result<&str, ErrorKind>; 8] = [
+            Ok("lib/init.luau"),
+            Ok("lib/util.luau"),
+            Ok("lib/helper.lua"),
+            Ok("modules/a.luau"),
+            Err(ErrorKind::InvalidInput),
+            Err(ErrorKind::NotFound),
+            Err(ErrorKind::NotFound),
+            Err(ErrorKind::Unsupported),
+        ];
+
+        for ((from, specifier), expected) in cases.into_iter().zip(expected) {
+            let expected = expected.map(PathBuf::from);
+            let from_zip = zip_fs.resolve(Path::new(from), specifier);
+            let from_std = StdFileSystem.resolve(&root.join(from), specifier);
+            assert_eq!(
+                from_zip.map_err(|e| e.kind()),
+                expected,
+                "zip: require(\"{specifier}\") from {from}"
+            );
+            assert_eq!(
+                from_std
+                    .map(|path| path.strip_prefix(&root).unwrap().to_path_buf())
+                    .map_err(|e| e.kind()),
+                expected,
+                "std: require(\"{specifier}\") from {from}"
+            );
+        }
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn files_are_not_directories() {
+        let zip_fs = zip_tree();
+        assert!(zip_fs.is_dir(Path::new("lib")));
+        assert!(zip_fs.is_dir(Path::new("empty")));
+        assert!(!zip_fs.is_dir(Path::new("lib/util.luau")));
+        assert!(!zip_fs.is_dir(Path::new("missing")));
+    }
+
+    #[test]
+    fn zip_writes_are_unsupported() {
+        let zip_fs = zip_tree();
+        let path = Path::new("main.luau");
+        let kind = |res: IoResult<()>| res.unwrap_err().kind();
+        assert_eq!(kind(zip_fs.write(path, b"")), ErrorKind::Unsupported);
+        assert_eq!(
+            kind(zip_fs.create_dir_all(Path::new("new"))),
+            ErrorKind::Unsupported
+        );
+        assert_eq!(kind(zip_fs.remove_file(path)), ErrorKind::Unsupported);
+        assert_eq!(zip_fs.read_to_string(path).unwrap(), "require(\"./lib\")");
+    }
+
+    #[test]
+    fn read_dir_lists_direct_children() {
+        let zip_fs = zip_tree();
+        let read_dir = |path: &str| zip_fs.read_dir(Path::new(path)).unwrap();
+        assert_eq!(
+            read_dir(""),
+            [
+                "ambiguous.lua",
+                "ambiguous.luau",
+                "empty",
+                "lib",
+                "main.luau",
+                "modules"
+            ]
+            .map(PathBuf::from)
+        );
+        assert_eq!(
+            read_dir("lib"),
+            ["lib/helper.lua", "lib/init.luau", "lib/util.luau"].map(PathBuf::from)
+        );
+        assert_eq!(read_dir("modules"), [PathBuf::from("modules/a.luau")]);
+        assert_eq!(zip_fs.list_all().len(), TREE.len());
+        assert_eq!(zip_fs.list_all()[0], "ambiguous.lua");
+        assert!(read_dir("missing").is_empty());
+        assert!(!zip_fs.is_empty_dir(Path::new("empty")).unwrap());
+        assert!(zip_fs.is_file(Path::new("lib/util.luau")));
+        assert!(!zip_fs.is_file(Path::new("lib")));
+    }
+
+    #[test]
+    fn reads_compressed_archives() {
+        for method in [
+            zip::CompressionMethod::Stored,
+            zip::CompressionMethod::Deflated,
+            zip::CompressionMethod::Zstd,
+        ] {
+            let zip_fs = zip_tree_with(method);
+            for (name, contents) in TREE {
+                assert_eq!(
+                    zip_fs.read_to_string(Path::new(name)).unwrap(),
+                    *contents,
+                    "{method}: {name}"
+                );
             }
+        }
+    }
 
-            let suffix = &file_name[prefix.len()..];
-            // If suffix contains /, it's a sub-sub-file.
-            // We only want direct children.
-            // But if it's a directory, it might end with /
+    #[test]
+    fn rejects_unsupported_compression_methods() {
+        let mut data = Vec::new();
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut data));
+        for (name, contents) in TREE {
+            zip.start_file(*name, zip::write::FileOptions::<()>::default())
+                .unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        assert!(ZipFileSystem::new(data.clone()).is_ok());
 
-            let parts: Vec<&str> = suffix.split('/').filter(|s| !s.is_empty()).collect();
-            if parts.is_empty() {
-                continue;
+        // Change the method of the last file to one that is never supported,
+        // in both its local header and its central directory header
+        let method = 97_u16.to_le_bytes();
+        let local = data.windows(4).rposition(|w| w == b"PK\x03\x04").unwrap();
+        let central = data.windows(4).rposition(|w| w == b"PK\x01\x02").unwrap();
+        data[local + 8..local + 10].copy_from_slice(&method);
+        data[central + 10..central + 12].copy_from_slice(&method);
+
+        let err = ZipFileSystem::new(data).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+        assert!(err.to_string().contains("empty/readme.txt"), "{err}");
+    }
+
+    #[test]
+    fn builds_archives_with_canonical_names() {
+        let mut builder = ZipArchiveBuilder::new();
+        let options = SimpleFileOptions::default();
+        assert_eq!(
+            builder
+                .add_file("./main.luau", b"return 1", options)
+                .unwrap(),
+            "main.luau"
+        );
+        builder
+            .add_file("lib\\util.luau", b"return 2", options)
+            .unwrap();
+
+        let err = builder
+            .add_file("lib/./util.luau", b"", options)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(
+            err.to_string(),
+            "'lib\\util.luau' and 'lib/./util.luau' would both be stored as 'lib/util.luau'"
+        );
+        let err = builder
+            .add_file("../escape.luau", b"", options)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        let zip_fs = ZipFileSystem::new(builder.finish().unwrap()).unwrap();
+        assert_eq!(zip_fs.list_all(), ["lib/util.luau", "main.luau"]);
+        assert_eq!(
+            zip_fs.read_to_string(Path::new("lib/util.luau")).unwrap(),
+            "return 2"
+        );
+    }
+
+    #[test]
+    fn reads_explicit_directory_entries() {
+        let mut data = Vec::new();
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut data));
+        let options = zip::write::FileOptions::<()>::default();
+        zip.add_directory("assets/", options).unwrap();
+        zip.start_file("main.luau", options).unwrap();
+        zip.write_all(b"return 1").unwrap();
+        zip.finish().unwrap();
+
+        let zip_fs = ZipFileSystem::new(data).unwrap();
+        assert_eq!(zip_fs.list_all(), ["main.luau"]);
+        assert!(zip_fs.is_dir(Path::new("assets")));
+        assert!(!zip_fs.is_file(Path::new("assets")));
+        assert!(zip_fs.is_empty_dir(Path::new("assets")).unwrap());
+    }
+
+    #[test]
+    fn caches_repeated_reads() {
+        let zip_fs = zip_tree_with(zip::CompressionMethod::Deflated);
+        let first = zip_fs.read_cached(Path::new("lib/util.luau")).unwrap();
+        let second = zip_fs.read_cached(Path::new("lib/util.luau")).unwrap();
+        assert!(Arc::ptr_eq(&first, &second), "file was decompressed twice");
+
+        // Clones share the same cache, and reading through the trait uses it too
+        let clone = zip_fs.clone();
+        let third = clone.read_cached(Path::new("lib/util.luau")).unwrap();
+        assert!(Arc::ptr_eq(&first, &third), "clone did not share the cache");
+        assert_eq!(
+            clone.read(Path::new("lib/util.luau")).unwrap(),
+            first.as_slice()
+        );
+        assert_eq!(zip_fs.cache.lock().unwrap().len(), 1);
+
+        assert!(zip_fs.read_cached(Path::new("missing.luau")).is_err());
+        assert_eq!(zip_fs.cache.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn reads_files_concurrently() {
+        let zip_fs = zip_tree_with(zip::CompressionMethod::Deflated);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for (name, contents) in TREE {
+                        let path = Path::new(name);
+                        assert_eq!(zip_fs.read_to_string(path).unwrap(), *contents);
+                        assert_eq!(zip_fs.metadata(path).unwrap().size, contents.len() as u64);
+                    }
+                });
             }
+        });
+        assert_eq!(zip_fs.cache.lock().unwrap().len(), TREE.len());
+    }
 
-            // The direct child name is the first part
-            let child_name = parts[0];
-            let child_path = if prefix.is_empty() {
-                PathBuf::from(child_name)
-            } else {
-                // Construct path correctly using Path (OS dependent separator)
-                // But input path was normalized.
-                // We should return PathBufs relative to root or whatever expected.
-                // The trait returns Vec<PathBuf>. Usually absolute or relative depending on input?
-                // StdFileSystem returns entries which are joined with input path.
-                path.join(child_name)
-            };
+    #[test]
+    fn streams_opened_files() {
+        let zip_fs = zip_tree_with(zip::CompressionMethod::Deflated);
+        let mut contents = String::new();
+        zip_fs
+            .open(Path::new("lib/util.luau"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(
+            contents,
+            zip_fs.read_to_string(Path::new("lib/util.luau")).unwrap()
+        );
+        assert_eq!(zip_fs.cache.lock().unwrap().len(), 1);
+        assert!(zip_fs.open(Path::new("lib")).is_err());
+        assert!(zip_fs.open(Path::new("missing.luau")).is_err());
+    }
+
+    #[test]
+    fn normalizes_paths_within_the_archive_root() {
+        let zip_fs = zip_tree();
+        let normalize = |path: &str| zip_fs.normalize_path(Path::new(path));
+        assert_eq!(normalize("./a/../b.luau").as_deref(), Some("b.luau"));
+        assert_eq!(normalize("/init.luau").as_deref(), Some("init.luau"));
+        assert_eq!(
+            normalize("lib\\util.luau").as_deref(),
+            Some("lib/util.luau")
+        );
+        assert_eq!(normalize("lib/./../lib/").as_deref(), Some("lib"));
+        assert_eq!(normalize("../escape"), None);
+        assert_eq!(normalize("a/../../escape"), None);
+
+        assert!(zip_fs.is_file(Path::new("/main.luau")));
+        assert!(zip_fs.is_file(Path::new("./modules/../lib/util.luau")));
+        assert!(!zip_fs.is_file(Path::new("../main.luau")));
+        assert!(zip_fs.is_dir(Path::new("/")));
+        assert_eq!(
+            zip_fs.read(Path::new("../main.luau")).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn normalizes_paths_within_the_given_root() {
+        let root = if cfg!(windows) {
+            "C:\\project"
+        } else {
+            "/project"
+        };
+        let zip_fs = zip_tree().with_root(root);
+        let root = Path::new(root);
+        assert!(zip_fs.is_file(&root.join("main.luau")));
+        assert!(zip_fs.is_file(&root.join("lib").join("util.luau")));
+        assert!(zip_fs.is_dir(root));
+        assert_eq!(
+            zip_fs.read_to_string(&root.join("modules/a.luau")).unwrap(),
+            "return {}"
+        );
+
+        // Relative paths and paths outside of the root are unchanged
+        assert!(zip_fs.is_file(Path::new("lib/util.luau")));
+        assert!(zip_fs.is_file(Path::new("/main.luau")));
+        assert!(!zip_fs.is_file(&root.join("project/main.luau")));
+    }
 
-            if !entries.contains(&child_path) {
-                entries.push(child_path);
+    #[test]
+    fn checks_existence_of_archive_paths() {
+        let zip_fs = zip_tree().with_aliases([("Lib".to_string(), "lib".to_string())]);
+        for path in [
+            "main.luau",
+            "lib",
+            "lib/",
+            "./lib/../lib/util.luau",
+            "@lib/util.luau",
+            "/",
+        ] {
+            assert!(zip_fs.exists(Path::new(path)), "{path}");
+        }
+        assert!(zip_fs.exists(Path::new(".luaurc")));
+        assert!(!zip_fs.exists(Path::new("missing.luau")));
+        assert!(!zip_fs.exists(Path::new("../main.luau")));
+    }
+
+    #[test]
+    fn canonicalizes_archive_paths() {
+        let zip_fs = zip_tree().with_root("/project");
+        let canonical = |path: &str| zip_fs.canonicalize(Path::new(path)).unwrap();
+        assert_eq!(canonical("lib/util.luau"), Path::new("lib/util.luau"));
+        assert_eq!(
+            canonical("./modules/../lib/util.luau"),
+            Path::new("lib/util.luau")
+        );
+        assert_eq!(canonical("lib\\util.luau"), Path::new("lib/util.luau"));
+        assert_eq!(canonical("/lib/util.luau"), Path::new("lib/util.luau"));
+        if cfg!(unix) {
+            assert_eq!(
+                canonical("/project/lib/util.luau"),
+                Path::new("lib/util.luau")
+            );
+        }
+        assert_eq!(canonical("lib/"), Path::new("lib"));
+        assert_eq!(
+            zip_fs
+                .canonicalize(Path::new("lib/missing.luau"))
+                .unwrap_err()
+                .kind(),
+            ErrorKind::NotFound
+        );
+        assert!(zip_fs.canonicalize(Path::new("../main.luau")).is_err());
+    }
+
+    #[test]
+    fn reads_metadata_from_entry_headers() {
+        let zip_fs = zip_tree_with(zip::CompressionMethod::Deflated);
+
+        let meta = zip_fs.metadata(Path::new("lib/util.luau")).unwrap();
+        assert_eq!(meta.size, "require(\"./helper\")".len() as u64);
+        assert!(!meta.is_dir);
+        assert!(meta.modified.is_some());
+        assert!(zip_fs.cache.lock().unwrap().is_empty());
+
+        let meta = zip_fs.metadata(Path::new("lib")).unwrap();
+        assert!(meta.is_dir);
+        assert_eq!(meta.size, 0);
+
+        assert_eq!(
+            zip_fs.metadata(Path::new("missing")).unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn converts_zip_times() {
+        // Times in archives only have a resolution of two seconds
+        let time = DateTime::from_date_and_time(2024, 2, 29, 12, 30, 16).unwrap();
+        let since_epoch = zip_time_to_system_time(time)
+            .unwrap()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        assert_eq!(since_epoch.as_secs(), 1_709_209_816);
+
+        let time = DateTime::from_date_and_time(1980, 1, 1, 0, 0, 0).unwrap();
+        let since_epoch = zip_time_to_system_time(time)
+            .unwrap()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        assert_eq!(since_epoch.as_secs(), 315_532_800);
+
+        assert_eq!(
+            unix_time_to_zip_time(1_709_209_816),
+            DateTime::from_date_and_time(2024, 2, 29, 12, 30, 16).ok()
+        );
+        assert_eq!(
+            unix_time_to_zip_time(315_532_800),
+            Some(DateTime::default())
+        );
+        assert_eq!(unix_time_to_zip_time(0), None);
+        assert_eq!(unix_time_to_zip_time(u64::MAX), None);
+    }
+
+    #[test]
+    fn builds_identical_archives() {
+        let build = || {
+            let mut builder = ZipArchiveBuilder::new();
+            let options = SimpleFileOptions::default();
+            builder.add_file("main.luau", b"return 1", options).unwrap();
+            builder
+                .add_file("assets/data.json", b"{}", options)
+                .unwrap();
+            builder.finish().unwrap()
+        };
+        let first = build();
+        assert_eq!(first, build());
+
+        let mut archive = ZipArchive::new(Cursor::new(first)).unwrap();
+        let file = archive.by_name("main.luau").unwrap();
+        assert_eq!(file.last_modified(), Some(source_date()));
+    }
+
+    #[test]
+    fn reads_entries_stored_with_backslashes() {
+        // Simulates an archive built on Windows, with names using backslashes
+        let mut data = Vec::new();
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut data));
+        let options = zip::write::FileOptions::<()>::default();
+        zip.start_file("lib\\nested\\util.luau", options).unwrap();
+        zip.write_all(b"return {}").unwrap();
+        zip.start_file("lib/mixed\\init.luau", options).unwrap();
+        zip.write_all(b"return true").unwrap();
+        zip.finish().unwrap();
+        let zip_fs = ZipFileSystem::new(data).unwrap();
+
+        let path = Path::new("lib/nested/util.luau");
+        assert!(zip_fs.is_file(path));
+        assert!(zip_fs.is_dir(Path::new("lib/nested")));
+        assert_eq!(zip_fs.read_to_string(path).unwrap(), "return {}");
+        assert_eq!(zip_fs.metadata(path).unwrap().size, 9);
+        assert_eq!(
+            zip_fs
+                .read_to_string(Path::new("/lib/mixed/init.luau"))
+                .unwrap(),
+            "return true"
+        );
+        assert_eq!(
+            zip_fs.read_dir(Path::new("lib")).unwrap(),
+            ["lib/mixed", "lib/nested"].map(PathBuf::from)
+        );
+        assert_eq!(
+            zip_fs
+                .file_entries()
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.name)
+                .collect::<Vec<_>>(),
+            ["lib/mixed/init.luau", "lib/nested/util.luau"]
+        );
+    }
+
+    #[test]
+    fn read_dir_paths_round_trip() {
+        let zip_fs = zip_tree().with_aliases([("lib".to_string(), "lib".to_string())]);
+        assert_eq!(
+            zip_fs.read_dir(Path::new("lib\\")).unwrap()[0],
+            PathBuf::from("lib/helper.lua")
+        );
+        assert_eq!(
+            zip_fs.read_dir(Path::new("/modules/")).unwrap(),
+            [PathBuf::from("/modules/a.luau")]
+        );
+
+        // Returned paths can always be given back to the filesystem as they are
+        for dir in ["", "/", "lib", "./lib/", "lib\\", "/empty", "@lib"] {
+            for child in zip_fs.read_dir(Path::new(dir)).unwrap() {
+                let child_str = child.to_string_lossy();
+                assert!(!child_str.contains('\\'), "{dir}: {child_str}");
+                assert!(zip_fs.exists(&child), "{dir}: {child_str}");
             }
         }
+        let nested = zip_fs.read_dir(Path::new("/")).unwrap();
+        let lib = nested.iter().find(|child| child.ends_with("lib")).unwrap();
+        for child in zip_fs.read_dir(lib).unwrap() {
+            assert!(zip_fs.is_file(&child), "{}", child.display());
+        }
+    }
 
-        Ok(entries)
+    #[test]
+    fn limits_decompressed_sizes() {
+        // Highly compressible contents, same as in a zip bomb, but much smaller
+        let mut data = Vec::new();
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut data));
+        let options = zip::write::FileOptions::<()>::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(&[0; 4096]).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let limits = |max_file_size, max_total_size| ZipLimits {
+            max_file_size,
+            max_total_size,
+        };
+        let zip_fs = ZipFileSystem::new(data.clone())
+            .unwrap()
+            .with_limits(limits(1024, u64::MAX));
+        assert_eq!(
+            zip_fs.read(Path::new("a.txt")).unwrap_err().kind(),
+            ErrorKind::FileTooLarge
+        );
+        assert!(zip_fs.cache.lock().unwrap().is_empty());
+
+        let zip_fs = ZipFileSystem::new(data)
+            .unwrap()
+            .with_limits(limits(4096, 8192));
+        assert_eq!(zip_fs.read(Path::new("a.txt")).unwrap().len(), 4096);
+        assert_eq!(zip_fs.read(Path::new("b.txt")).unwrap().len(), 4096);
+        assert_eq!(
+            zip_fs.read(Path::new("c.txt")).unwrap_err().kind(),
+            ErrorKind::FileTooLarge
+        );
+
+        // Cached files are never decompressed again, so they can still be read
+        assert_eq!(zip_fs.read(Path::new("a.txt")).unwrap().len(), 4096);
+    }
+
+    #[test]
+    fn resolves_aliased_paths() {
+        let zip_fs = zip_tree().with_aliases([
+            ("Lib".to_string(), "lib".to_string()),
+            ("modules".to_string(), "./modules/".to_string()),
+        ]);
+        assert!(zip_fs.is_file(Path::new("@lib/util.luau")));
+        assert!(zip_fs.is_file(Path::new("@LIB/helper.lua")));
+        assert!(zip_fs.is_dir(Path::new("@modules")));
+        assert!(!zip_fs.is_file(Path::new("@unknown/util.luau")));
+        assert_eq!(
+            zip_fs.read_to_string(Path::new("@lib/util.luau")).unwrap(),
+            "require(\"./helper\")"
+        );
+        assert_eq!(
+            zip_fs.resolve(Path::new("main.luau"), "@lib/util").unwrap(),
+            PathBuf::from("@lib/util.luau")
+        );
+        assert_eq!(
+            zip_fs
+                .resolve(Path::new("main.luau"), "@modules/a")
+                .unwrap(),
+            PathBuf::from("@modules/a.luau")
+        );
+        assert_eq!(
+            zip_fs
+                .resolve(Path::new("main.luau"), "@unknown/a")
+                .unwrap_err()
+                .kind(),
+            ErrorKind::Unsupported
+        );
+
+        // A config is provided for require to find the aliases at runtime
+        let config = zip_fs.read(Path::new(FILE_NAME_CONFIG)).unwrap();
+        let config = serde_json::from_slice::<serde_json::Value>(&config).unwrap();
+        assert_eq!(config["aliases"]["lib"], "./lib");
+        assert_eq!(config["aliases"]["modules"], "./modules");
+        assert!(!zip_tree().is_file(Path::new(FILE_NAME_CONFIG)));
     }
 }
@@ -22,7 +22,13 @@ fn main() -> ExitCode {
 
     async_io::block_on(async {
         if let Some(bin) = standalone::check().await {
-            return standalone::run(bin).await.unwrap();
+            return match standalone::run(bin).await {
+                Ok(code) => code,
+                Err(err) => {
+                    eprintln!("{}\n{err:?}", Label::Error);
+                    ExitCode::FAILURE
+                }
+            };
         }
 
         #[cfg(feature = "cli")]
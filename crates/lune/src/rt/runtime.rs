@@ -12,7 +12,9 @@ use std::{
 use async_fs as fs;
 use lune_utils::{
     path::{LuauModulePath, constants::FILE_CHUNK_PREFIX},
-    process::{ProcessArgs, ProcessEnv, ProcessJitEnablement},
+    process::{
+        ProcessArgs, ProcessBuildConfig, ProcessEnv, ProcessJitEnablement, ProcessProgramName,
+    },
 };
 use mlua::prelude::*;
 use mlua_luau_scheduler::{Functions, Scheduler};
@@ -67,8 +69,11 @@ pub struct Runtime {
     lua: Lua,
     sched: Scheduler,
     args: ProcessArgs,
+    program_name: ProcessProgramName,
     env: ProcessEnv,
     jit: ProcessJitEnablement,
+    build_config: ProcessBuildConfig,
+    removed_globals: Vec<String>,
 }
 
 impl Runtime {
@@ -135,15 +140,20 @@ impl Runtime {
         }
 
         let args = ProcessArgs::current();
+        let program_name = ProcessProgramName::current();
         let env = ProcessEnv::current();
         let jit = ProcessJitEnablement::default();
+        let build_config = ProcessBuildConfig::default();
 
         Ok(Self {
             lua,
             sched,
             args,
+            program_name,
             env,
             jit,
+            build_config,
+            removed_globals: Vec::new(),
         })
     }
 
@@ -162,6 +172,17 @@ impl Runtime {
         self
     }
 
+    /**
+        Sets the program name to give in `process.programName` for Lune scripts.
+
+        By default, the file name of the first argument given to the current process is used.
+    */
+    #[must_use]
+    pub fn with_program_name(mut self, name: impl Into<String>) -> Self {
+        self.program_name = ProcessProgramName::new(name.into());
+        self
+    }
+
     /**
         Sets environment values to give in `process.env` for Lune scripts.
 
@@ -190,6 +211,36 @@ impl Runtime {
         self
     }
 
+    /**
+        Sets the build config, given as JSON text, to give in `process.buildConfig` for Lune scripts.
+
+        By default, no build config is given, and `process.buildConfig` will be `nil`.
+    */
+    #[must_use]
+    pub fn with_build_config(mut self, json: impl Into<String>) -> Self {
+        self.build_config = ProcessBuildConfig::new(json.into());
+        self
+    }
+
+    /**
+        Removes globals and standard libraries, so that Lune scripts can not use them.
+
+        Globals may be nested, such as `task.spawn`, while standard libraries are
+        given using their require path, such as `@lune/process`, and are always
+        removed entirely.
+
+        By default, no globals or standard libraries are removed.
+    */
+    #[must_use]
+    pub fn with_removed_globals<G, S>(mut self, globals: G) -> Self
+    where
+        G: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.removed_globals = globals.into_iter().map(Into::into).collect();
+        self
+    }
+
     /**
         Adds a custom library to the runtime, making it available through `require`.
 
@@ -258,7 +309,38 @@ impl Runtime {
     }
 
     /**
-        Sets the filesystem implementation to use for `require` calls.
+        Adds a custom global to the runtime, available to all Lune scripts without requiring it.
+
+        Globals added this way can still be removed using [`Runtime::with_removed_globals`].
+
+        # Errors
+
+        Returns an error if:
+
+        - The global name is empty
+        - The provided `make_global` function errors
+    */
+    pub fn with_global<S, F>(self, name: S, make_global: F) -> RuntimeResult<Self>
+    where
+        S: AsRef<str>,
+        F: FnOnce(&Lua) -> LuaResult<LuaValue>,
+    {
+        let name = name.as_ref().trim();
+        if name.is_empty() {
+            return Err(RuntimeError::from(LuaError::external(
+                "Global names must not be empty",
+            )));
+        }
+
+        let global = make_global(&self.lua)?;
+        self.lua.globals().set(name, global)?;
+
+        Ok(self)
+    }
+
+    /**
+        Sets the filesystem implementation to use for `require` calls,
+        as well as for standard library functions that support it.
 
         This overwrites the default `require` logic.
     */
@@ -266,6 +348,7 @@ impl Runtime {
         self,
         fs: std::sync::Arc<dyn lune_utils::fs::FileSystem>,
     ) -> RuntimeResult<Self> {
+        lune_utils::fs::set_lua_filesystem(&self.lua, Arc::clone(&fs));
         let resolver = lune_std::RequireResolver::new_with_fs(fs);
         self.lua
             .create_require_function(resolver)
@@ -366,10 +449,12 @@ impl Runtime {
             eprintln!("{}", RuntimeError::from(e));
         });
 
-        // Store the provided args, environment variables, and jit enablement as AppData
+        // Store the provided args, program name, environment variables, jit enablement and build config as AppData
         self.lua.set_app_data(self.args.clone());
+        self.lua.set_app_data(self.program_name.clone());
         self.lua.set_app_data(self.env.clone());
         self.lua.set_app_data(self.jit);
+        self.lua.set_app_data(self.build_config.clone());
 
         // Inject all the standard libraries that are enabled - this needs to be done after
         // storing the args/env, since some standard libraries use those during initialization
@@ -389,6 +474,9 @@ impl Runtime {
             lune_std::inject_std(self.lua.clone())?;
         }
 
+        // Remove any globals and standard libraries that scripts should not have access to
+        remove_globals(&self.lua, &self.removed_globals)?;
+
         // Enable / disable the JIT as requested, before loading anything
         self.lua.enable_jit(self.jit.enabled());
 
@@ -416,6 +504,74 @@ impl Runtime {
     }
 }
 
+/**
+    Removes the given globals and standard libraries from the Luau VM.
+
+    Since the globals of a sandboxed VM are read-only, they can not be removed
+    directly - top-level globals are instead hidden behind the global proxy table,
+    and nested globals are shadowed by a copy of their parent without the field.
+*/
+fn remove_globals(lua: &Lua, paths: &[String]) -> LuaResult<()> {
+    let globals = lua.globals();
+    let mut hidden = Vec::new();
+
+    for path in paths {
+        if path.starts_with('@') {
+            lua.unload_module(path)?;
+            continue;
+        }
+        let mut parts = path.split('.');
+        let root = parts.next().unwrap_or_default().to_string();
+        let fields = parts.collect::<Vec<_>>();
+        if fields.is_empty() {
+            globals.raw_set(root.as_str(), LuaValue::Nil)?;
+            hidden.push(root);
+        } else if let LuaValue::Table(table) = globals.get::<LuaValue>(root.as_str())? {
+            globals.raw_set(root, copy_without_field(lua, &table, &fields)?)?;
+        }
+    }
+
+    let original = globals
+        .metatable()
+        .and_then(|meta| meta.raw_get::<LuaValue>("__index").ok());
+    if let Some(LuaValue::Table(original)) = original
+        && !hidden.is_empty()
+    {
+        let index = lua.create_function(move |_, (_, key): (LuaValue, LuaValue)| {
+            if let LuaValue::String(name) = &key
+                && hidden.iter().any(|hidden| *name == hidden.as_str())
+            {
+                return Ok(LuaValue::Nil);
+            }
+            original.get::<LuaValue>(key)
+        })?;
+        let meta = lua.create_table()?;
+        meta.raw_set("__index", index)?;
+        globals.set_metatable(Some(meta))?;
+    }
+
+    Ok(())
+}
+
+fn copy_without_field(lua: &Lua, table: &LuaTable, fields: &[&str]) -> LuaResult<LuaTable> {
+    let copy = lua.create_table()?;
+    for pair in table.pairs::<LuaValue, LuaValue>() {
+        let (key, value) = pair?;
+        copy.raw_set(key, value)?;
+    }
+    copy.set_metatable(table.metatable())?;
+    match fields {
+        [field] => copy.raw_set(*field, LuaValue::Nil)?,
+        [field, rest @ ..] => {
+            if let LuaValue::Table(inner) = table.raw_get::<LuaValue>(*field)? {
+                copy.raw_set(*field, copy_without_field(lua, &inner, rest)?)?;
+            }
+        }
+        [] => {}
+    }
+    Ok(copy)
+}
+
 fn strip_shebang(mut contents: Vec<u8>) -> Vec<u8> {
     if contents.starts_with(b"#!")
         && let Some(first_newline_idx) = contents
@@ -0,0 +1,86 @@
+/*!
+    Hot reloading for standalone binaries, intended for development only.
+
+    When the `LUNE_HOT_RELOAD` environment variable is set, and a source file with the
+    same name as the standalone binary exists next to it (`app.luau` for `app.exe`),
+    that source file will be run instead of the embedded bytecode, and re-run any time
+    it changes on disk. Binaries distributed without the source file stay fully sealed.
+*/
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Result;
+use async_fs as fs;
+use async_io::Timer;
+use futures_lite::future;
+use lune::Runtime;
+
+use super::metadata::CURRENT_EXE;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/**
+    Returns the path to the on-disk source file for the current
+    standalone binary, if hot reloading has been requested.
+*/
+pub fn source_path() -> Option<PathBuf> {
+    let enabled = env::var("LUNE_HOT_RELOAD")
+        .ok()
+        .is_some_and(|s| matches!(s.as_str(), "1" | "true" | "on"));
+    if !enabled {
+        return None;
+    }
+    let source = CURRENT_EXE.with_extension("luau");
+    source.is_file().then_some(source)
+}
+
+/**
+    Runs the given source file, re-running it whenever it changes on disk.
+
+    This will keep running, even after the script completes,
+    until the process is stopped or an unrecoverable error occurs.
+*/
+pub async fn run(source: PathBuf, new_runtime: impl Fn() -> Result<Runtime>) -> Result<ExitCode> {
+    eprintln!("Hot reloading is enabled, running {}", source.display());
+    loop {
+        let modified = modified_at(&source).await;
+        let contents = fs::read(&source).await?;
+
+        let mut rt = new_runtime()?;
+
+        let changed = future::or(
+            async {
+                match rt.run_custom("@init.luau", contents).await {
+                    Err(err) => eprintln!("{err}"),
+                    Ok(values) if !values.success() => {
+                        eprintln!("Script exited with status {}", values.status());
+                    }
+                    Ok(_) => {}
+                }
+                wait_for_change(&source, modified).await;
+            },
+            wait_for_change(&source, modified),
+        );
+        changed.await;
+
+        eprintln!("Detected changes in {}, reloading...", source.display());
+    }
+}
+
+async fn modified_at(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).await.ok()?.modified().ok()
+}
+
+async fn wait_for_change(path: &Path, last_modified: Option<SystemTime>) {
+    loop {
+        Timer::after(POLL_INTERVAL).await;
+        if modified_at(path).await != last_modified {
+            return;
+        }
+    }
+}
@@ -1,27 +1,156 @@
-use std::{env, path::PathBuf, sync::LazyLock};
+use std::{
+    collections::BTreeMap,
+    env, fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::LazyLock,
+};
 
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail};
 use async_fs as fs;
+use futures_lite::{AsyncWrite, AsyncWriteExt};
 use mlua::Compiler as LuaCompiler;
-use std::io::Write;
+use object::{Object, ObjectSection};
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use zip::CompressionMethod;
+
+use crate::fs::{ZipArchiveBuilder, ZipNames};
 
 pub static CURRENT_EXE: LazyLock<PathBuf> =
     LazyLock::new(|| env::current_exe().expect("failed to get current exe"));
 const MAGIC: &[u8; 8] = b"cr3sc3nt";
+const ZIP_SIGNATURE: &[u8; 2] = b"PK";
+
+/**
+    The name of the binary section that stores the payload, when built using `--embed-mode section`.
+*/
+const SECTION_NAME: &str = ".lune";
+
+/**
+    The name that the input file is stored under in the archive, unless another
+    one was given using `lune build --entry-name`.
+*/
+pub const DEFAULT_ENTRY_NAME: &str = "init.luau";
+
+/**
+    The name of the archive entry that stores the config given using `lune build --config`.
+*/
+pub const BUILD_CONFIG_FILE_NAME: &str = ".lune-build-config.json";
+
+/**
+    The archive directory that stores native libraries given using `lune build --native`.
+*/
+pub const NATIVE_LIBS_DIR: &str = ".lune-native";
 
 /*
-    TODO: Right now all we do is append the bytecode to the end
-    of the binary, but we will need a more flexible solution in
-    the future to store many files as well as their metadata.
+    Keys of the metadata that binaries using the first format version may store in
+    the archive comment - later versions store all metadata in the [`Payload`] instead
+*/
+const LEGACY_COMMENT_KEY_LUNE_VERSION: &str = "lune-version";
+const LEGACY_COMMENT_KEY_VERSION_FLAG: &str = "version-flag";
+const LEGACY_COMMENT_KEY_REMOVED_GLOBALS: &str = "removed-globals";
+const LEGACY_COMMENT_KEY_NATIVE_LIBS: &str = "native-libs";
+
+/**
+    The current version of the standalone payload format.
+
+    - Version 1 stored the raw ZIP archive directly before the length and magic trailer.
+    - Version 2 stores a [`Payload`] serialized using Postcard, containing the ZIP archive.
+    - Version 3 adds a CRC32 checksum of the ZIP archive to the [`Payload`].
+    - Version 4 stores all metadata in the [`Payload`], instead of in the archive comment.
+
+    This must be incremented whenever the [`Payload`] struct changes in a way that
+    older runtimes can not read, so that they fail with a clear error instead.
+*/
+const FORMAT_VERSION: u32 = 4;
 
-    The best solution here is most likely to use a well-supported
-    and rust-native binary serialization format with a stable
-    specification, one that also supports byte arrays well without
-    overhead, so the best solution seems to currently be Postcard:
+/**
+    The serialized payload of a standalone binary, stored using Postcard.
 
-    https://github.com/jamesmunns/postcard
-    https://crates.io/crates/postcard
+    The format version must always be the first field, so that it
+    can be read and checked before the rest of the payload is.
 */
+#[derive(Debug, Serialize, Deserialize)]
+struct Payload {
+    format_version: u32,
+    /// CRC32 checksum of the ZIP archive, to detect truncated or corrupted binaries
+    checksum: u32,
+    #[serde(with = "serde_bytes")]
+    zip_data: Vec<u8>,
+    metadata: PayloadMetadata,
+}
+
+/**
+    Metadata describing how to run the ZIP archive in a [`Payload`].
+*/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PayloadMetadata {
+    lune_version: Option<String>,
+    version_flag: bool,
+    entry_name: String,
+    removed_globals: Vec<String>,
+    native_libs: Vec<String>,
+    entries: BTreeMap<String, String>,
+    aliases: BTreeMap<String, String>,
+}
+
+impl Default for PayloadMetadata {
+    fn default() -> Self {
+        Self {
+            lune_version: None,
+            version_flag: true,
+            entry_name: DEFAULT_ENTRY_NAME.to_string(),
+            removed_globals: Vec::new(),
+            native_libs: Vec::new(),
+            entries: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+        }
+    }
+}
+
+/**
+    How the payload of a standalone binary is stored in the executable.
+*/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmbedMode {
+    /// Appended after the end of the executable, marked by a magic trailer
+    #[default]
+    Trailer,
+    /// Stored in a dedicated binary section, keeping the executable well-formed
+    Section,
+}
+
+impl EmbedMode {
+    pub const ALL: &'static [Self] = &[Self::Trailer, Self::Section];
+}
+
+impl fmt::Display for EmbedMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Trailer => write!(f, "trailer"),
+            Self::Section => write!(f, "section"),
+        }
+    }
+}
+
+impl FromStr for EmbedMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "trailer" => Ok(Self::Trailer),
+            "section" => Ok(Self::Section),
+            _ => Err(format!(
+                "invalid embed mode, expected one of: {}",
+                Self::ALL
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
 
 /**
     Metadata for a standalone Lune executable. Can be used to
@@ -30,64 +159,182 @@ const MAGIC: &[u8; 8] = b"cr3sc3nt";
 #[derive(Debug, Clone)]
 pub struct Metadata {
     pub zip_data: Vec<u8>,
+    /// The version of the payload format that the binary was built with
+    pub format_version: u32,
+    /// The version of Lune that the binary was built with, if known
+    pub lune_version: Option<String>,
+    /// Whether the reserved `--lune-version` argument should be handled
+    pub version_flag: bool,
+    /// The archive name of the main entry point, which runs when no named one is given
+    pub entry_name: String,
+    /// Globals and standard libraries that were removed when building
+    pub removed_globals: Vec<String>,
+    /// Archive names of native libraries to extract before running
+    pub native_libs: Vec<String>,
+    /// Named entry points, mapped to the archive names of their files
+    pub entries: BTreeMap<String, String>,
+    /// Require aliases, mapped to the archive directories that they point to
+    pub aliases: BTreeMap<String, String>,
+}
+
+/**
+    Options for creating a standalone binary, other than the files embedded in it.
+*/
+#[derive(Debug, Clone)]
+pub struct PatchOptions {
+    /// The compression method for all files in the embedded archive
+    pub compression: CompressionMethod,
+    /// Whether the reserved `--lune-version` argument should be handled
+    pub version_flag: bool,
+    /// The archive name to store the main script under, such as [`DEFAULT_ENTRY_NAME`]
+    pub entry_name: String,
+    /// Globals and standard libraries to remove when running
+    pub removed_globals: Vec<String>,
+    /// Archive names of embedded native libraries, which must be in [`NATIVE_LIBS_DIR`]
+    pub native_libs: Vec<String>,
+    /// Named entry points, mapped to the archive names of their files
+    pub entries: BTreeMap<String, String>,
+    /// Require aliases, mapped to the archive directories that they point to
+    pub aliases: BTreeMap<String, String>,
 }
 
 impl Metadata {
     /**
         Returns whether or not the currently executing Lune binary
         is a standalone binary, and if so, the bytes of the binary.
+
+        For binaries that store their payload in a section, only the
+        contents of that section are returned instead of the full binary.
+        Binaries that are not standalone are never read in full, and no
+        bytes are returned for them.
     */
     pub async fn check_env() -> (bool, Vec<u8>) {
-        let contents = fs::read(CURRENT_EXE.to_path_buf())
-            .await
-            .unwrap_or_default();
-        let is_standalone = contents.ends_with(MAGIC);
-        (is_standalone, contents)
+        let path = CURRENT_EXE.to_path_buf();
+        match blocking::unblock(move || read_exe_payload(&path)).await {
+            Ok(Some(payload)) => (true, payload),
+            Ok(None) | Err(_) => (false, Vec::new()),
+        }
     }
 
     /**
-        Creates a patched standalone binary from the given script contents.
+        Compiles the given script contents into the bytecode that is stored as the main
+        entry point, stripping all debug info, such as line numbers, if `minify` is set.
+
+        Compiling is kept separate from creating the archive, so that a script
+        only needs to be compiled once when building for several targets.
     */
-    pub async fn create_env_patched_bin(
-        base_exe_path: PathBuf,
-        script_contents: impl Into<Vec<u8>>,
-        extra_files: Vec<(String, Vec<u8>)>,
-    ) -> Result<Vec<u8>> {
+    pub fn compile_script(script_contents: impl Into<Vec<u8>>, minify: bool) -> Result<Vec<u8>> {
         let compiler = LuaCompiler::new()
             .set_optimization_level(2)
             .set_coverage_level(0)
-            .set_debug_level(1);
+            .set_debug_level(if minify { 0 } else { 1 });
+        Ok(compiler.compile(script_contents.into())?)
+    }
+
+    /**
+        Creates the metadata for a standalone binary, storing the given compiled
+        main script and extra files in an archive, to later be written using
+        [`Metadata::patch_bin`].
 
-        let mut patched_bin = fs::read(base_exe_path).await?;
+        All files in the embedded archive, including the main script,
+        will be written using the compression method in the given options.
 
-        // Compile luau input into bytecode
-        let bytecode = compiler.compile(script_contents.into())?;
+        The current Lune version is stored alongside the archive, and can be
+        printed by running the binary with the reserved `--lune-version`
+        argument, unless `version_flag` is `false` in the given options.
+    */
+    pub fn create(
+        bytecode: &[u8],
+        extra_files: &[(String, Vec<u8>)],
+        options: PatchOptions,
+    ) -> Result<Self> {
+        let PatchOptions {
+            compression,
+            version_flag,
+            entry_name,
+            removed_globals,
+            native_libs,
+            entries,
+            aliases,
+        } = options;
 
-        // Create a ZIP archive in memory
-        let mut zip_data = Vec::new();
-        {
-            let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut zip_data));
-            let options = zip::write::FileOptions::<()>::default()
-                .compression_method(zip::CompressionMethod::Stored) // Faster load, larger size
+        // Make sure that every file can be found under its own name once embedded
+        check_archive_names(
+            &entry_name,
+            extra_files.iter().map(|(name, _)| name.as_str()),
+        )?;
+
+        // Create a ZIP archive in memory, with all other metadata stored next to it
+        let (zip_data, entry_name) = {
+            let mut zip = ZipArchiveBuilder::new();
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(compression)
                 .unix_permissions(0o755);
 
-            // Add main script as init.luau
-            zip.start_file("init.luau", options)?;
-            zip.write_all(&bytecode)?;
+            // Add main script under the entry name, which is stored in its canonical
+            // form, so that the runtime can always read it using the recorded name
+            let entry_name = zip.add_file(&entry_name, bytecode, options)?;
 
             // Add extra files
             for (name, content) in extra_files {
-                zip.start_file(name, options)?;
-                zip.write_all(&content)?;
+                zip.add_file(name, content, options)?;
             }
-            zip.finish()?;
-        }
 
-        // Append the ZIP / metadata to the end
-        let meta = Self { zip_data };
-        patched_bin.extend_from_slice(&meta.to_bytes());
+            (zip.finish()?, entry_name)
+        };
+
+        Ok(Self {
+            zip_data,
+            format_version: FORMAT_VERSION,
+            lune_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            version_flag,
+            entry_name,
+            removed_globals,
+            native_libs,
+            entries,
+            aliases,
+        })
+    }
+
+    /**
+        Writes a patched standalone binary, consisting of the given base executable with
+        this metadata embedded in it, to the given output, returning the number of bytes
+        that were written.
+
+        The base executable is streamed to the output instead of being read into memory,
+        unless the payload is stored in a section, which requires rewriting the executable.
+    */
+    pub async fn patch_bin(
+        &self,
+        base_exe_path: &Path,
+        embed_mode: EmbedMode,
+        output: &mut (impl AsyncWrite + Unpin),
+    ) -> Result<u64> {
+        write_patched_bin(base_exe_path, &self.to_bytes(), embed_mode, output).await
+    }
 
-        Ok(patched_bin)
+    /**
+        Returns whether the bytes of a full executable contain a standalone payload,
+        stored using any embed mode, without reading or validating the payload itself.
+    */
+    pub fn is_standalone_exe(bytes: impl AsRef<[u8]>) -> bool {
+        let bytes = bytes.as_ref();
+        bytes.ends_with(MAGIC) || find_payload_section(bytes).is_some()
+    }
+
+    /**
+        Tries to read a standalone binary from the bytes of a full
+        executable, with its payload stored using any embed mode.
+    */
+    pub fn from_exe_bytes(bytes: impl AsRef<[u8]>) -> Result<Self> {
+        let bytes = bytes.as_ref();
+        if bytes.ends_with(MAGIC) {
+            return Self::from_bytes(bytes);
+        }
+        match find_payload_section(bytes) {
+            Some(payload) => Self::from_bytes(payload),
+            None => bail!("not a standalone binary"),
+        }
     }
 
     /**
@@ -99,25 +346,615 @@ impl Metadata {
             bail!("not a standalone binary")
         }
 
-        // Extract payload size
+        // Extract payload size, which can not be larger than the binary
+        // itself, unless it was truncated after it was built
         let payload_size_bytes = &bytes[bytes.len() - 16..bytes.len() - 8];
         let payload_size =
             usize::try_from(u64::from_be_bytes(payload_size_bytes.try_into().unwrap()))?;
+        if payload_size > bytes.len() - 16 {
+            bail!("standalone payload corrupted (truncated)");
+        }
 
-        // Extract payload (ZIP)
-        let zip_data = bytes[bytes.len() - 16 - payload_size..bytes.len() - 16].to_vec();
+        // Extract payload, which is the ZIP archive itself for binaries using
+        // the first format version - those are always the start of an archive,
+        // and may only have some of the metadata, stored in the archive comment
+        let payload = &bytes[bytes.len() - 16 - payload_size..bytes.len() - 16];
+        let (zip_data, format_version, metadata) = if payload.starts_with(ZIP_SIGNATURE) {
+            let metadata = read_legacy_comment(payload)?;
+            (payload.to_vec(), 1, metadata)
+        } else {
+            let payload = read_payload(payload)?;
+            (payload.zip_data, payload.format_version, payload.metadata)
+        };
 
-        Ok(Self { zip_data })
+        Ok(Self {
+            zip_data,
+            format_version,
+            lune_version: metadata.lune_version,
+            version_flag: metadata.version_flag,
+            entry_name: metadata.entry_name,
+            removed_globals: metadata.removed_globals,
+            native_libs: metadata.native_libs,
+            entries: metadata.entries,
+            aliases: metadata.aliases,
+        })
     }
 
     /**
         Writes the metadata chunk to a byte vector, to later bet read using `from_bytes`.
     */
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut bytes = Vec::new();
-        bytes.extend_from_slice(&self.zip_data);
-        bytes.extend_from_slice(&(self.zip_data.len() as u64).to_be_bytes());
+        let payload = Payload {
+            format_version: FORMAT_VERSION,
+            checksum: crc32fast::hash(&self.zip_data),
+            zip_data: self.zip_data.clone(),
+            metadata: PayloadMetadata {
+                lune_version: self.lune_version.clone(),
+                version_flag: self.version_flag,
+                entry_name: self.entry_name.clone(),
+                removed_globals: self.removed_globals.clone(),
+                native_libs: self.native_libs.clone(),
+                entries: self.entries.clone(),
+                aliases: self.aliases.clone(),
+            },
+        };
+        let mut bytes = postcard::to_stdvec(&payload).expect("payload is always serializable");
+        let payload_size = bytes.len() as u64;
+        bytes.extend_from_slice(&payload_size.to_be_bytes());
         bytes.extend_from_slice(MAGIC);
         bytes
     }
 }
+
+/**
+    Checks that the given names of extra files to store in an archive, together with the
+    name of the main entry point, are all distinct once normalized the same way as when
+    reading the archive, since otherwise only one of the files could ever be read at runtime.
+*/
+fn check_archive_names<'a>(
+    entry_name: &str,
+    names: impl IntoIterator<Item = &'a str>,
+) -> Result<()> {
+    let mut stored = ZipNames::default();
+    if stored.insert(entry_name).is_err() {
+        bail!("entry name '{entry_name}' is not a valid archive name");
+    }
+
+    let mut conflicts = Vec::new();
+    for name in names {
+        match stored.insert(name) {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                conflicts.push(format!("    {e}"));
+            }
+            Err(_) => bail!("embedded file name '{name}' is not a valid archive name"),
+        }
+    }
+    if !conflicts.is_empty() {
+        bail!(
+            "found embedded files with conflicting names:\n{}",
+            conflicts.join("\n")
+        );
+    }
+    Ok(())
+}
+
+/**
+    Reads the metadata stored in the archive comment of a binary using the first
+    format version, where binaries built with older versions of Lune will not
+    have any comment at all, and use the default metadata instead.
+*/
+fn read_legacy_comment(zip_data: &[u8]) -> Result<PayloadMetadata> {
+    let archive = zip::ZipArchive::new(Cursor::new(zip_data))?;
+    let comment = String::from_utf8_lossy(archive.comment());
+    let list = |value: &str| {
+        value
+            .split(',')
+            .filter(|name| !name.is_empty())
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    let mut metadata = PayloadMetadata::default();
+    for line in comment.lines() {
+        match line.split_once('=') {
+            Some((LEGACY_COMMENT_KEY_LUNE_VERSION, value)) => {
+                metadata.lune_version = Some(value.to_string());
+            }
+            Some((LEGACY_COMMENT_KEY_VERSION_FLAG, value)) => {
+                metadata.version_flag = value != "false";
+            }
+            Some((LEGACY_COMMENT_KEY_REMOVED_GLOBALS, value)) => {
+                metadata.removed_globals = list(value);
+            }
+            Some((LEGACY_COMMENT_KEY_NATIVE_LIBS, value)) => metadata.native_libs = list(value),
+            _ => {}
+        }
+    }
+    Ok(metadata)
+}
+
+/**
+    Reads a [`Payload`] serialized using Postcard, checking its format
+    version first, and then the checksum of the contained ZIP archive.
+*/
+fn read_payload(bytes: &[u8]) -> Result<Payload> {
+    let (format_version, _) = postcard::take_from_bytes::<u32>(bytes)
+        .context("failed to read standalone binary format version")?;
+    if format_version > FORMAT_VERSION {
+        bail!(
+            "standalone binary built with newer Lune format v{format_version}, \
+            this runtime supports v{FORMAT_VERSION}"
+        );
+    } else if format_version != FORMAT_VERSION {
+        bail!(
+            "standalone binary built with unsupported Lune format v{format_version}, \
+            this runtime supports v{FORMAT_VERSION}"
+        );
+    }
+    let payload: Payload =
+        postcard::from_bytes(bytes).context("failed to read standalone binary payload")?;
+    if crc32fast::hash(&payload.zip_data) != payload.checksum {
+        bail!("standalone payload corrupted (checksum mismatch)");
+    }
+    Ok(payload)
+}
+
+/**
+    Reads the payload of the standalone executable at the given path, if it has one.
+
+    Only the trailing magic is read first, and the full executable is only read if it
+    is present. Otherwise, only the headers needed to find a payload section are read,
+    which is the common case for the normal Lune binary, and keeps startup fast.
+*/
+fn read_exe_payload(path: &Path) -> std::io::Result<Option<Vec<u8>>> {
+    let mut file = std::fs::File::open(path)?;
+
+    let mut magic = [0; MAGIC.len()];
+    let len = file.seek(SeekFrom::End(0))?;
+    if len >= MAGIC.len() as u64 {
+        file.seek(SeekFrom::End(-(MAGIC.len() as i64)))?;
+        file.read_exact(&mut magic)?;
+    }
+    if &magic == MAGIC {
+        let mut contents = Vec::with_capacity(len as usize);
+        file.seek(SeekFrom::Start(0))?;
+        file.read_to_end(&mut contents)?;
+        return Ok(Some(contents));
+    }
+
+    let cache = object::ReadCache::new(file);
+    Ok(find_payload_section(&cache).map(<[u8]>::to_vec))
+}
+
+/**
+    Finds the payload section in the given executable, if it has one.
+
+    The section contains the same bytes as the trailer that would otherwise
+    be appended to the executable, and can be read using `Metadata::from_bytes`.
+*/
+fn find_payload_section<'data, R: object::ReadRef<'data>>(exe: R) -> Option<&'data [u8]> {
+    let file = object::File::parse(exe).ok()?;
+    let section = file.section_by_name(SECTION_NAME)?;
+    let data = section.data().ok()?;
+    data.ends_with(MAGIC).then_some(data)
+}
+
+/**
+    Writes the executable at the given path to the output, with the given payload
+    embedded using the given mode, returning the number of bytes that were written.
+
+    Executables using a trailer are streamed to the output as they are read, so that
+    large executables are never held in memory in full, but adding a section needs
+    to rewrite the executable, and reads it in full instead.
+*/
+async fn write_patched_bin(
+    base_exe_path: &Path,
+    payload: &[u8],
+    embed_mode: EmbedMode,
+    output: &mut (impl AsyncWrite + Unpin),
+) -> Result<u64> {
+    let written = match embed_mode {
+        EmbedMode::Trailer => {
+            let base_exe = fs::File::open(base_exe_path).await?;
+            let copied = futures_lite::io::copy(base_exe, &mut *output).await?;
+            output.write_all(payload).await?;
+            copied + payload.len() as u64
+        }
+        EmbedMode::Section => {
+            let base_exe = fs::read(base_exe_path).await?;
+            let patched_bin = add_payload_section(&base_exe, payload)?;
+            output.write_all(&patched_bin).await?;
+            patched_bin.len() as u64
+        }
+    };
+    output.flush().await?;
+    Ok(written)
+}
+
+/**
+    Adds a section with the given payload to the given executable.
+
+    The section is not loaded into memory when the executable runs, and is
+    instead read from disk on startup, same as the trailer would have been.
+
+    FUTURE: Only ELF executables can currently be modified, Mach-O and PE
+    executables need a new segment to be inserted before their link edit
+    data, which is not something that the `object` crate can do for us.
+*/
+fn add_payload_section(exe: &[u8], payload: &[u8]) -> Result<Vec<u8>> {
+    match object::FileKind::parse(exe)? {
+        object::FileKind::Elf32 | object::FileKind::Elf64 => {}
+        kind => bail!(
+            "embedding in a section is currently only supported for ELF executables (linux targets), \
+            got {kind:?} - use --embed-mode trailer instead"
+        ),
+    }
+
+    let mut builder =
+        object::build::elf::Builder::read(exe).context("failed to read base executable")?;
+    if builder
+        .sections
+        .iter()
+        .any(|section| section.name.as_slice() == SECTION_NAME.as_bytes())
+    {
+        bail!("base executable already contains a '{SECTION_NAME}' section");
+    }
+
+    let section = builder.sections.add();
+    section.name = SECTION_NAME.as_bytes().into();
+    section.sh_type = object::elf::SHT_PROGBITS;
+    section.sh_addralign = 1;
+    section.data = object::build::elf::SectionData::Data(payload.into());
+
+    let mut patched_bin = Vec::new();
+    builder
+        .write(&mut patched_bin)
+        .context("failed to write payload section")?;
+    Ok(patched_bin)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn zip_with_comment(comment: &str) -> Vec<u8> {
+        let mut zip_data = Vec::new();
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut zip_data));
+        zip.start_file("init.luau", zip::write::FileOptions::<()>::default())
+            .unwrap();
+        zip.write_all(b"print('hello')").unwrap();
+        zip.set_comment(comment);
+        zip.finish().unwrap();
+        zip_data
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let meta = Metadata {
+            format_version: FORMAT_VERSION,
+            zip_data: zip_with_comment(""),
+            lune_version: Some("1.2.3".to_string()),
+            version_flag: false,
+            entry_name: "main=app,v2.luau".to_string(),
+            removed_globals: vec!["print".to_string(), "@lune/net".to_string()],
+            native_libs: vec![".lune-native/lib,v2=final.so".to_string()],
+            entries: BTreeMap::from([
+                ("cli".to_string(), "cli/main.luau".to_string()),
+                ("server".to_string(), "server,v2=final.luau".to_string()),
+            ]),
+            aliases: BTreeMap::from([("lib".to_string(), "packages/lib".to_string())]),
+        };
+        let bytes = meta.to_bytes();
+        assert!(bytes.ends_with(MAGIC));
+
+        let read = Metadata::from_bytes(&bytes).unwrap();
+        assert_eq!(read.zip_data, meta.zip_data);
+        assert_eq!(read.format_version, FORMAT_VERSION);
+        assert_eq!(read.lune_version, meta.lune_version);
+        assert_eq!(read.version_flag, meta.version_flag);
+        assert_eq!(read.entry_name, meta.entry_name);
+        assert_eq!(read.removed_globals, meta.removed_globals);
+        assert_eq!(read.native_libs, meta.native_libs);
+        assert_eq!(read.entries, meta.entries);
+        assert_eq!(read.aliases, meta.aliases);
+    }
+
+    #[test]
+    fn round_trips_aliases_with_separators() {
+        let aliases = BTreeMap::from([
+            ("lib,v2".to_string(), "packages/lib=v2".to_string()),
+            ("multi\nline".to_string(), "packages/a,b\nc".to_string()),
+        ]);
+        let meta = Metadata::create(
+            b"return nil",
+            &[(
+                "packages/lib=v2/init.luau".to_string(),
+                b"return 1".to_vec(),
+            )],
+            PatchOptions {
+                compression: CompressionMethod::Stored,
+                version_flag: true,
+                entry_name: DEFAULT_ENTRY_NAME.to_string(),
+                removed_globals: Vec::new(),
+                native_libs: Vec::new(),
+                entries: BTreeMap::new(),
+                aliases: aliases.clone(),
+            },
+        )
+        .unwrap();
+
+        let read = Metadata::from_bytes(meta.to_bytes()).unwrap();
+        assert_eq!(read.aliases, aliases);
+    }
+
+    #[test]
+    fn reads_first_format_version() {
+        let read_v1 = |zip_data: &[u8]| {
+            let mut bytes = zip_data.to_vec();
+            bytes.extend_from_slice(&(zip_data.len() as u64).to_be_bytes());
+            bytes.extend_from_slice(MAGIC);
+            Metadata::from_bytes(&bytes).unwrap()
+        };
+
+        let zip_data = zip_with_comment("");
+        let read = read_v1(&zip_data);
+        assert_eq!(read.zip_data, zip_data);
+        assert_eq!(read.format_version, 1);
+        assert_eq!(read.lune_version, None);
+        assert!(read.version_flag);
+        assert_eq!(read.entry_name, DEFAULT_ENTRY_NAME);
+
+        let read = read_v1(&zip_with_comment(
+            "lune-version=1.2.3\nversion-flag=false\nremoved-globals=print,@lune/net",
+        ));
+        assert_eq!(read.lune_version.as_deref(), Some("1.2.3"));
+        assert!(!read.version_flag);
+        assert_eq!(read.removed_globals, ["print", "@lune/net"]);
+    }
+
+    #[test]
+    fn rejects_newer_format_versions() {
+        let payload = Payload {
+            format_version: FORMAT_VERSION + 1,
+            checksum: 0,
+            zip_data: zip_with_comment(""),
+            metadata: PayloadMetadata::default(),
+        };
+        let mut bytes = postcard::to_stdvec(&payload).unwrap();
+        bytes.extend_from_slice(&(bytes.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(MAGIC);
+
+        let err = Metadata::from_bytes(&bytes).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "standalone binary built with newer Lune format v{}, this runtime supports v{}",
+                FORMAT_VERSION + 1,
+                FORMAT_VERSION
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_corrupted_payloads() {
+        let meta = Metadata {
+            zip_data: zip_with_comment(""),
+            format_version: FORMAT_VERSION,
+            lune_version: None,
+            version_flag: true,
+            entry_name: DEFAULT_ENTRY_NAME.to_string(),
+            removed_globals: Vec::new(),
+            native_libs: Vec::new(),
+            entries: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+        };
+
+        // Flip a byte in the middle of the archive
+        let mut bytes = meta.to_bytes();
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0xFF;
+        let err = Metadata::from_bytes(&bytes).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "standalone payload corrupted (checksum mismatch)"
+        );
+
+        // Drop the first half of the payload, keeping the trailer
+        let bytes = meta.to_bytes();
+        let err = Metadata::from_bytes(&bytes[middle..]).unwrap_err();
+        assert_eq!(err.to_string(), "standalone payload corrupted (truncated)");
+    }
+
+    #[test]
+    fn rejects_conflicting_archive_names() {
+        let check = |names: &[&str]| check_archive_names(DEFAULT_ENTRY_NAME, names.iter().copied());
+        assert!(check(&["lib/util.luau", "lib/init.luau", "assets/logo.png"]).is_ok());
+
+        let err = check(&["lib\\util.luau", "lib/util.luau", "./init.luau"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "found embedded files with conflicting names:\n    \
+            'lib\\util.luau' and 'lib/util.luau' would both be stored as 'lib/util.luau'\n    \
+            'init.luau' and './init.luau' would both be stored as 'init.luau'"
+        );
+        assert!(check(&["../escape.luau"]).is_err());
+
+        // The input file can be stored under another name, freeing up init.luau
+        assert!(check_archive_names("main.luau", ["init.luau"]).is_ok());
+        assert!(check_archive_names("main.luau", ["./main.luau"]).is_err());
+        assert!(check_archive_names("../main.luau", std::iter::empty()).is_err());
+        assert!(check_archive_names("main=app,v2.luau", ["init.luau"]).is_ok());
+    }
+
+    #[test]
+    fn streams_base_executables_with_trailers() {
+        let dir = env::temp_dir().join(format!("lune-stream-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // Larger than any buffer used for copying, to stream it in several chunks
+        let base_exe = b"not really an executable".repeat(10_000);
+        let base_exe_path = dir.join("base");
+        std::fs::write(&base_exe_path, &base_exe).unwrap();
+
+        let meta = Metadata {
+            zip_data: zip_with_comment(""),
+            format_version: FORMAT_VERSION,
+            lune_version: Some("1.2.3".to_string()),
+            version_flag: true,
+            entry_name: DEFAULT_ENTRY_NAME.to_string(),
+            removed_globals: Vec::new(),
+            native_libs: Vec::new(),
+            entries: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+        };
+        let payload = meta.to_bytes();
+
+        let mut output = Vec::new();
+        let written = futures_lite::future::block_on(write_patched_bin(
+            &base_exe_path,
+            &payload,
+            EmbedMode::Trailer,
+            &mut output,
+        ))
+        .unwrap();
+
+        // The result is the same as appending the payload in memory
+        let mut expected = base_exe;
+        expected.extend_from_slice(&payload);
+        assert_eq!(output, expected);
+        assert_eq!(written, expected.len() as u64);
+        let read = Metadata::from_exe_bytes(&output).unwrap();
+        assert_eq!(read.lune_version.as_deref(), Some("1.2.3"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn builds_identical_binaries() {
+        let dir = env::temp_dir().join(format!("lune-reproducible-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let base_exe_path = dir.join("base");
+        std::fs::write(&base_exe_path, b"not really an executable").unwrap();
+
+        let build = || {
+            let bytecode = Metadata::compile_script("print(require('./lib/util'))", false).unwrap();
+            let meta = Metadata::create(
+                &bytecode,
+                &[
+                    ("lib/util.luau".to_string(), b"return 1".to_vec()),
+                    ("assets/logo.png".to_string(), vec![0x89; 64]),
+                ],
+                PatchOptions {
+                    compression: CompressionMethod::Deflated,
+                    version_flag: true,
+                    entry_name: DEFAULT_ENTRY_NAME.to_string(),
+                    removed_globals: Vec::new(),
+                    native_libs: Vec::new(),
+                    entries: BTreeMap::new(),
+                    aliases: BTreeMap::new(),
+                },
+            )
+            .unwrap();
+            let mut output = Vec::new();
+            futures_lite::future::block_on(meta.patch_bin(
+                &base_exe_path,
+                EmbedMode::Trailer,
+                &mut output,
+            ))
+            .unwrap();
+            output
+        };
+        assert_eq!(build(), build());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reads_payloads_from_executables() {
+        let dir = env::temp_dir().join(format!("lune-metadata-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let meta = Metadata {
+            zip_data: zip_with_comment(""),
+            format_version: FORMAT_VERSION,
+            lune_version: None,
+            version_flag: true,
+            entry_name: DEFAULT_ENTRY_NAME.to_string(),
+            removed_globals: Vec::new(),
+            native_libs: Vec::new(),
+            entries: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+        };
+        let mut patched = b"not really an executable".to_vec();
+        patched.extend_from_slice(&meta.to_bytes());
+
+        assert!(Metadata::is_standalone_exe(&patched));
+        assert!(!Metadata::is_standalone_exe(b"not really an executable"));
+
+        let patched_path = dir.join("patched");
+        std::fs::write(&patched_path, &patched).unwrap();
+        assert_eq!(read_exe_payload(&patched_path).unwrap(), Some(patched));
+
+        let plain_path = dir.join("plain");
+        std::fs::write(&plain_path, b"not really an executable").unwrap();
+        assert_eq!(read_exe_payload(&plain_path).unwrap(), None);
+
+        let tiny_path = dir.join("tiny");
+        std::fs::write(&tiny_path, b"PK").unwrap();
+        assert_eq!(read_exe_payload(&tiny_path).unwrap(), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reads_payloads_from_elf_sections() {
+        // A minimal ELF executable, with only the section names that a new section needs
+        let mut builder = object::build::elf::Builder::new(object::Endianness::Little, true);
+        builder.header.e_type = object::elf::ET_EXEC;
+        builder.header.e_machine = object::elf::EM_X86_64;
+        let names = builder.sections.add();
+        names.name = b".shstrtab"[..].into();
+        names.sh_type = object::elf::SHT_STRTAB;
+        names.data = object::build::elf::SectionData::SectionString;
+        let mut base_exe = Vec::new();
+        builder.write(&mut base_exe).unwrap();
+
+        let meta = Metadata {
+            zip_data: zip_with_comment(""),
+            format_version: FORMAT_VERSION,
+            lune_version: Some("0.10.4".to_string()),
+            version_flag: true,
+            entry_name: DEFAULT_ENTRY_NAME.to_string(),
+            removed_globals: Vec::new(),
+            native_libs: Vec::new(),
+            entries: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+        };
+        let payload = meta.to_bytes();
+        let patched = add_payload_section(&base_exe, &payload).unwrap();
+
+        assert!(!patched.ends_with(MAGIC));
+        assert_eq!(
+            find_payload_section(patched.as_slice()),
+            Some(payload.as_slice())
+        );
+        assert!(Metadata::is_standalone_exe(&patched));
+        let read = Metadata::from_exe_bytes(&patched).unwrap();
+        assert_eq!(read.zip_data, meta.zip_data);
+        assert_eq!(read.lune_version.as_deref(), Some("0.10.4"));
+
+        let dir = env::temp_dir().join(format!("lune-section-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let patched_path = dir.join("patched");
+        std::fs::write(&patched_path, &patched).unwrap();
+        let read_payload = read_exe_payload(&patched_path);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(read_payload.unwrap(), Some(payload.clone()));
+
+        assert!(add_payload_section(&patched, &payload).is_err());
+        assert!(add_payload_section(b"not really an executable", &payload).is_err());
+    }
+}
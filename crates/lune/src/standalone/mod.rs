@@ -3,10 +3,22 @@ use std::{env, process::ExitCode};
 use anyhow::Result;
 use lune::Runtime;
 
+pub(crate) mod hot_reload;
 pub(crate) mod metadata;
+pub(crate) mod native;
 pub(crate) mod tracer;
 
-use self::metadata::Metadata;
+/**
+    A reserved argument that, when given as the first argument to a standalone
+    binary, prints the version of Lune that the binary was built with and exits.
+
+    Scripts that need to accept this argument themselves can be built
+    using `lune build --version-flag false` to disable this behavior.
+*/
+const VERSION_FLAG: &str = "--lune-version";
+
+use self::metadata::{BUILD_CONFIG_FILE_NAME, Metadata};
+use self::native::{NATIVE_DIR_ENV_VAR, NativeLibsDir};
 
 /**
     Returns whether or not the currently executing Lune binary
@@ -25,30 +37,143 @@ pub async fn check() -> Option<Vec<u8>> {
     Discovers, loads and executes the bytecode contained in a standalone binary.
 */
 use crate::fs::ZipFileSystem;
-use lune_utils::fs::FileSystem;
+use lune_utils::{
+    fs::{FileSystem, OverlayFileSystem, StdFileSystem, TracingFileSystem},
+    path::get_current_dir,
+    process::ProcessProgramName,
+};
 use std::sync::Arc;
 
+/**
+    Opens the archive embedded in a standalone binary, with the aliases it was built with.
+
+    The archive is rooted at the current working directory, since that is what the
+    require resolver makes module paths absolute with, so that relative requires
+    between embedded files resolve from the directory of the entry point, which
+    is the archive root - the same as they would for `lune run` on disk.
+*/
+fn open_embedded_fs(
+    zip_data: Vec<u8>,
+    aliases: impl IntoIterator<Item = (String, String)>,
+) -> Result<ZipFileSystem> {
+    Ok(ZipFileSystem::new(zip_data)?
+        .with_aliases(aliases)
+        .with_root(get_current_dir().to_path_buf()))
+}
+
 /**
     Discovers, loads and executes the bytecode contained in a standalone binary.
+
+    The binary exits with the code given to `process.exit`, which stops the script
+    right away and cancels any pending tasks, even when called from a spawned thread.
+    Scripts that never call it exit with `0`, or `1` if any error was thrown.
 */
 pub async fn run(patched_bin: impl AsRef<[u8]>) -> Result<ExitCode> {
-    // The first argument is the path to the current executable
-    let args = env::args().skip(1).collect::<Vec<_>>();
-    let meta = Metadata::from_bytes(patched_bin).expect("must be a standalone binary");
+    // The first argument is the path to the current executable, which is given
+    // to scripts separately, so that they can print usage with the invoked name
+    let program_name = ProcessProgramName::current();
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+    let meta = Metadata::from_bytes(patched_bin)?;
+
+    if meta.version_flag && args.first().is_some_and(|arg| arg == VERSION_FLAG) {
+        let version = meta.lune_version.as_deref().unwrap_or("unknown");
+        println!("Lune v{version}");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Binaries with named entry points run the one given as the first argument,
+    // and only fall back to the main entry point without arguments - the name of
+    // the entry point is removed, so scripts only receive the arguments after it
+    let entry_file = if meta.entries.is_empty() || args.is_empty() {
+        meta.entry_name.clone()
+    } else {
+        let name = args.remove(0);
+        let Some(file) = meta.entries.get(&name) else {
+            eprintln!("Unknown entry point '{name}', available entry points are:");
+            for available in meta.entries.keys() {
+                eprintln!("    {available}");
+            }
+            return Ok(ExitCode::FAILURE);
+        };
+        file.clone()
+    };
+
+    // Initialize filesystem from embedded ZIP data, recording all
+    // accesses to it if requested, to help with profiling startup
+    let zip_fs = open_embedded_fs(meta.zip_data, meta.aliases)?;
+    let embedded_names = zip_fs.list_all();
+    let tracing_fs = env::var_os("LUNE_TRACE_FS").map(|_| TracingFileSystem::new(zip_fs.clone()));
+    let zip_fs: Arc<dyn FileSystem> = match &tracing_fs {
+        Some(tracing_fs) => Arc::new(tracing_fs.clone()),
+        None => Arc::new(zip_fs),
+    };
+
+    // Read the build config, which is only embedded if one was given when building
+    let build_config = zip_fs
+        .read_to_string(std::path::Path::new(BUILD_CONFIG_FILE_NAME))
+        .ok();
 
-    // Initialize filesystem from embedded ZIP data
-    let zip_fs = Arc::new(ZipFileSystem::new(meta.zip_data)?);
+    // Extract any native libraries, which are removed again once this guard is dropped
+    let native_dir = NativeLibsDir::extract(&*zip_fs, &meta.native_libs)?;
+    let mut env_vars = env::vars_os().collect::<Vec<_>>();
+    if let Some(native_dir) = &native_dir {
+        env_vars.push((NATIVE_DIR_ENV_VAR.into(), native_dir.path().into()));
+    }
+
+    // Embedded files are layered on top of the real filesystem, so that scripts can still
+    // read files given to them by path, without disk files ever shadowing embedded modules
+    let runtime_fs: Arc<dyn FileSystem> = Arc::new(OverlayFileSystem::new(
+        Arc::clone(&zip_fs),
+        Arc::new(StdFileSystem),
+    ));
+
+    let new_runtime = || -> Result<Runtime> {
+        let mut rt = Runtime::new()?
+            .with_args(args.clone())
+            .with_program_name(program_name.name())
+            .with_env(env_vars.clone())
+            .with_removed_globals(meta.removed_globals.clone())
+            .with_fs(Arc::clone(&runtime_fs))?
+            .with_global("embedded", |lua| {
+                // embedded.list() returns the names of all embedded files, sorted
+                let names = embedded_names.clone();
+                let embedded = lua.create_table()?;
+                embedded.set("list", lua.create_function(move |_, ()| Ok(names.clone()))?)?;
+                Ok(mlua::Value::Table(embedded))
+            })?;
+        if let Some(build_config) = &build_config {
+            rt = rt.with_build_config(build_config.clone());
+        }
+        Ok(rt)
+    };
 
-    // Read the main entry point (init.luau)
-    let main_chunk = zip_fs.read(std::path::Path::new("init.luau"))?;
+    // Development builds may run a source file from disk instead of the embedded bytecode
+    if let Some(source) = hot_reload::source_path() {
+        return hot_reload::run(source, new_runtime).await;
+    }
 
-    let mut rt = Runtime::new()?.with_args(args).with_fs(zip_fs)?;
+    // Read the selected entry point, the main one unless a named one was given
+    let main_chunk = zip_fs.read(std::path::Path::new(&entry_file))?;
+    let mut rt = new_runtime()?;
 
-    // Use a path that indicates we are at the root of the virtual filesystem
-    let chunk_name = "@init.luau";
+    // Use a path relative to the root of the virtual filesystem
+    let chunk_name = format!("@{entry_file}");
 
     let result = rt.run_custom(chunk_name, main_chunk).await;
 
+    if let Some(tracing_fs) = tracing_fs {
+        eprintln!("Filesystem accesses:");
+        for summary in tracing_fs.summary() {
+            eprintln!(
+                "{:>10.3?} {:>6} calls {:>10} bytes  {}",
+                summary.duration,
+                summary.calls,
+                summary.bytes,
+                summary.path.display()
+            );
+        }
+    }
+
     Ok(match result {
         Err(err) => {
             eprintln!("{err}");
@@ -57,3 +182,32 @@ pub async fn run(patched_bin: impl AsRef<[u8]>) -> Result<ExitCode> {
         Ok(values) => ExitCode::from(values.status()),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use zip::write::SimpleFileOptions;
+
+    use crate::fs::ZipArchiveBuilder;
+
+    use super::*;
+
+    #[test]
+    fn resolves_relative_requires_from_the_entry_directory() {
+        let mut builder = ZipArchiveBuilder::new();
+        for (name, contents) in [
+            ("init.luau", "assert(require('./lib/util') == 'shared')"),
+            ("lib/util.luau", "return require('../shared')"),
+            ("shared.luau", "return 'shared'"),
+        ] {
+            builder
+                .add_file(name, contents.as_bytes(), SimpleFileOptions::default())
+                .unwrap();
+        }
+        let zip_fs = open_embedded_fs(builder.finish().unwrap(), []).unwrap();
+        let main_chunk = zip_fs.read(std::path::Path::new("init.luau")).unwrap();
+
+        let mut rt = Runtime::new().unwrap().with_fs(Arc::new(zip_fs)).unwrap();
+        let result = futures_lite::future::block_on(rt.run_custom("@init.luau", main_chunk));
+        assert!(result.unwrap().success());
+    }
+}
@@ -0,0 +1,85 @@
+/*!
+    Extraction of native libraries embedded in standalone binaries.
+
+    Native libraries can not be loaded from memory, so any libraries embedded
+    using `lune build --native` are written to a temporary directory before
+    the script runs, and removed again once the binary exits.
+*/
+
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use anyhow::{Context, Result, bail};
+use lune_utils::fs::FileSystem;
+
+/**
+    The environment variable given to scripts, containing the
+    directory that embedded native libraries were extracted to.
+*/
+pub const NATIVE_DIR_ENV_VAR: &str = "LUNE_NATIVE_DIR";
+
+/**
+    A temporary directory with extracted native libraries, removed when dropped.
+*/
+#[derive(Debug)]
+pub struct NativeLibsDir {
+    path: PathBuf,
+}
+
+impl NativeLibsDir {
+    /**
+        Extracts the given native libraries from the embedded archive,
+        returning `None` if the binary does not have any native libraries.
+
+        Libraries are extracted by file name, and two libraries with the
+        same file name are an error instead of overwriting each other.
+    */
+    pub fn extract(filesystem: &dyn FileSystem, names: &[String]) -> Result<Option<Self>> {
+        if names.is_empty() {
+            return Ok(None);
+        }
+
+        let dir = Self {
+            path: env::temp_dir().join(format!("lune-native-{}", process::id())),
+        };
+        fs::create_dir_all(&dir.path).with_context(|| {
+            format!(
+                "failed to create native library directory '{}'",
+                dir.path.display()
+            )
+        })?;
+
+        let mut extracted = HashMap::new();
+        for name in names {
+            let Some(file_name) = Path::new(name).file_name() else {
+                continue;
+            };
+            if let Some(existing) = extracted.insert(file_name, name) {
+                bail!(
+                    "embedded native libraries '{existing}' and '{name}' have the same file name"
+                );
+            }
+            let contents = filesystem
+                .read(Path::new(name))
+                .with_context(|| format!("failed to read embedded native library '{name}'"))?;
+            fs::write(dir.path.join(file_name), contents)
+                .with_context(|| format!("failed to extract native library '{name}'"))?;
+        }
+
+        Ok(Some(dir))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for NativeLibsDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -13,6 +13,10 @@ use crate::Runtime;
 const ARGS: &[&str] = &["Foo", "Bar"];
 
 fn run_test(path: &str) -> Result<ExitCode> {
+    run_test_with(path, |rt| rt)
+}
+
+fn run_test_with(path: &str, configure: impl FnOnce(Runtime) -> Runtime) -> Result<ExitCode> {
     async_io::block_on(async {
         // We need to change the current directory to the workspace root since
         // we are in a sub-crate and tests would run relative to the sub-crate
@@ -26,7 +30,7 @@ fn run_test(path: &str) -> Result<ExitCode> {
         set_colors_enabled_stderr(false);
 
         // The rest of the test logic can continue as normal
-        let mut rt = Runtime::new()?.with_args(ARGS).with_jit(true);
+        let mut rt = configure(Runtime::new()?.with_args(ARGS).with_jit(true));
 
         let script_path = workspace_dir.join("tests").join(format!("{path}.luau"));
         let script_values = rt.run_file(script_path).await?;
@@ -63,6 +67,7 @@ create_tests! {
     require_async_sequential: "require/tests/async_sequential",
     require_builtins: "require/tests/builtins",
     require_children: "require/tests/children",
+    require_circular: "require/tests/circular",
     require_init: "require/tests/init_files",
     require_invalid: "require/tests/invalid",
     require_multi_ext: "require/tests/multi_ext",
@@ -101,6 +106,7 @@ create_tests! {
 create_tests! {
     fs_files: "fs/files",
     fs_copy: "fs/copy",
+    fs_encoding: "fs/encoding",
     fs_dirs: "fs/dirs",
     fs_metadata: "fs/metadata",
     fs_move: "fs/move",
@@ -144,6 +150,7 @@ create_tests! {
 #[cfg(feature = "std-process")]
 create_tests! {
     process_args: "process/args",
+    process_build_config: "process/build_config",
     process_cwd: "process/cwd",
     process_env: "process/env",
     process_exit: "process/exit",
@@ -268,3 +275,24 @@ create_tests! {
     task_spawn: "task/spawn",
     task_wait: "task/wait",
 }
+
+#[cfg(feature = "std-process")]
+#[test]
+fn global_removed() -> Result<ExitCode> {
+    run_test_with("globals/removed", |rt| {
+        rt.with_removed_globals(["print", "string.rep", "@lune/process"])
+    })
+}
+
+#[cfg(feature = "std-process")]
+#[test]
+fn process_program_name() -> Result<ExitCode> {
+    run_test_with("process/program_name", |rt| rt.with_program_name("my-tool"))
+}
+
+#[cfg(all(feature = "std-process", feature = "std-task"))]
+#[test]
+fn process_exit_code() -> Result<()> {
+    assert_eq!(run_test("process/exit_code")?, ExitCode::from(2));
+    Ok(())
+}